@@ -0,0 +1,27 @@
+use std::env;
+use std::error::Error;
+use std::fs::File;
+use std::io::BufWriter;
+
+use hangouts_rs::raw::generate::{generate, GeneratorOptions};
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args().skip(1);
+    let path = args.next().unwrap();
+    let mut options = GeneratorOptions::default();
+    if let Some(conversations) = args.next() {
+        options.conversations = conversations.parse()?;
+    }
+    if let Some(events) = args.next() {
+        options.events = events.parse()?;
+    }
+
+    println!("Generating...");
+    let hangouts = generate(&options);
+
+    println!("Writing...");
+    let writer = BufWriter::new(File::create(path)?);
+    serde_json::to_writer(writer, &hangouts)?;
+
+    Ok(())
+}
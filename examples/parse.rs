@@ -24,7 +24,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     hangouts
         .conversations
         .iter_mut()
-        .for_each(|conv| conv.events.sort_by(|a, b| a.timestamp.cmp(&b.timestamp)));
+        .for_each(|conv| conv.events.sort_by_key(|event| event.timestamp));
 
     let last = hangouts.conversations.last().expect("No conversations");
 
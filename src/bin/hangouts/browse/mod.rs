@@ -0,0 +1,446 @@
+//! Interactive terminal UI for browsing conversations.
+
+#[cfg(test)]
+mod tests;
+mod view;
+
+use self::view::Transcript;
+use crate::transcript::{local_time, message_text, system_text};
+use crate::Error;
+
+use std::cmp::Reverse;
+use std::io;
+use std::process::{Command, Stdio};
+
+use chrono::NaiveDate;
+use hangouts_rs::{ChatSegment, Conversation, Event, Hangouts};
+use ratatui::crossterm::event::{self, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+
+const HELP: &str =
+    "q quit  tab switch pane  ↑↓ move  / search  n/N next/previous  d jump to date  o open links";
+
+/// Browse the conversations of `hangouts` until the user quits.
+pub fn run(hangouts: &Hangouts, utc: bool) -> Result<(), Error> {
+    let mut app = App::new(hangouts, utc);
+    let mut terminal = ratatui::try_init()?;
+    let result = app.run(&mut terminal);
+    ratatui::try_restore()?;
+    result
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    List,
+    Messages,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PromptKind {
+    Search,
+    Date,
+}
+
+struct App<'a> {
+    /// Conversations, most recent first.
+    conversations: Vec<&'a Conversation>,
+    /// Descriptions of the conversations in the list.
+    summaries: Vec<String>,
+    list: ListState,
+    focus: Focus,
+    utc: bool,
+
+    /// Index of the highlighted event in the selected conversation.
+    cursor: usize,
+    /// First line shown in the message view.
+    scroll: usize,
+    /// Number of events moved by page keys, depending on the view height.
+    page: usize,
+    /// Transcript of the selected conversation with the index and width it was rendered at.
+    transcript: Option<(usize, u16, Transcript)>,
+
+    prompt: Option<(PromptKind, String)>,
+    /// Last searched text.
+    search: Option<String>,
+    status: Option<String>,
+}
+
+impl<'a> App<'a> {
+    fn new(hangouts: &'a Hangouts, utc: bool) -> Self {
+        let mut conversations: Vec<_> = hangouts.conversations.iter().collect();
+        conversations.sort_by_key(|c| Reverse(c.sort_timestamp));
+        let summaries = conversations
+            .iter()
+            .map(|c| {
+                let messages = c.events.iter().filter(|e| e.data.is_chat_message()).count();
+                format!(
+                    "{}  {} messages",
+                    local_time(c.sort_timestamp, utc).format("%Y-%m-%d"),
+                    messages
+                )
+            })
+            .collect();
+
+        let mut app = Self {
+            conversations,
+            summaries,
+            list: ListState::default(),
+            focus: Focus::List,
+            utc,
+            cursor: 0,
+            scroll: 0,
+            page: 10,
+            transcript: None,
+            prompt: None,
+            search: None,
+            status: None,
+        };
+        app.select(0);
+        app
+    }
+
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Error> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let event::Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press && !self.handle(key) {
+                    return Ok(());
+                }
+            }
+        }
+    }
+
+    fn conversation(&self) -> Option<&'a Conversation> {
+        self.list
+            .selected()
+            .and_then(|i| self.conversations.get(i).copied())
+    }
+
+    fn events(&self) -> &'a [Event] {
+        self.conversation().map(|c| &c.events[..]).unwrap_or(&[])
+    }
+
+    /// Select a conversation, showing its most recent events.
+    fn select(&mut self, index: usize) {
+        if self.conversations.is_empty() {
+            return;
+        }
+        let index = index.min(self.conversations.len() - 1);
+        self.list.select(Some(index));
+        self.cursor = self.events().len().saturating_sub(1);
+        self.scroll = 0;
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        let last = self.events().len().saturating_sub(1) as isize;
+        self.cursor = (self.cursor as isize).saturating_add(delta).clamp(0, last) as usize;
+    }
+
+    /// Handle a key press. Returns `false` to quit.
+    fn handle(&mut self, key: KeyEvent) -> bool {
+        if let Some((kind, mut input)) = self.prompt.take() {
+            match key.code {
+                KeyCode::Enter => self.submit(kind, input),
+                KeyCode::Esc => {}
+                KeyCode::Backspace => {
+                    input.pop();
+                    self.prompt = Some((kind, input));
+                }
+                KeyCode::Char(c) => {
+                    input.push(c);
+                    self.prompt = Some((kind, input));
+                }
+                _ => self.prompt = Some((kind, input)),
+            }
+            return true;
+        }
+
+        self.status = None;
+        let selected = self.list.selected().unwrap_or(0);
+        match (self.focus, key.code) {
+            (_, KeyCode::Char('q')) => return false,
+            (_, KeyCode::Char('c')) if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                return false
+            }
+            (_, KeyCode::Tab) => {
+                self.focus = match self.focus {
+                    Focus::List => Focus::Messages,
+                    Focus::Messages => Focus::List,
+                }
+            }
+            (_, KeyCode::Char('/')) => self.prompt = Some((PromptKind::Search, String::new())),
+            (_, KeyCode::Char('d')) => self.prompt = Some((PromptKind::Date, String::new())),
+            (_, KeyCode::Char('n')) => self.find_next(true),
+            (_, KeyCode::Char('N')) => self.find_next(false),
+            (_, KeyCode::Char('o')) => self.open_links(),
+
+            (Focus::List, KeyCode::Up | KeyCode::Char('k')) => {
+                self.select(selected.saturating_sub(1))
+            }
+            (Focus::List, KeyCode::Down | KeyCode::Char('j')) => self.select(selected + 1),
+            (Focus::List, KeyCode::PageUp) => self.select(selected.saturating_sub(self.page)),
+            (Focus::List, KeyCode::PageDown) => self.select(selected + self.page),
+            (Focus::List, KeyCode::Home | KeyCode::Char('g')) => self.select(0),
+            (Focus::List, KeyCode::End | KeyCode::Char('G')) => self.select(usize::MAX),
+            (Focus::List, KeyCode::Enter | KeyCode::Right | KeyCode::Char('l')) => {
+                self.focus = Focus::Messages
+            }
+
+            (Focus::Messages, KeyCode::Up | KeyCode::Char('k')) => self.move_cursor(-1),
+            (Focus::Messages, KeyCode::Down | KeyCode::Char('j')) => self.move_cursor(1),
+            (Focus::Messages, KeyCode::PageUp) => self.move_cursor(-(self.page as isize)),
+            (Focus::Messages, KeyCode::PageDown) => self.move_cursor(self.page as isize),
+            (Focus::Messages, KeyCode::Home | KeyCode::Char('g')) => self.cursor = 0,
+            (Focus::Messages, KeyCode::End | KeyCode::Char('G')) => self.move_cursor(isize::MAX),
+            (Focus::Messages, KeyCode::Esc | KeyCode::Left | KeyCode::Char('h')) => {
+                self.focus = Focus::List
+            }
+            _ => {}
+        }
+        true
+    }
+
+    fn submit(&mut self, kind: PromptKind, input: String) {
+        match kind {
+            PromptKind::Search if input.is_empty() => {}
+            PromptKind::Search => {
+                self.search = Some(input.to_lowercase());
+                self.find_next(true);
+            }
+            PromptKind::Date => match NaiveDate::parse_from_str(input.trim(), "%Y-%m-%d") {
+                Ok(date) => self.jump_to_date(date),
+                Err(_) => self.status = Some(format!("Invalid date {:?}, use YYYY-MM-DD", input)),
+            },
+        }
+    }
+
+    /// Move the cursor to the next or previous event containing the searched text,
+    /// wrapping around the conversation.
+    fn find_next(&mut self, forward: bool) {
+        let query = match &self.search {
+            Some(query) => query.clone(),
+            None => return,
+        };
+        let (conversation, events) = match self.conversation() {
+            Some(conversation) if !conversation.events.is_empty() => {
+                (conversation, &conversation.events)
+            }
+            _ => return,
+        };
+
+        let len = events.len();
+        let found = (1..=len)
+            .map(|step| {
+                if forward {
+                    (self.cursor + step) % len
+                } else {
+                    (self.cursor + len - step % len) % len
+                }
+            })
+            .find(|&i| {
+                let event = &events[i];
+                message_text(event)
+                    .or_else(|| system_text(conversation, event))
+                    .is_some_and(|text| text.to_lowercase().contains(&query))
+            });
+
+        match found {
+            Some(i) => {
+                if (forward && i <= self.cursor) || (!forward && i >= self.cursor) {
+                    self.status = Some("Search wrapped".to_owned());
+                }
+                self.cursor = i;
+                self.focus = Focus::Messages;
+            }
+            None => self.status = Some(format!("No match for {:?}", query)),
+        }
+    }
+
+    /// Move the cursor to the first event on or after `date`, or the last event.
+    fn jump_to_date(&mut self, date: NaiveDate) {
+        let utc = self.utc;
+        let events = self.events();
+        let index = events
+            .iter()
+            .position(|event| local_time(event.timestamp, utc).date_naive() >= date)
+            .unwrap_or_else(|| events.len().saturating_sub(1));
+        self.cursor = index;
+        self.focus = Focus::Messages;
+    }
+
+    /// Open the web links and attachments of the highlighted event in the default browser.
+    fn open_links(&mut self) {
+        let message = match self
+            .events()
+            .get(self.cursor)
+            .and_then(|event| event.data.as_chat_message())
+        {
+            Some(message) => message,
+            None => return,
+        };
+
+        let urls: Vec<&str> = message
+            .contents
+            .iter()
+            .filter_map(|segment| match segment {
+                ChatSegment::Link { target, .. } => Some(target.as_str()),
+                _ => None,
+            })
+            .chain(message.attachments.iter().filter_map(|a| a.item.url()))
+            .filter(|url| is_web_url(url))
+            .collect();
+        if urls.is_empty() {
+            self.status = Some("No web links in this message".to_owned());
+            return;
+        }
+
+        let failed = urls.iter().filter(|url| open(url).is_err()).count();
+        self.status = Some(if failed == 0 {
+            format!("Opened {} link(s)", urls.len())
+        } else {
+            format!("Failed to open {} of {} link(s)", failed, urls.len())
+        });
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        let [list_area, view_area] =
+            Layout::horizontal([Constraint::Percentage(30), Constraint::Percentage(70)])
+                .areas(main);
+
+        self.draw_list(frame, list_area);
+        self.draw_messages(frame, view_area);
+
+        let line = match &self.prompt {
+            Some((PromptKind::Search, input)) => Line::from(format!("/{}", input)),
+            Some((PromptKind::Date, input)) => Line::from(format!("Jump to date: {}", input)),
+            None => match &self.status {
+                Some(status) => Line::from(status.as_str()).yellow(),
+                None => Line::from(HELP).dark_gray(),
+            },
+        };
+        frame.render_widget(line, status);
+    }
+
+    fn draw_list(&mut self, frame: &mut Frame, area: Rect) {
+        let items: Vec<_> = self
+            .conversations
+            .iter()
+            .zip(&self.summaries)
+            .map(|(conversation, summary)| {
+                ListItem::new(vec![
+                    Line::from(conversation.display_name()).bold(),
+                    Line::from(summary.as_str()).dark_gray(),
+                ])
+            })
+            .collect();
+        let list = List::new(items)
+            .block(block("Conversations", self.focus == Focus::List))
+            .highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(list, area, &mut self.list);
+    }
+
+    fn draw_messages(&mut self, frame: &mut Frame, area: Rect) {
+        let conversation = match self.list.selected() {
+            Some(index) => (index, self.conversations[index]),
+            None => {
+                frame.render_widget(block("Messages", false), area);
+                return;
+            }
+        };
+        let block = block(
+            &conversation.1.display_name(),
+            self.focus == Focus::Messages,
+        );
+        let inner = block.inner(area);
+        let height = inner.height as usize;
+        self.page = (height / 2).max(1);
+
+        let stale = !matches!(&self.transcript, Some((index, width, _))
+            if *index == conversation.0 && *width == inner.width);
+        if stale {
+            let transcript = Transcript::new(conversation.1, inner.width as usize, self.utc);
+            self.transcript = Some((conversation.0, inner.width, transcript));
+        }
+        let transcript = match &self.transcript {
+            Some((_, _, transcript)) => transcript,
+            None => return,
+        };
+
+        // Scroll the least needed to show the whole highlighted event, or its start.
+        let current = transcript.events.get(self.cursor).cloned().unwrap_or(0..0);
+        if current.end > self.scroll + height {
+            self.scroll = current.end.saturating_sub(height);
+        }
+        if current.start < self.scroll || current.len() > height {
+            // Show the date separator above the first event of a day too.
+            let previous_end = match self.cursor {
+                0 => 0,
+                cursor => transcript.events[cursor - 1].end,
+            };
+            self.scroll = previous_end.min(current.start);
+        }
+
+        let end = (self.scroll + height).min(transcript.lines.len());
+        let lines: Vec<_> = (self.scroll..end)
+            .map(|i| {
+                let line = transcript.lines[i].clone();
+                if current.contains(&i) {
+                    line.patch_style(Style::new().bg(Color::Indexed(236)))
+                } else {
+                    line
+                }
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+fn block(title: &str, focused: bool) -> Block<'static> {
+    let block = Block::bordered().title(format!(" {} ", title));
+    if focused {
+        block.border_style(Style::new().cyan())
+    } else {
+        block.border_style(Style::new().dark_gray())
+    }
+}
+
+/// Returns `true` if `url` is an `http` or `https` URL, the only links handed to the
+/// platform.
+fn is_web_url(url: &str) -> bool {
+    let scheme = url.split_once("://").map_or("", |(scheme, _)| scheme);
+    (scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https"))
+        && !url.chars().any(char::is_control)
+}
+
+/// Open the web `url` with the default browser of the platform.
+///
+/// Fails with [`io::ErrorKind::InvalidInput`] for anything but `http` and `https` URLs, so
+/// that links cannot start programs or pass options to the opener.
+fn open(url: &str) -> io::Result<()> {
+    if !is_web_url(url) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "not a web URL"));
+    }
+    let mut command = if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else if cfg!(windows) {
+        // Unlike `cmd /C start`, this does not interpret `&` and other shell characters.
+        let mut command = Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        Command::new("xdg-open")
+    };
+    command
+        .arg(url)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map(drop)
+}
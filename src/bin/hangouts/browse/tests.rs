@@ -0,0 +1,198 @@
+//! Tests of the terminal UI, drawn on a test backend.
+
+use super::view::Transcript;
+use super::*;
+
+use chrono::Duration;
+use hangouts_rs::builder::{default_start, ChatMessageBuilder, ConversationBuilder, EventBuilder};
+use ratatui::backend::TestBackend;
+use ratatui::style::Modifier;
+use ratatui::Terminal;
+
+fn hangouts() -> Hangouts {
+    let next_day = default_start() + Duration::days(1);
+    Hangouts {
+        conversations: vec![
+            ConversationBuilder::new("old")
+                .name("Old friends")
+                .participant("1", "Alice")
+                .participant("2", "Bob")
+                .message("1", "Hello there")
+                .message("2", "General Kenobi")
+                .build(),
+            ConversationBuilder::new("new")
+                .participant("1", "Alice")
+                .participant("3", "Carol")
+                .message("1", "Lunch today?")
+                .event(EventBuilder::rename("3", "", "Lunch club"))
+                .event(
+                    EventBuilder::message(
+                        "3",
+                        ChatMessageBuilder::new()
+                            .bold("Sure")
+                            .text(", see ")
+                            .link("the menu", "https://example.com/menu?a=1&b=2")
+                            .link("or mail me", "mailto:carol@example.com"),
+                    )
+                    .timestamp(next_day),
+                )
+                .event(
+                    EventBuilder::message(
+                        "1",
+                        ChatMessageBuilder::new().link("mail", "mailto:alice@example.com"),
+                    )
+                    .timestamp(next_day + Duration::minutes(1)),
+                )
+                .build(),
+        ],
+    }
+}
+
+/// Type `keys` as if pressed one after another, with `\n` for Enter.
+fn press(app: &mut App, keys: &str) {
+    for c in keys.chars() {
+        let code = match c {
+            '\n' => KeyCode::Enter,
+            '\t' => KeyCode::Tab,
+            c => KeyCode::Char(c),
+        };
+        assert!(app.handle(KeyEvent::from(code)));
+    }
+}
+
+fn screen(app: &mut App) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(100, 12)).unwrap();
+    terminal.draw(|frame| app.draw(frame)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let width = buffer.area.width as usize;
+    let symbols: Vec<_> = buffer.content().iter().map(|cell| cell.symbol()).collect();
+    symbols
+        .chunks(width)
+        .map(|row| row.concat().trim_end().to_owned())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[test]
+fn lists_recent_conversations_first() {
+    let hangouts = hangouts();
+    let mut app = App::new(&hangouts, true);
+    let names: Vec<_> = app.conversations.iter().map(|c| c.display_name()).collect();
+    assert_eq!(names, ["Lunch club", "Old friends"]);
+    assert_eq!(
+        app.summaries,
+        ["2015-01-02  3 messages", "2015-01-01  2 messages"]
+    );
+    // The most recent event of the selected conversation is highlighted.
+    assert_eq!(app.cursor, 3);
+
+    let shown = screen(&mut app);
+    assert!(shown.contains("│Lunch club"));
+    assert!(shown.contains("│2015-01-01  2 messages"));
+    assert!(shown.contains("00:00 Alice: Lunch today?"));
+    assert!(shown.contains("00:01 * Carol named the conversation \"Lunch club\""));
+    assert!(shown.contains("── Friday, 2015-01-02 ──"));
+    assert!(shown.contains("00:00 Carol: Sure, see the menuor mail me"));
+    assert!(shown.ends_with(HELP));
+
+    press(&mut app, "j\t");
+    assert!(app.focus == Focus::Messages);
+    assert!(screen(&mut app).contains("00:01 Bob: General Kenobi"));
+    assert_eq!(app.cursor, 1);
+    press(&mut app, "kkg");
+    assert_eq!(app.cursor, 0);
+
+    assert!(!app.handle(KeyEvent::from(KeyCode::Char('q'))));
+}
+
+#[test]
+fn renders_formatting_and_wraps() {
+    let hangouts = hangouts();
+    let conversation = &hangouts.conversations[1];
+    let transcript = Transcript::new(conversation, 20, true);
+
+    let text: Vec<String> = transcript.lines.iter().map(ToString::to_string).collect();
+    assert_eq!(text[0].trim(), "── Thursday, 2015-01-01 ──");
+    assert_eq!(text[1], "00:00 Alice: Lunch ");
+    assert_eq!(text[2], "      today?");
+    assert_eq!(transcript.events.len(), 4);
+    assert_eq!(transcript.events[0], 1..3);
+
+    let sure = transcript
+        .lines
+        .iter()
+        .flat_map(|line| &line.spans)
+        .find(|span| span.content == "Sure")
+        .unwrap();
+    assert!(sure.style.add_modifier.contains(Modifier::BOLD));
+    let link = transcript
+        .lines
+        .iter()
+        .flat_map(|line| &line.spans)
+        .find(|span| span.content.contains("menu"))
+        .unwrap();
+    assert!(link.style.add_modifier.contains(Modifier::UNDERLINED));
+}
+
+#[test]
+fn searches_and_jumps_to_dates() {
+    let hangouts = hangouts();
+    let mut app = App::new(&hangouts, true);
+
+    press(&mut app, "/LUNCH\n");
+    assert_eq!(app.search.as_deref(), Some("lunch"));
+    assert_eq!(app.cursor, 0);
+    assert_eq!(app.status.as_deref(), Some("Search wrapped"));
+    press(&mut app, "n");
+    assert_eq!(app.cursor, 1);
+    assert_eq!(app.status, None);
+    press(&mut app, "N");
+    assert_eq!(app.cursor, 0);
+    press(&mut app, "/nobody\n");
+    assert_eq!(app.status.as_deref(), Some("No match for \"nobody\""));
+    assert!(screen(&mut app).ends_with("No match for \"nobody\""));
+
+    press(&mut app, "d2015-01-02\n");
+    assert_eq!(app.cursor, 2);
+    press(&mut app, "d2014-12-31\n");
+    assert_eq!(app.cursor, 0);
+    press(&mut app, "d2016-01-01\n");
+    assert_eq!(app.cursor, 3);
+
+    press(&mut app, "dsoon");
+    assert!(screen(&mut app).ends_with("Jump to date: soon"));
+    press(&mut app, "\n");
+    assert_eq!(
+        app.status.as_deref(),
+        Some("Invalid date \"soon\", use YYYY-MM-DD")
+    );
+}
+
+#[test]
+fn opens_only_web_links() {
+    assert!(is_web_url("https://example.com/menu?a=1&b=2"));
+    assert!(is_web_url("HTTP://example.com"));
+    for url in [
+        "",
+        "-oProxyCommand=calc",
+        "file:///etc/passwd",
+        "javascript:alert(1)",
+        "mailto:carol@example.com",
+        "https:example.com",
+        "http://example.com/\nrm",
+    ] {
+        assert!(!is_web_url(url), "{:?}", url);
+        assert_eq!(
+            open(url).unwrap_err().kind(),
+            io::ErrorKind::InvalidInput,
+            "{:?}",
+            url
+        );
+    }
+
+    // The last message only links to an email address.
+    let hangouts = hangouts();
+    let mut app = App::new(&hangouts, true);
+    press(&mut app, "o");
+    assert_eq!(app.status.as_deref(), Some("No web links in this message"));
+}
@@ -0,0 +1,189 @@
+//! Styled and wrapped lines of a conversation for the message view.
+
+use crate::transcript::{attachment, local_time, name, system_text};
+
+use std::collections::HashMap;
+use std::mem;
+use std::ops::Range;
+
+use chrono::NaiveDate;
+use hangouts_rs::{ChatSegment, Conversation, Formatting};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Colors of sender names, assigned in participant order.
+const SENDER_COLORS: &[Color] = &[
+    Color::Cyan,
+    Color::Green,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Blue,
+    Color::Red,
+];
+
+/// Width of the time column, such as `09:46 `.
+const TIME_WIDTH: usize = 6;
+
+/// The rendered lines of a conversation at a given width.
+pub struct Transcript {
+    pub lines: Vec<Line<'static>>,
+    /// Lines of every event, excluding date separators.
+    pub events: Vec<Range<usize>>,
+}
+
+impl Transcript {
+    /// Render every event of `conversation`, wrapped to `width` columns.
+    pub fn new(conversation: &Conversation, width: usize, utc: bool) -> Self {
+        let mut ids: Vec<_> = conversation.participants.keys().collect();
+        ids.sort_unstable();
+        let colors: HashMap<_, _> = ids
+            .into_iter()
+            .enumerate()
+            .map(|(i, id)| (id, SENDER_COLORS[i % SENDER_COLORS.len()]))
+            .collect();
+
+        let mut lines = Vec::new();
+        let mut events = Vec::with_capacity(conversation.events.len());
+        let mut last_date: Option<NaiveDate> = None;
+        for event in &conversation.events {
+            let time = local_time(event.timestamp, utc);
+            if last_date != Some(time.date_naive()) {
+                last_date = Some(time.date_naive());
+                lines.push(
+                    Line::from(format!("── {} ──", time.format("%A, %Y-%m-%d")))
+                        .dark_gray()
+                        .centered(),
+                );
+            }
+
+            let start = lines.len();
+            let mut wrapper = Wrapper::new(&mut lines, width, TIME_WIDTH);
+            wrapper.push(&time.format("%H:%M ").to_string(), Style::new().dark_gray());
+            match event.data.as_chat_message() {
+                Some(message) => {
+                    let color = colors.get(&event.sender).copied().unwrap_or(Color::White);
+                    wrapper.push(
+                        name(conversation, &event.sender),
+                        Style::new().fg(color).bold(),
+                    );
+                    wrapper.push(": ", Style::new());
+                    for segment in &message.contents {
+                        match segment {
+                            ChatSegment::Text { text, format } => wrapper.push(text, style(format)),
+                            ChatSegment::Link { text, format, .. } => {
+                                wrapper.push(text, style(format).blue().underlined())
+                            }
+                            ChatSegment::LinkBreak { .. } => wrapper.new_line(),
+                        }
+                    }
+                    for item in &message.attachments {
+                        wrapper.new_line();
+                        wrapper.push(&attachment(item), Style::new().blue());
+                    }
+                }
+                None => {
+                    let text = system_text(conversation, event).unwrap_or_default();
+                    wrapper.push(&format!("* {}", text), Style::new().dark_gray().italic());
+                }
+            }
+            wrapper.finish();
+            events.push(start..lines.len());
+        }
+
+        Self { lines, events }
+    }
+}
+
+/// Convert message formatting to a terminal style.
+fn style(format: &Formatting) -> Style {
+    let mut modifier = Modifier::empty();
+    if format.bold {
+        modifier |= Modifier::BOLD;
+    }
+    if format.italics {
+        modifier |= Modifier::ITALIC;
+    }
+    if format.strikethrough {
+        modifier |= Modifier::CROSSED_OUT;
+    }
+    if format.underline {
+        modifier |= Modifier::UNDERLINED;
+    }
+    Style::new().add_modifier(modifier)
+}
+
+/// Word wrapping of styled text, with continuation lines indented.
+struct Wrapper<'a> {
+    lines: &'a mut Vec<Line<'static>>,
+    width: usize,
+    indent: usize,
+    spans: Vec<Span<'static>>,
+    /// Width of the current line.
+    used: usize,
+    /// Width of the indentation of the current line.
+    start: usize,
+}
+
+impl<'a> Wrapper<'a> {
+    fn new(lines: &'a mut Vec<Line<'static>>, width: usize, indent: usize) -> Self {
+        let width = width.max(1);
+        Self {
+            lines,
+            width,
+            // Give up on indenting in very narrow views.
+            indent: if indent * 2 < width { indent } else { 0 },
+            spans: Vec::new(),
+            used: 0,
+            start: 0,
+        }
+    }
+
+    fn push(&mut self, text: &str, style: Style) {
+        for (i, line) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.new_line();
+            }
+            for word in line.split_inclusive(' ') {
+                let fit = word.trim_end_matches(' ').width();
+                if self.used + fit > self.width && self.used > self.start {
+                    self.new_line();
+                }
+                if self.used + fit <= self.width {
+                    self.append(word, style);
+                    continue;
+                }
+                // Break words longer than a line anywhere.
+                for c in word.chars() {
+                    let width = c.width().unwrap_or(0);
+                    if self.used + width > self.width && self.used > self.start {
+                        self.new_line();
+                    }
+                    self.append(c.encode_utf8(&mut [0; 4]), style);
+                }
+            }
+        }
+    }
+
+    fn append(&mut self, s: &str, style: Style) {
+        self.used += s.width();
+        match self.spans.last_mut() {
+            Some(span) if span.style == style => span.content.to_mut().push_str(s),
+            _ => self.spans.push(Span::styled(s.to_owned(), style)),
+        }
+    }
+
+    fn new_line(&mut self) {
+        let spans = mem::take(&mut self.spans);
+        self.lines.push(Line::from(spans));
+        self.spans.push(Span::raw(" ".repeat(self.indent)));
+        self.used = self.indent;
+        self.start = self.indent;
+    }
+
+    fn finish(mut self) {
+        if !self.spans.is_empty() {
+            self.lines.push(Line::from(mem::take(&mut self.spans)));
+        }
+    }
+}
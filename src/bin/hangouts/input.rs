@@ -0,0 +1,86 @@
+//! Loading of Hangouts.json files, Takeout directories and Takeout zip archives.
+
+use crate::Error;
+
+use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use hangouts_rs::raw::stream::for_each_conversation;
+use hangouts_rs::{Conversation, Hangouts};
+
+/// Locations of Hangouts.json inside a Takeout directory, in order of preference.
+const CANDIDATES: &[&str] = &[
+    "Takeout/Hangouts/Hangouts.json",
+    "Hangouts/Hangouts.json",
+    "Hangouts.json",
+];
+
+/// Loaded Hangouts data.
+pub struct Input {
+    pub hangouts: Hangouts,
+    /// Directory holding the media files of the export, if it is on disk.
+    pub media_dir: Option<PathBuf>,
+}
+
+/// Load the Hangouts data at `path`, which may be a Hangouts.json file, an extracted Takeout
+/// directory or a Takeout zip archive.
+///
+/// Events of every conversation are sorted by time.
+pub fn load(path: &Path) -> Result<Input, Error> {
+    let (hangouts, media_dir) = read(path, parse)?;
+    Ok(Input {
+        hangouts,
+        media_dir,
+    })
+}
+
+/// Call `f` with a reader of the Hangouts.json at `path`, also returning the directory of
+/// the media files if the input is an extracted Takeout.
+pub fn read<T, F>(path: &Path, f: F) -> Result<(T, Option<PathBuf>), Error>
+where
+    F: FnOnce(&mut dyn Read) -> Result<T, Error>,
+{
+    if path.is_dir() {
+        let json = CANDIDATES
+            .iter()
+            .map(|candidate| path.join(candidate))
+            .find(|candidate| candidate.is_file())
+            .ok_or_else(|| Error::NotFound(path.to_owned()))?;
+        let value = f(&mut BufReader::new(File::open(&json)?))?;
+        return Ok((value, json.parent().map(Path::to_owned)));
+    }
+
+    let mut file = File::open(path)?;
+    let mut magic = [0; 4];
+    let is_zip = file.read_exact(&mut magic).is_ok() && magic == *b"PK\x03\x04";
+    drop(file);
+
+    let value = if is_zip {
+        let mut archive = zip::ZipArchive::new(BufReader::new(File::open(path)?))?;
+        let index = (0..archive.len())
+            .filter_map(|i| Some((i, archive.name_for_index(i)?)))
+            .filter(|(_, name)| *name == "Hangouts.json" || name.ends_with("/Hangouts.json"))
+            // Prefer the shortest path, i.e. the one closest to the archive root.
+            .min_by_key(|(_, name)| name.len())
+            .map(|(i, _)| i)
+            .ok_or_else(|| Error::NotFound(path.to_owned()))?;
+        let mut entry = BufReader::new(archive.by_index(index)?);
+        f(&mut entry)?
+    } else {
+        f(&mut BufReader::new(File::open(path)?))?
+    };
+    Ok((value, None))
+}
+
+fn parse(reader: &mut dyn Read) -> Result<Hangouts, Error> {
+    let mut conversations = Vec::new();
+    for_each_conversation(reader, |raw| {
+        let mut conversation = Conversation::try_from(raw)?;
+        conversation.sort_events_by_time();
+        conversations.push(conversation);
+        Ok(())
+    })?;
+    Ok(Hangouts { conversations })
+}
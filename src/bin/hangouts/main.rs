@@ -0,0 +1,1104 @@
+//! The `hangouts` command-line tool.
+//!
+//! Every subcommand reads a Hangouts.json file, an extracted Takeout directory or a Takeout
+//! zip archive directly.
+
+#[cfg(feature = "tui")]
+mod browse;
+mod input;
+mod transcript;
+
+use crate::input::Input;
+use crate::transcript::{local_time, message_text, name, system_text};
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use chrono::{DateTime, Utc};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use hangouts_rs::diff::Diff;
+use hangouts_rs::export::{
+    geo, ical, jsonl, mail, matrix, mattermost, sanitize_file_name, slack, vcard, xmpp, ExportError,
+};
+use hangouts_rs::media::MediaResolver;
+use hangouts_rs::pii::{self, PiiKind, PiiOptions, Scanner};
+use hangouts_rs::raw;
+use hangouts_rs::raw::anonymize::{AnonymizeOptions, Anonymizer, TextMode};
+use hangouts_rs::raw::convert::ConversionError;
+use hangouts_rs::raw::stream::{for_each_conversation, StreamError};
+use hangouts_rs::stats::{
+    Frequencies, Language, Responsiveness, SessionOptions, Stats, TextOptions, Vocabulary,
+};
+use hangouts_rs::validate::ValidationReport;
+use hangouts_rs::{format_duration, Conversation, Hangouts, ParticipantId};
+
+/// Error produced by a subcommand.
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    Io(#[from] io::Error),
+    Parse(#[from] StreamError<ConversionError>),
+    Zip(#[from] zip::result::ZipError),
+    Json(#[from] serde_json::Error),
+    Export(#[from] ExportError),
+    /// No Hangouts.json was found in the directory or archive.
+    NotFound(PathBuf),
+    /// No conversation matches the query.
+    NoConversation(String),
+    /// Several conversations match the query.
+    Ambiguous(String, Vec<String>),
+    /// The arguments are missing a value required by the input.
+    Usage(String),
+    /// Validation found problems.
+    Invalid(usize),
+}
+
+impl fmt::Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Io(err) => write!(f, "io: {}", err),
+            Self::Parse(err) => write!(f, "parse: {}", err),
+            Self::Zip(err) => write!(f, "zip: {}", err),
+            Self::Json(err) => write!(f, "json: {}", err),
+            Self::Export(err) => write!(f, "export: {}", err),
+            Self::NotFound(path) => write!(f, "no Hangouts.json in {}", path.display()),
+            Self::NoConversation(query) => write!(f, "no conversation matches {:?}", query),
+            Self::Ambiguous(query, names) => write!(
+                f,
+                "{:?} matches several conversations:\n  {}",
+                query,
+                names.join("\n  ")
+            ),
+            Self::Usage(msg) => write!(f, "{}", msg),
+            Self::Invalid(count) => write!(f, "{} problem(s) found", count),
+        }
+    }
+}
+
+/// Inspect and convert Google Hangouts Takeout exports.
+#[derive(Parser)]
+#[command(name = "hangouts", version)]
+struct Cli {
+    /// Show times in UTC instead of the local time zone.
+    #[arg(long, global = true)]
+    utc: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// List conversations with their participants, message counts and date range.
+    List {
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Print the transcript of a conversation.
+    Show {
+        #[command(flatten)]
+        input: InputArgs,
+        /// ID or part of the name of the conversation.
+        conversation: String,
+    },
+    /// Search chat messages for text.
+    Search {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Text to search for.
+        query: String,
+        /// Only search the conversation with this ID or name.
+        #[arg(short, long)]
+        conversation: Option<String>,
+        /// Only search messages of senders whose ID or name contains this text.
+        #[arg(short, long)]
+        sender: Option<String>,
+        /// Match case.
+        #[arg(long)]
+        case_sensitive: bool,
+    },
+    /// Print message, call, reply and vocabulary statistics.
+    Stats {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Only count the conversation with this ID or name.
+        #[arg(short, long)]
+        conversation: Option<String>,
+        /// Number of most frequent words, emoji and domains shown.
+        #[arg(long, default_value_t = 10)]
+        top: usize,
+        /// Stopword languages excluded from word counts.
+        #[arg(long, value_enum, value_delimiter = ',', default_value = "en")]
+        stopwords: Vec<StopwordLanguage>,
+        /// Print the statistics as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Convert conversations to another format.
+    Export(ExportArgs),
+    /// Write a copy of Hangouts.json with names, IDs, text, links and places replaced by
+    /// pseudonyms, for sharing.
+    Anonymize {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Output file. Defaults to standard output.
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Seed of the pseudonyms. The same seed always gives the same output.
+        #[arg(long)]
+        seed: u64,
+        /// Rewrite equal words the same way, preserving word frequencies. Frequent words
+        /// can then be recovered from the output.
+        #[arg(long)]
+        hash_words: bool,
+    },
+    /// Report emails, phone numbers, card numbers and other personal data in messages.
+    Pii {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Only scan the conversation with this ID or name.
+        #[arg(short, long, conflicts_with = "redact")]
+        conversation: Option<String>,
+        /// Kinds of data looked for. Defaults to all.
+        #[arg(long, value_enum, value_delimiter = ',')]
+        kind: Vec<PiiKindArg>,
+        /// Print the findings as JSON.
+        #[arg(long, conflicts_with = "redact")]
+        json: bool,
+        /// Write a copy of Hangouts.json with every finding replaced by a placeholder.
+        #[arg(long, value_name = "OUTPUT")]
+        redact: Option<PathBuf>,
+    },
+    /// Compare two exports and list the conversations, participants and events that changed.
+    Diff {
+        /// The old export.
+        old: PathBuf,
+        /// The new export.
+        new: PathBuf,
+        /// Print the differences as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Check that the input parses and its conversations are consistent.
+    Validate {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Print the report as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// Browse conversations in an interactive terminal UI.
+    #[cfg(feature = "tui")]
+    Browse {
+        #[command(flatten)]
+        input: InputArgs,
+    },
+    /// Serve a web viewer of the conversations on localhost.
+    #[cfg(feature = "web")]
+    Serve {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Port to listen on, or 0 for any free port.
+        #[arg(short, long, default_value_t = 8080)]
+        port: u16,
+        /// Directory with the media files of the export. Defaults to the directory of
+        /// Hangouts.json in an extracted Takeout.
+        #[arg(long)]
+        media: Option<PathBuf>,
+    },
+}
+
+#[derive(Args)]
+struct InputArgs {
+    /// Hangouts.json, extracted Takeout directory or Takeout zip archive.
+    input: PathBuf,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    #[command(flatten)]
+    input: InputArgs,
+    /// Output format.
+    #[arg(short, long, value_enum)]
+    format: Format,
+    /// Output file, or directory for mbox, maildir, matrix, xmpp and slack. Single files are
+    /// written to standard output if omitted. Slack exports ending in `.zip` are zipped.
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Only export the conversations with these IDs or names.
+    #[arg(short, long)]
+    conversation: Vec<String>,
+    /// Directory with the media files of the export, for slack and mattermost. Defaults to
+    /// the directory of Hangouts.json in an extracted Takeout.
+    #[arg(long)]
+    media: Option<PathBuf>,
+    /// Gaia ID of the account owner, for xmpp. Defaults to the only participant of every
+    /// conversation.
+    #[arg(long)]
+    owner: Option<String>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    /// One JSON object per event.
+    Jsonl,
+    /// One mbox file per conversation.
+    Mbox,
+    /// One Maildir folder per conversation.
+    Maildir,
+    /// One Matrix room history per conversation.
+    Matrix,
+    /// XEP-0227 archive of one-to-one chats and MUC archives of group chats.
+    Xmpp,
+    /// Mattermost bulk import file.
+    Mattermost,
+    /// Slack export directory or zip archive.
+    Slack,
+    /// iCalendar file of calls.
+    Ical,
+    /// vCard file of participants.
+    Vcard,
+    /// GeoJSON file of shared places.
+    Geojson,
+    /// KML file of shared places.
+    Kml,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StopwordLanguage {
+    En,
+    Fr,
+    De,
+    Es,
+    None,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum PiiKindArg {
+    Email,
+    Phone,
+    CreditCard,
+    Iban,
+    Address,
+    ApiKey,
+    Password,
+}
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    match run(cli) {
+        Ok(()) => ExitCode::SUCCESS,
+        // Output piped into a command that exited early, such as `head`.
+        Err(Error::Io(err)) if err.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run(cli: Cli) -> Result<(), Error> {
+    let utc = cli.utc;
+    match cli.command {
+        Command::List { input } => list(&load(&input)?.hangouts, utc),
+        Command::Show {
+            input,
+            conversation,
+        } => {
+            let hangouts = load(&input)?.hangouts;
+            show(select(&hangouts, &conversation)?, utc)
+        }
+        Command::Search {
+            input,
+            query,
+            conversation,
+            sender,
+            case_sensitive,
+        } => {
+            let hangouts = load(&input)?.hangouts;
+            let conversations = match conversation {
+                Some(query) => vec![select(&hangouts, &query)?],
+                None => hangouts.conversations.iter().collect(),
+            };
+            search(
+                &conversations,
+                &query,
+                sender.as_deref(),
+                case_sensitive,
+                utc,
+            )
+        }
+        Command::Stats {
+            input,
+            conversation,
+            top,
+            stopwords,
+            json,
+        } => {
+            let hangouts = load(&input)?.hangouts;
+            let conversation = match conversation {
+                Some(query) => Some(select(&hangouts, &query)?),
+                None => None,
+            };
+            let options = TextOptions {
+                stopwords: stopwords.iter().filter_map(|s| s.language()).collect(),
+                ..TextOptions::default()
+            };
+            stats(&hangouts, conversation, top, &options, json, utc)
+        }
+        Command::Export(args) => export(&args),
+        Command::Anonymize {
+            input,
+            output,
+            seed,
+            hash_words,
+        } => {
+            let options = AnonymizeOptions {
+                seed,
+                text: if hash_words {
+                    TextMode::Hash
+                } else {
+                    TextMode::Scramble
+                },
+            };
+            anonymize(&input.input, output.as_deref(), options)
+        }
+        Command::Pii {
+            input,
+            conversation,
+            kind,
+            json,
+            redact,
+        } => {
+            let options = if kind.is_empty() {
+                PiiOptions::default()
+            } else {
+                PiiOptions {
+                    kinds: kind.into_iter().map(PiiKindArg::kind).collect(),
+                }
+            };
+            let scanner = Scanner::new(&options);
+            match redact {
+                Some(output) => pii_redact(&input.input, &output, &scanner),
+                None => {
+                    let hangouts = load(&input)?.hangouts;
+                    let conversations = match conversation {
+                        Some(query) => vec![select(&hangouts, &query)?],
+                        None => hangouts.conversations.iter().collect(),
+                    };
+                    pii_report(&conversations, &scanner, json, utc)
+                }
+            }
+        }
+        Command::Diff { old, new, json } => diff(&old, &new, json),
+        Command::Validate { input, json } => validate(&input.input, json),
+        #[cfg(feature = "tui")]
+        Command::Browse { input } => browse::run(&load(&input)?.hangouts, utc),
+        #[cfg(feature = "web")]
+        Command::Serve { input, port, media } => serve(load(&input)?, port, media),
+    }
+}
+
+impl PiiKindArg {
+    fn kind(self) -> PiiKind {
+        match self {
+            Self::Email => PiiKind::Email,
+            Self::Phone => PiiKind::Phone,
+            Self::CreditCard => PiiKind::CreditCard,
+            Self::Iban => PiiKind::Iban,
+            Self::Address => PiiKind::Address,
+            Self::ApiKey => PiiKind::ApiKey,
+            Self::Password => PiiKind::Password,
+        }
+    }
+}
+
+impl StopwordLanguage {
+    fn language(self) -> Option<Language> {
+        match self {
+            Self::En => Some(Language::English),
+            Self::Fr => Some(Language::French),
+            Self::De => Some(Language::German),
+            Self::Es => Some(Language::Spanish),
+            Self::None => None,
+        }
+    }
+}
+
+#[inline]
+fn load(args: &InputArgs) -> Result<Input, Error> {
+    input::load(&args.input)
+}
+
+#[inline]
+fn stdout() -> BufWriter<io::StdoutLock<'static>> {
+    BufWriter::new(io::stdout().lock())
+}
+
+/// Find a conversation by ID, or by a case-insensitive part of its name.
+///
+/// A conversation whose whole name matches is preferred over partial matches.
+fn select<'a>(hangouts: &'a Hangouts, query: &str) -> Result<&'a Conversation, Error> {
+    let conversations = &hangouts.conversations;
+    if let Some(conversation) = conversations.iter().find(|c| c.conversation_id == query) {
+        return Ok(conversation);
+    }
+
+    let needle = query.to_lowercase();
+    let matches: Vec<_> = conversations
+        .iter()
+        .filter(|c| c.display_name().to_lowercase().contains(&needle))
+        .collect();
+    let exact: Vec<_> = matches
+        .iter()
+        .copied()
+        .filter(|c| c.display_name().to_lowercase() == needle)
+        .collect();
+
+    match (matches.as_slice(), exact.as_slice()) {
+        ([], _) => Err(Error::NoConversation(query.to_owned())),
+        ([conversation], _) | (_, [conversation]) => Ok(conversation),
+        _ => Err(Error::Ambiguous(
+            query.to_owned(),
+            matches
+                .iter()
+                .map(|c| format!("{}  {}", c.conversation_id, c.display_name()))
+                .collect(),
+        )),
+    }
+}
+
+/// Get the name of a participant in any conversation, falling back to their ID.
+fn any_name<'a>(hangouts: &'a Hangouts, id: &'a ParticipantId) -> &'a str {
+    hangouts
+        .conversations
+        .iter()
+        .find_map(|c| c.participant_name(id))
+        .map(String::as_str)
+        .unwrap_or(&id.gaia_id)
+}
+
+fn format_time(time: DateTime<Utc>, utc: bool) -> String {
+    local_time(time, utc)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
+
+fn format_date(time: DateTime<Utc>, utc: bool) -> String {
+    local_time(time, utc).format("%Y-%m-%d").to_string()
+}
+
+fn list(hangouts: &Hangouts, utc: bool) -> Result<(), Error> {
+    let mut conversations: Vec<_> = hangouts.conversations.iter().collect();
+    conversations.sort_by_key(|c| Reverse(c.sort_timestamp));
+
+    let mut out = stdout();
+    for conversation in conversations {
+        let messages: Vec<_> = conversation
+            .events
+            .iter()
+            .filter(|event| event.data.is_chat_message())
+            .collect();
+        let range = match (messages.first(), messages.last()) {
+            (Some(first), Some(last)) => format!(
+                "{} to {}",
+                format_date(first.timestamp, utc),
+                format_date(last.timestamp, utc)
+            ),
+            _ => "no messages".to_owned(),
+        };
+        let mut participants: Vec<_> = conversation
+            .participants
+            .keys()
+            .map(|id| name(conversation, id))
+            .collect();
+        participants.sort_unstable();
+
+        writeln!(
+            out,
+            "{}  {}",
+            conversation.conversation_id,
+            conversation.display_name()
+        )?;
+        writeln!(out, "    {} messages, {}", messages.len(), range)?;
+        writeln!(out, "    {}", participants.join(", "))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn show(conversation: &Conversation, utc: bool) -> Result<(), Error> {
+    let mut out = stdout();
+    writeln!(out, "# {}", conversation.display_name())?;
+    writeln!(out)?;
+
+    for event in &conversation.events {
+        let time = format_time(event.timestamp, utc);
+        if let Some(text) = message_text(event) {
+            let mut lines = text.lines();
+            writeln!(
+                out,
+                "{}  {}: {}",
+                time,
+                name(conversation, &event.sender),
+                lines.next().unwrap_or("")
+            )?;
+            for line in lines {
+                writeln!(out, "{:width$}  {}", "", line, width = time.len())?;
+            }
+        } else if let Some(text) = system_text(conversation, event) {
+            writeln!(out, "{}  * {}", time, text)?;
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn search(
+    conversations: &[&Conversation],
+    query: &str,
+    sender: Option<&str>,
+    case_sensitive: bool,
+    utc: bool,
+) -> Result<(), Error> {
+    let fold = |s: &str| {
+        if case_sensitive {
+            s.to_owned()
+        } else {
+            s.to_lowercase()
+        }
+    };
+    let query = fold(query);
+    let sender = sender.map(str::to_lowercase);
+
+    let mut results = Vec::new();
+    for conversation in conversations {
+        for event in &conversation.events {
+            let message = match event.data.as_chat_message() {
+                Some(message) => message,
+                None => continue,
+            };
+            let sender_name = name(conversation, &event.sender);
+            if let Some(sender) = &sender {
+                if !sender_name.to_lowercase().contains(sender) && event.sender.gaia_id != *sender {
+                    continue;
+                }
+            }
+            let text = message.contents_as_str();
+            if fold(&text).contains(&query) {
+                results.push((event.timestamp, conversation, sender_name, text));
+            }
+        }
+    }
+    results.sort_by_key(|(timestamp, ..)| *timestamp);
+
+    let mut out = stdout();
+    for (timestamp, conversation, sender_name, text) in results {
+        writeln!(
+            out,
+            "{}  [{}]  {}: {}",
+            format_time(timestamp, utc),
+            conversation.display_name(),
+            sender_name,
+            text.replace('\n', " ")
+        )?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn stats(
+    hangouts: &Hangouts,
+    conversation: Option<&Conversation>,
+    top: usize,
+    options: &TextOptions,
+    json: bool,
+    utc: bool,
+) -> Result<(), Error> {
+    let session_options = SessionOptions::default();
+    let (stats, responsiveness, vocabulary) = match conversation {
+        Some(c) => (
+            Stats::for_conversation(c),
+            Responsiveness::for_conversation(c, &session_options),
+            Vocabulary::for_conversation(c, options),
+        ),
+        None => (
+            Stats::for_hangouts(hangouts),
+            Responsiveness::for_hangouts(hangouts, &session_options),
+            Vocabulary::for_hangouts(hangouts, options),
+        ),
+    };
+    let words = &vocabulary.overall;
+
+    let mut out = stdout();
+    if json {
+        let value = serde_json::json!({
+            "stats": stats,
+            "responsiveness": responsiveness,
+            "top_words": words.words.top(top),
+            "top_ngrams": words.ngrams.top(top),
+            "top_emoji": words.emoji.top(top),
+            "top_domains": words.domains.top(top),
+        });
+        serde_json::to_writer_pretty(&mut out, &value)?;
+        writeln!(out)?;
+        out.flush()?;
+        return Ok(());
+    }
+
+    let totals = &stats.totals;
+    match conversation {
+        Some(c) => writeln!(out, "{}", c.display_name())?,
+        None => writeln!(out, "All conversations ({})", hangouts.conversations.len())?,
+    }
+    writeln!(
+        out,
+        "  Messages:        {} ({} words, {} characters)",
+        totals.messages, totals.words, totals.characters
+    )?;
+    writeln!(
+        out,
+        "  Attachments:     {} ({} links, {} places)",
+        totals.attachments, totals.links, totals.places
+    )?;
+    writeln!(
+        out,
+        "  Calls:           {} ({})",
+        stats.calls,
+        format_duration(stats.call_duration)
+    )?;
+    for (label, message) in [
+        ("First message:", &stats.first_message),
+        ("Last message:", &stats.last_message),
+    ] {
+        if let Some(message) = message {
+            writeln!(
+                out,
+                "  {:16} {} by {}",
+                label,
+                format_time(message.timestamp, utc),
+                any_name(hangouts, &message.sender)
+            )?;
+        }
+    }
+    if let Some(day) = &stats.busiest_day {
+        writeln!(
+            out,
+            "  Busiest day:     {} ({} messages)",
+            day.date, day.messages
+        )?;
+    }
+    if let Some(silence) = &stats.longest_silence {
+        writeln!(
+            out,
+            "  Longest silence: {} days, from {} to {}",
+            silence.secs / 86400,
+            format_date(silence.start, utc),
+            format_date(silence.end, utc)
+        )?;
+    }
+    writeln!(
+        out,
+        "  Sessions:        {} (average {:.1} messages)",
+        responsiveness.sessions, responsiveness.average_session_messages
+    )?;
+
+    writeln!(out)?;
+    writeln!(
+        out,
+        "  {:24} {:>8} {:>8} {:>6} {:>13}",
+        "Participant", "Messages", "Words", "Share", "Median reply"
+    )?;
+    let replies: HashMap<_, _> = responsiveness
+        .participants
+        .iter()
+        .map(|p| (&p.id, p.median_reply_secs))
+        .collect();
+    for participant in &stats.participants {
+        let share = if totals.messages == 0 {
+            0.0
+        } else {
+            participant.counts.messages as f64 * 100.0 / totals.messages as f64
+        };
+        let reply = match replies.get(&participant.id).copied().flatten() {
+            Some(secs) => format_duration(secs.max(0) as u64),
+            None => "-".to_owned(),
+        };
+        writeln!(
+            out,
+            "  {:24} {:>8} {:>8} {:>5.1}% {:>13}",
+            participant
+                .name
+                .as_deref()
+                .unwrap_or(&participant.id.gaia_id),
+            participant.counts.messages,
+            participant.counts.words,
+            share,
+            reply
+        )?;
+    }
+
+    for (label, frequencies) in [
+        ("Top words", &words.words),
+        ("Top phrases", &words.ngrams),
+        ("Top emoji", &words.emoji),
+        ("Top domains", &words.domains),
+    ] {
+        write_top(&mut out, label, frequencies, top)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn write_top<W: Write>(
+    out: &mut W,
+    label: &str,
+    frequencies: &Frequencies,
+    n: usize,
+) -> io::Result<()> {
+    let top = frequencies.top(n);
+    if top.is_empty() {
+        return Ok(());
+    }
+    writeln!(out)?;
+    writeln!(out, "  {}:", label)?;
+    for (s, count) in top {
+        writeln!(out, "    {:>6}  {}", count, s)?;
+    }
+    Ok(())
+}
+
+fn export(args: &ExportArgs) -> Result<(), Error> {
+    let Input {
+        mut hangouts,
+        media_dir,
+    } = load(&args.input)?;
+    if !args.conversation.is_empty() {
+        let ids = args
+            .conversation
+            .iter()
+            .map(|query| Ok(select(&hangouts, query)?.conversation_id.clone()))
+            .collect::<Result<HashSet<_>, Error>>()?;
+        hangouts
+            .conversations
+            .retain(|c| ids.contains(&c.conversation_id));
+    }
+
+    let hangouts = &hangouts;
+    let media = || -> Result<HashMap<String, PathBuf>, Error> {
+        match args.media.as_ref().or(media_dir.as_ref()) {
+            Some(dir) => Ok(MediaResolver::new(dir)?.resolve_all(hangouts).paths()),
+            None => Ok(HashMap::new()),
+        }
+    };
+    let dir = || -> Result<&Path, Error> {
+        args.output.as_deref().ok_or_else(|| {
+            Error::Usage("this format needs an output directory (--output)".to_owned())
+        })
+    };
+    let file = || -> Result<Box<dyn Write>, Error> {
+        match args.output.as_deref() {
+            Some(path) if path != Path::new("-") => {
+                Ok(Box::new(BufWriter::new(File::create(path)?)))
+            }
+            _ => Ok(Box::new(stdout())),
+        }
+    };
+
+    match args.format {
+        Format::Jsonl => jsonl::write_hangouts(file()?, hangouts)?,
+        Format::Mbox => mail::export_mbox_files(dir()?, hangouts, &Default::default())?,
+        Format::Maildir => mail::export_maildirs(dir()?, hangouts, &Default::default())?,
+        Format::Matrix => {
+            let dir = dir()?;
+            fs::create_dir_all(dir)?;
+            let options = matrix::MatrixOptions::default();
+            let mut used = HashSet::new();
+            for conversation in &hangouts.conversations {
+                let path = dir.join(file_name(&mut used, &conversation.conversation_id, "json"));
+                let mut writer = BufWriter::new(File::create(path)?);
+                matrix::write_room(&mut writer, conversation, &options)?;
+                writer.flush()?;
+            }
+        }
+        Format::Xmpp => {
+            let owner = owner(hangouts, args.owner.as_deref())?;
+            let dir = dir()?;
+            fs::create_dir_all(dir)?;
+            let options = xmpp::XmppOptions::default();
+            let mut used = HashSet::new();
+            let path = dir.join(file_name(&mut used, &owner.gaia_id, "xml"));
+            xmpp::write_user_data(
+                BufWriter::new(File::create(path)?),
+                hangouts,
+                &owner,
+                &options,
+            )?;
+            for conversation in hangouts.conversations.iter().filter(|c| c.is_group()) {
+                let path = dir.join(file_name(&mut used, &conversation.conversation_id, "xml"));
+                xmpp::write_muc_archive(
+                    BufWriter::new(File::create(path)?),
+                    conversation,
+                    &options,
+                )?;
+            }
+        }
+        Format::Mattermost => {
+            let options = mattermost::MattermostOptions {
+                media: media()?,
+                ..Default::default()
+            };
+            mattermost::write_bulk_import(file()?, hangouts, &options)?
+        }
+        Format::Slack => {
+            let options = slack::SlackOptions {
+                media: media()?,
+                ..Default::default()
+            };
+            let export = slack::SlackExport::new(hangouts, &options)?;
+            let path = dir()?;
+            if path.extension().is_some_and(|ext| ext == "zip") {
+                let mut writer = BufWriter::new(File::create(path)?);
+                export.write_zip(&mut writer)?;
+                writer.flush()?;
+            } else {
+                export.write_dir(path)?;
+            }
+        }
+        Format::Ical => ical::write_calendar(file()?, hangouts, &Default::default())?,
+        Format::Vcard => vcard::write_vcards(file()?, hangouts)?,
+        Format::Geojson => geo::write_geojson(file()?, hangouts)?,
+        Format::Kml => geo::write_kml(file()?, hangouts)?,
+    }
+    Ok(())
+}
+
+/// Name a file after `id`, made safe and unique among the `used` names of a directory
+/// regardless of case.
+fn file_name(used: &mut HashSet<String>, id: &str, extension: &str) -> String {
+    let base = sanitize_file_name(id);
+    let mut name = format!("{}.{}", base, extension);
+    let mut n = 2;
+    while !used.insert(name.to_lowercase()) {
+        name = format!("{}-{}.{}", base, n, extension);
+        n += 1;
+    }
+    name
+}
+
+/// Write a pseudonymized copy of the Hangouts.json of `input` to `output`, or to standard
+/// output.
+fn anonymize(input: &Path, output: Option<&Path>, options: AnonymizeOptions) -> Result<(), Error> {
+    let mut anonymizer = Anonymizer::new(options);
+    rewrite(input, output, |conversation| {
+        anonymizer.anonymize_conversation(conversation)
+    })
+}
+
+fn pii_report(
+    conversations: &[&Conversation],
+    scanner: &Scanner,
+    json: bool,
+    utc: bool,
+) -> Result<(), Error> {
+    let mut findings = Vec::new();
+    for conversation in conversations {
+        let events: HashMap<_, _> = conversation
+            .events
+            .iter()
+            .map(|event| (event.id.as_str(), event))
+            .collect();
+        for finding in scanner.scan_conversation(conversation) {
+            let event = events[finding.event_id.as_str()];
+            findings.push((event.timestamp, *conversation, event, finding));
+        }
+    }
+    findings.sort_by_key(|(timestamp, ..)| *timestamp);
+
+    let mut out = stdout();
+    if json {
+        let findings: Vec<_> = findings.iter().map(|(.., finding)| finding).collect();
+        serde_json::to_writer_pretty(&mut out, &findings)?;
+        writeln!(out)?;
+    } else {
+        for (timestamp, conversation, event, finding) in &findings {
+            writeln!(
+                out,
+                "{}  [{}]  {}: {}  {}",
+                format_time(*timestamp, utc),
+                conversation.display_name(),
+                name(conversation, &event.sender),
+                finding.kind.placeholder(),
+                finding.text.replace('\n', " ")
+            )?;
+        }
+        write_pii_totals(&mut out, findings.iter().map(|(.., finding)| finding.kind))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn pii_redact(input: &Path, output: &Path, scanner: &Scanner) -> Result<(), Error> {
+    let mut kinds = Vec::new();
+    rewrite(input, Some(output), |conversation| {
+        let findings = scanner.scan_raw_conversation(conversation);
+        kinds.extend(findings.iter().map(|finding| finding.kind));
+        pii::redact_raw(conversation, &findings);
+    })?;
+
+    let mut out = stdout();
+    write_pii_totals(&mut out, kinds.into_iter())?;
+    out.flush()?;
+    Ok(())
+}
+
+fn write_pii_totals<W: Write>(
+    mut out: W,
+    kinds: impl Iterator<Item = PiiKind>,
+) -> Result<(), Error> {
+    let mut counts: BTreeMap<PiiKind, usize> = BTreeMap::new();
+    for kind in kinds {
+        *counts.entry(kind).or_default() += 1;
+    }
+    let total: usize = counts.values().sum();
+    let counts: Vec<_> = counts
+        .iter()
+        .map(|(kind, count)| format!("{} {}", count, kind.placeholder()))
+        .collect();
+    if counts.is_empty() {
+        writeln!(out, "0 findings")?;
+    } else {
+        writeln!(out, "{} findings: {}", total, counts.join(", "))?;
+    }
+    Ok(())
+}
+
+/// Write a copy of the Hangouts.json of `input` to `output`, or to standard output, passing
+/// every conversation through `f` as it is read.
+fn rewrite<F>(input: &Path, output: Option<&Path>, mut f: F) -> Result<(), Error>
+where
+    F: FnMut(&mut raw::Conversation),
+{
+    let mut out: Box<dyn Write> = match output {
+        Some(path) if path != Path::new("-") => Box::new(BufWriter::new(File::create(path)?)),
+        _ => Box::new(stdout()),
+    };
+
+    write!(out, "{{\"conversations\":[")?;
+    input::read(input, |reader| {
+        let mut first = true;
+        for_each_conversation(reader, |mut conversation| -> Result<(), Error> {
+            f(&mut conversation);
+            if !first {
+                out.write_all(b",")?;
+            }
+            first = false;
+            serde_json::to_writer(&mut out, &conversation)?;
+            Ok(())
+        })
+        .map_err(|err| match err {
+            StreamError::Json(err) => Error::Json(err),
+            StreamError::Callback(err) => err,
+        })
+    })?;
+    writeln!(out, "]}}")?;
+    out.flush()?;
+    Ok(())
+}
+
+/// Get the account owner by Gaia ID, or as the only participant of every conversation.
+fn owner(hangouts: &Hangouts, gaia_id: Option<&str>) -> Result<ParticipantId, Error> {
+    let mut ids = hangouts
+        .conversations
+        .iter()
+        .flat_map(|c| c.participants.keys());
+    if let Some(gaia_id) = gaia_id {
+        return Ok(ids
+            .find(|id| id.gaia_id == gaia_id)
+            .cloned()
+            .unwrap_or_else(|| ParticipantId {
+                gaia_id: gaia_id.to_owned(),
+                chat_id: gaia_id.to_owned(),
+            }));
+    }
+
+    let mut common: Option<HashSet<&ParticipantId>> = None;
+    for conversation in &hangouts.conversations {
+        let ids: HashSet<_> = conversation.participants.keys().collect();
+        common = Some(match common {
+            Some(common) => common.intersection(&ids).copied().collect(),
+            None => ids,
+        });
+    }
+    let common = common.unwrap_or_default();
+    match common.into_iter().collect::<Vec<_>>().as_slice() {
+        [id] => Ok((*id).clone()),
+        _ => Err(Error::Usage(
+            "cannot tell the account owner apart, pass --owner".to_owned(),
+        )),
+    }
+}
+
+fn diff(old: &Path, new: &Path, json: bool) -> Result<(), Error> {
+    let diff = Diff::new(&input::load(old)?.hangouts, &input::load(new)?.hangouts);
+
+    let mut out = stdout();
+    if json {
+        serde_json::to_writer_pretty(&mut out, &diff)?;
+        writeln!(out)?;
+    } else if diff.is_empty() {
+        writeln!(out, "no differences")?;
+    } else {
+        write!(out, "{}", diff)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn validate(input: &Path, json: bool) -> Result<(), Error> {
+    let mut report = ValidationReport::new(Utc::now());
+    input::read(input, |reader| {
+        for_each_conversation(reader, |raw| -> Result<(), ConversionError> {
+            report.check_raw(&raw);
+            report.check(&Conversation::try_from(raw)?);
+            Ok(())
+        })
+        .map_err(Error::from)
+    })?;
+
+    let mut out = stdout();
+    if json {
+        serde_json::to_writer_pretty(&mut out, &report)?;
+        writeln!(out)?;
+    } else {
+        write!(out, "{}", report)?;
+    }
+    out.flush()?;
+
+    if report.is_valid() {
+        Ok(())
+    } else {
+        Err(Error::Invalid(report.issues.len()))
+    }
+}
+
+#[cfg(feature = "web")]
+fn serve(input: Input, port: u16, media: Option<PathBuf>) -> Result<(), Error> {
+    use hangouts_rs::web::{Server, Viewer};
+
+    let resolver = match media.or(input.media_dir) {
+        Some(dir) => Some(MediaResolver::new(dir)?),
+        None => None,
+    };
+    let viewer = Viewer::new(input.hangouts, resolver.as_ref());
+    let server = Server::bind(port)?;
+    println!("Serving on http://{}/", server.addr());
+    server.run(&viewer);
+    Ok(())
+}
@@ -0,0 +1,109 @@
+//! Plain-text rendering of events.
+
+use chrono::{DateTime, FixedOffset, Local, Utc};
+use hangouts_rs::{
+    format_duration, AttachmentSegment, Conversation, Event, EventData, HangoutEventType,
+    MediaType, MembershipChangeType, ParticipantId,
+};
+
+/// Convert `time` to UTC or to the local time zone.
+#[inline]
+pub fn local_time(time: DateTime<Utc>, utc: bool) -> DateTime<FixedOffset> {
+    if utc {
+        time.fixed_offset()
+    } else {
+        time.with_timezone(&Local).fixed_offset()
+    }
+}
+
+/// Get the name of a participant, falling back to their ID.
+#[inline]
+pub fn name<'a>(conversation: &'a Conversation, id: &'a ParticipantId) -> &'a str {
+    conversation
+        .participant_name(id)
+        .map(String::as_str)
+        .unwrap_or(&id.gaia_id)
+}
+
+/// Describe an attachment, such as `[photo] https://...`.
+pub fn attachment(attachment: &AttachmentSegment) -> String {
+    let item = &attachment.item;
+    let kind = if item.photo.is_some() {
+        "photo"
+    } else if item.place.is_some() {
+        "place"
+    } else {
+        "attachment"
+    };
+    let label = item.place.as_ref().and_then(|place| place.name.as_deref());
+    match (label, item.url()) {
+        (Some(label), Some(url)) => format!("[{}] {} {}", kind, label, url),
+        (None, Some(url)) => format!("[{}] {}", kind, url),
+        (Some(label), None) => format!("[{}] {}", kind, label),
+        (None, None) => format!("[{}]", kind),
+    }
+}
+
+/// Render the body of a chat message, with one line per attachment after the text.
+pub fn message_text(event: &Event) -> Option<String> {
+    let message = event.data.as_chat_message()?;
+    let mut lines: Vec<String> = Vec::new();
+    let text = message.contents_as_str();
+    if !text.is_empty() {
+        lines.push(text);
+    }
+    lines.extend(message.attachments.iter().map(attachment));
+    Some(lines.join("\n"))
+}
+
+/// Describe an event that is not a chat message, such as a call or a membership change.
+pub fn system_text(conversation: &Conversation, event: &Event) -> Option<String> {
+    let sender = name(conversation, &event.sender);
+    let names = |ids: &[ParticipantId]| {
+        ids.iter()
+            .map(|id| name(conversation, id))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+
+    let text = match &event.data {
+        EventData::ChatMessage(_) => return None,
+        EventData::HangoutEvent(hangout) => {
+            let (started, ended) = match hangout.media_type {
+                Some(MediaType::Audio) => ("an audio call", "Audio call"),
+                Some(MediaType::Video) | Some(MediaType::AudioVideo) => {
+                    ("a video call", "Video call")
+                }
+                _ => ("a call", "Call"),
+            };
+            match hangout.typ {
+                HangoutEventType::Start => format!("{} started {}", sender, started),
+                HangoutEventType::End { duration: secs } => {
+                    format!("{} ended after {}", ended, format_duration(secs))
+                }
+            }
+        }
+        EventData::MembershipChange(change) => match change.typ {
+            MembershipChangeType::Join => {
+                format!("{} added {}", sender, names(&change.participants))
+            }
+            MembershipChangeType::Leave if change.participants == [event.sender.clone()] => {
+                format!("{} left", sender)
+            }
+            MembershipChangeType::Leave => {
+                format!("{} removed {}", sender, names(&change.participants))
+            }
+        },
+        EventData::ConversationRename(rename) => {
+            if rename.old.is_empty() {
+                format!("{} named the conversation \"{}\"", sender, rename.new)
+            } else {
+                format!(
+                    "{} renamed the conversation from \"{}\" to \"{}\"",
+                    sender, rename.old, rename.new
+                )
+            }
+        }
+    };
+    Some(text)
+}
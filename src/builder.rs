@@ -0,0 +1,593 @@
+//! Fluent construction of conversations in code.
+//!
+//! Building a [`Conversation`] by hand means filling in its self state, invitation, read
+//! states and every field of its events. The builders here fill in sensible defaults, so that
+//! tests only spell out what they are about:
+//!
+//! ```
+//! use hangouts_rs::builder::{ChatMessageBuilder, ConversationBuilder, EventBuilder};
+//!
+//! let conversation = ConversationBuilder::new("conversation")
+//!     .participant("1", "Alice")
+//!     .participant("2", "Bob")
+//!     .message("1", "Hi Bob")
+//!     .event(EventBuilder::message(
+//!         "2",
+//!         ChatMessageBuilder::new().bold("Hello").text(" Alice"),
+//!     ))
+//!     .event(EventBuilder::join("1", &["3"]))
+//!     .build();
+//!
+//! assert_eq!(conversation.events.len(), 3);
+//! assert_eq!(conversation.participants.len(), 3);
+//! assert!(conversation.events[0].timestamp < conversation.events[1].timestamp);
+//! ```
+//!
+//! Participants are referred to by their Gaia ID, which is also used as their chat ID.
+//! Senders and the participants of calls and membership changes are registered as
+//! participants of the conversation when first seen, without a name.
+
+use crate::{
+    AttachmentSegment, ChatMessage, ChatSegment, Conversation, ConversationRename,
+    ConversationStatus, EmbedItem, Event, EventData, Formatting, HangoutEvent, HangoutEventType,
+    InvitationAffinity, InvitationData, InvitationStatus, LinkSharingStatus, MediaType,
+    MembershipChange, MembershipChangeType, NotificationLevel, Participant, ParticipantId,
+    ParticipantType, Photo, ReadState, SelfEventState, SelfState, Thumbnail, View,
+};
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+
+/// Time of the first event when no start time is given: 2015-01-01T00:00:00Z.
+#[inline]
+pub fn default_start() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2015, 1, 1, 0, 0, 0).unwrap()
+}
+
+/// Create the ID of a participant, using `gaia_id` as its chat ID too.
+#[inline]
+pub fn participant_id(gaia_id: &str) -> ParticipantId {
+    ParticipantId {
+        gaia_id: gaia_id.to_owned(),
+        chat_id: gaia_id.to_owned(),
+    }
+}
+
+/// Builder of a [`Conversation`].
+///
+/// Events without an explicit timestamp are placed one [`interval`](Self::interval) after
+/// the previous event, starting at [`start`](Self::start). Events without an explicit ID
+/// are numbered `event-1`, `event-2`, … in order of addition.
+#[derive(Debug, Clone)]
+pub struct ConversationBuilder {
+    id: String,
+    name: Option<String>,
+    /// Participants, with whether their read state was given.
+    participants: Vec<(ParticipantId, Participant, bool)>,
+    current: Vec<ParticipantId>,
+    events: Vec<Event>,
+    next: DateTime<Utc>,
+    interval: Duration,
+    self_state: SelfState,
+    link_sharing: LinkSharingStatus,
+}
+
+impl ConversationBuilder {
+    /// Start building a conversation with the given ID, without participants or events.
+    pub fn new(id: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: None,
+            participants: Vec::new(),
+            current: Vec::new(),
+            events: Vec::new(),
+            next: default_start(),
+            interval: Duration::minutes(1),
+            self_state: SelfState {
+                status: ConversationStatus::Active,
+                notification_level: NotificationLevel::Ring,
+                invitation: InvitationData {
+                    inviter: participant_id(""),
+                    timestamp: default_start(),
+                    affinity: InvitationAffinity::None,
+                },
+                views: vec![View::Inbox],
+            },
+            link_sharing: LinkSharingStatus::Off,
+        }
+    }
+
+    /// Set the name of the conversation.
+    #[inline]
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// Add a current participant with the given name.
+    ///
+    /// The first participant added is the account owner, who is also the inviter of the
+    /// conversation unless set otherwise.
+    pub fn participant(mut self, gaia_id: &str, name: impl Into<String>) -> Self {
+        let id = participant_id(gaia_id);
+        self.register(&id).fallback_name = Some(name.into());
+        if !self.current.contains(&id) {
+            self.current.push(id);
+        }
+        self
+    }
+
+    /// Add a participant with all its data, without making it a current participant.
+    ///
+    /// The read state of the participant is kept as given.
+    pub fn participant_data(mut self, id: ParticipantId, participant: Participant) -> Self {
+        match self
+            .participants
+            .iter_mut()
+            .find(|(known, ..)| *known == id)
+        {
+            Some(entry) => *entry = (id, participant, true),
+            None => self.participants.push((id, participant, true)),
+        }
+        self
+    }
+
+    /// Set the time of the next event without an explicit timestamp.
+    #[inline]
+    pub fn start(mut self, time: DateTime<Utc>) -> Self {
+        self.next = time;
+        self
+    }
+
+    /// Set the time between consecutive events, one minute by default.
+    #[inline]
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    /// Set the user's state in the conversation, by default active, ringing and in the inbox.
+    #[inline]
+    pub fn self_state(mut self, self_state: SelfState) -> Self {
+        self.self_state = self_state;
+        self
+    }
+
+    /// Set the notification level of the user.
+    #[inline]
+    pub fn notification_level(mut self, level: NotificationLevel) -> Self {
+        self.self_state.notification_level = level;
+        self
+    }
+
+    /// Set the views the conversation is present in.
+    #[inline]
+    pub fn views(mut self, views: Vec<View>) -> Self {
+        self.self_state.views = views;
+        self
+    }
+
+    /// Set the group link sharing status, off by default.
+    #[inline]
+    pub fn link_sharing(mut self, status: LinkSharingStatus) -> Self {
+        self.link_sharing = status;
+        self
+    }
+
+    /// Add a plain text message from `sender`.
+    #[inline]
+    pub fn message(self, sender: &str, text: &str) -> Self {
+        self.event(EventBuilder::message(
+            sender,
+            ChatMessageBuilder::new().text(text),
+        ))
+    }
+
+    /// Add an event.
+    ///
+    /// Membership changes update the current participants, and renames the name of the
+    /// conversation.
+    pub fn event(mut self, event: EventBuilder) -> Self {
+        let timestamp = event.timestamp.unwrap_or(self.next);
+        self.next = timestamp + self.interval;
+        let id = event
+            .id
+            .clone()
+            .unwrap_or_else(|| format!("event-{}", self.events.len() + 1));
+        let event = event.build_with(id, timestamp);
+
+        self.register(&event.sender);
+        match &event.data {
+            EventData::ChatMessage(_) => {}
+            EventData::HangoutEvent(data) => {
+                for id in &data.participants {
+                    self.register(id);
+                }
+            }
+            EventData::MembershipChange(data) => {
+                for id in &data.participants {
+                    self.register(id);
+                    match data.typ {
+                        MembershipChangeType::Join if !self.current.contains(id) => {
+                            self.current.push(id.clone())
+                        }
+                        MembershipChangeType::Join => {}
+                        MembershipChangeType::Leave => self.current.retain(|known| known != id),
+                    }
+                }
+            }
+            EventData::ConversationRename(data) => self.name = Some(data.new.clone()),
+        }
+
+        self.events.push(event);
+        self
+    }
+
+    /// Build the conversation.
+    ///
+    /// Participants without an explicit read state have read up to the last event, and the
+    /// sort timestamp is the time of the last event advancing it.
+    pub fn build(mut self) -> Conversation {
+        let first = self.events.iter().map(|event| event.timestamp).min();
+        let last = self.events.iter().map(|event| event.timestamp).max();
+        for (_, participant, read) in &mut self.participants {
+            if !*read {
+                participant.read_state.timestamp = last.unwrap_or(self.next);
+            }
+        }
+
+        let mut self_state = self.self_state;
+        if self_state.invitation.inviter.gaia_id.is_empty() {
+            if let Some((owner, ..)) = self.participants.first() {
+                self_state.invitation.inviter = owner.clone();
+            }
+            self_state.invitation.timestamp = first.unwrap_or(self.next);
+        }
+
+        let sort_timestamp = self
+            .events
+            .iter()
+            .filter(|event| event.advances_sort_timestamp)
+            .map(|event| event.timestamp)
+            .max()
+            .or(last)
+            .unwrap_or(self.next);
+
+        Conversation {
+            conversation_id: self.id.clone(),
+            id: self.id,
+            name: self.name,
+            current_participants: self.current,
+            participants: self
+                .participants
+                .into_iter()
+                .map(|(id, participant, _)| (id, participant))
+                .collect::<HashMap<_, _>>(),
+            events: self.events,
+            self_state,
+            sort_timestamp,
+            group_link_sharing_status: self.link_sharing,
+        }
+    }
+
+    /// Get the data of participant `id`, adding it without a name if unknown.
+    fn register(&mut self, id: &ParticipantId) -> &mut Participant {
+        let i = match self.participants.iter().position(|(known, ..)| known == id) {
+            Some(i) => i,
+            None => {
+                self.participants.push((
+                    id.clone(),
+                    Participant {
+                        fallback_name: None,
+                        typ: Some(ParticipantType::Gaia),
+                        phone_number: None,
+                        invitation_status: Some(InvitationStatus::Accepted),
+                        new_invitation_status: Some(InvitationStatus::Accepted),
+                        read_state: ReadState {
+                            timestamp: self.next,
+                        },
+                    },
+                    false,
+                ));
+                self.participants.len() - 1
+            }
+        };
+        &mut self.participants[i].1
+    }
+}
+
+/// Builder of an [`Event`].
+///
+/// Used on its own, [`build`](Self::build) gives the event the ID `event` and the
+/// [`default_start`] time unless set. Added to a [`ConversationBuilder`], it gets the next
+/// ID and time of the conversation instead.
+#[derive(Debug, Clone)]
+pub struct EventBuilder {
+    id: Option<String>,
+    sender: ParticipantId,
+    timestamp: Option<DateTime<Utc>>,
+    data: EventData,
+    self_state: SelfEventState,
+    advances_sort_timestamp: bool,
+    version: Option<u64>,
+}
+
+impl EventBuilder {
+    /// Start building an event of `sender` with the given data.
+    pub fn new(sender: &str, data: EventData) -> Self {
+        Self {
+            id: None,
+            sender: participant_id(sender),
+            timestamp: None,
+            data,
+            self_state: SelfEventState {
+                client_generated_id: None,
+                notification_level: None,
+            },
+            advances_sort_timestamp: true,
+            version: None,
+        }
+    }
+
+    /// Start building a chat message of `sender`.
+    #[inline]
+    pub fn message(sender: &str, message: ChatMessageBuilder) -> Self {
+        Self::new(sender, EventData::ChatMessage(message.build()))
+    }
+
+    /// Start building the start of a call by `sender`.
+    #[inline]
+    pub fn call_start(sender: &str, media_type: MediaType) -> Self {
+        Self::new(
+            sender,
+            EventData::HangoutEvent(HangoutEvent {
+                typ: HangoutEventType::Start,
+                media_type: Some(media_type),
+                participants: Vec::new(),
+            }),
+        )
+    }
+
+    /// Start building the end of a call of `sender` with `participants`, lasting `duration`
+    /// seconds.
+    #[inline]
+    pub fn call_end(sender: &str, duration: u64, participants: &[&str]) -> Self {
+        Self::new(
+            sender,
+            EventData::HangoutEvent(HangoutEvent {
+                typ: HangoutEventType::End { duration },
+                media_type: None,
+                participants: participants.iter().map(|id| participant_id(id)).collect(),
+            }),
+        )
+    }
+
+    /// Start building the addition of `participants` by `sender`.
+    #[inline]
+    pub fn join(sender: &str, participants: &[&str]) -> Self {
+        Self::membership_change(sender, MembershipChangeType::Join, participants)
+    }
+
+    /// Start building the departure or removal of `participants`, by `sender`.
+    #[inline]
+    pub fn leave(sender: &str, participants: &[&str]) -> Self {
+        Self::membership_change(sender, MembershipChangeType::Leave, participants)
+    }
+
+    fn membership_change(sender: &str, typ: MembershipChangeType, participants: &[&str]) -> Self {
+        Self::new(
+            sender,
+            EventData::MembershipChange(MembershipChange {
+                typ,
+                participants: participants.iter().map(|id| participant_id(id)).collect(),
+            }),
+        )
+    }
+
+    /// Start building a rename of the conversation by `sender`.
+    #[inline]
+    pub fn rename(sender: &str, old: impl Into<String>, new: impl Into<String>) -> Self {
+        Self::new(
+            sender,
+            EventData::ConversationRename(ConversationRename {
+                old: old.into(),
+                new: new.into(),
+            }),
+        )
+    }
+
+    /// Set the ID of the event.
+    #[inline]
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set the time of the event.
+    #[inline]
+    pub fn timestamp(mut self, timestamp: DateTime<Utc>) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Set the version of the event, by default its timestamp in microseconds.
+    #[inline]
+    pub fn version(mut self, version: u64) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Set the media type of a call event.
+    ///
+    /// Has no effect on other events.
+    #[inline]
+    pub fn media_type(mut self, media_type: MediaType) -> Self {
+        if let EventData::HangoutEvent(data) = &mut self.data {
+            data.media_type = Some(media_type);
+        }
+        self
+    }
+
+    /// Set the client generated ID, present on events sent by the user.
+    #[inline]
+    pub fn client_generated_id(mut self, id: impl Into<String>) -> Self {
+        self.self_state.client_generated_id = Some(id.into());
+        self
+    }
+
+    /// Set the notification level of the event.
+    #[inline]
+    pub fn notification_level(mut self, level: NotificationLevel) -> Self {
+        self.self_state.notification_level = Some(level);
+        self
+    }
+
+    /// Set whether the event advances the sort timestamp of the conversation, `true` by
+    /// default.
+    #[inline]
+    pub fn advances_sort_timestamp(mut self, advances: bool) -> Self {
+        self.advances_sort_timestamp = advances;
+        self
+    }
+
+    /// Build the event.
+    #[inline]
+    pub fn build(self) -> Event {
+        let id = self.id.clone().unwrap_or_else(|| "event".to_owned());
+        let timestamp = self.timestamp.unwrap_or_else(default_start);
+        self.build_with(id, timestamp)
+    }
+
+    fn build_with(self, id: String, timestamp: DateTime<Utc>) -> Event {
+        Event {
+            id,
+            sender: self.sender,
+            timestamp,
+            data: self.data,
+            self_state: self.self_state,
+            advances_sort_timestamp: self.advances_sort_timestamp,
+            version: self
+                .version
+                .unwrap_or_else(|| timestamp.timestamp_micros().max(0) as u64),
+        }
+    }
+}
+
+/// Builder of a [`ChatMessage`], segment by segment.
+#[derive(Debug, Clone, Default)]
+pub struct ChatMessageBuilder {
+    message: ChatMessage,
+}
+
+impl ChatMessageBuilder {
+    /// Start building an empty message.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add unformatted text.
+    #[inline]
+    pub fn text(self, text: impl Into<String>) -> Self {
+        self.formatted(text, Formatting::default())
+    }
+
+    /// Add bold text.
+    #[inline]
+    pub fn bold(self, text: impl Into<String>) -> Self {
+        self.formatted(
+            text,
+            Formatting {
+                bold: true,
+                ..Formatting::default()
+            },
+        )
+    }
+
+    /// Add italic text.
+    #[inline]
+    pub fn italics(self, text: impl Into<String>) -> Self {
+        self.formatted(
+            text,
+            Formatting {
+                italics: true,
+                ..Formatting::default()
+            },
+        )
+    }
+
+    /// Add text with the given formatting.
+    #[inline]
+    pub fn formatted(mut self, text: impl Into<String>, format: Formatting) -> Self {
+        self.message.contents.push(ChatSegment::Text {
+            text: text.into(),
+            format,
+        });
+        self
+    }
+
+    /// Add a link to `target` displayed as `text`.
+    #[inline]
+    pub fn link(mut self, text: impl Into<String>, target: impl Into<String>) -> Self {
+        self.message.contents.push(ChatSegment::Link {
+            text: text.into(),
+            target: target.into(),
+            display_url: None,
+            format: Formatting::default(),
+        });
+        self
+    }
+
+    /// Add a line break.
+    #[inline]
+    pub fn line_break(mut self) -> Self {
+        self.message.contents.push(ChatSegment::LinkBreak {
+            text: Some("\n".to_owned()),
+            format: Formatting::default(),
+        });
+        self
+    }
+
+    /// Add an attachment.
+    #[inline]
+    pub fn attachment(mut self, attachment: AttachmentSegment) -> Self {
+        self.message.attachments.push(attachment);
+        self
+    }
+
+    /// Add a photo attachment with the given ID and URL.
+    pub fn photo(self, id: impl Into<String>, url: impl Into<String>) -> Self {
+        let id = id.into();
+        let url = url.into();
+        self.attachment(AttachmentSegment {
+            id: id.clone(),
+            item: EmbedItem {
+                id: Some(id.clone()),
+                photo: Some(Photo {
+                    media_type: MediaType::Photo,
+                    thumbnail: Thumbnail {
+                        height: 0,
+                        width: 0,
+                        url: None,
+                    },
+                    album_id: String::new(),
+                    photo_id: id,
+                    stream_id: Vec::new(),
+                    original_url: url.clone(),
+                    url,
+                    download_url: None,
+                    owner_obfuscated_id: String::new(),
+                }),
+                place: None,
+                thing: None,
+            },
+        })
+    }
+
+    /// Build the message.
+    #[inline]
+    pub fn build(self) -> ChatMessage {
+        self.message
+    }
+}
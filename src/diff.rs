@@ -0,0 +1,452 @@
+//! Structural differences between two exports.
+//!
+//! A [`Diff`] lists the conversations added to and removed from an export, and the changes
+//! within the conversations present in both: renames, settings of the user, participants
+//! joining, leaving or changing names, and events added, removed or modified, matched by
+//! [`Event::id`].
+//!
+//! Its [`Display`](fmt::Display) implementation renders the differences for people, in the
+//! spirit of a unified diff, and with the `serde-impl` feature it serializes to JSON.
+
+use crate::{
+    Conversation, Event, Hangouts, LinkSharingStatus, NotificationLevel, ParticipantId, View,
+};
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+/// Differences between an old and a new export.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct Diff {
+    /// Conversations only present in the new export, in its order.
+    pub added: Vec<ConversationSummary>,
+    /// Conversations only present in the old export, in its order.
+    pub removed: Vec<ConversationSummary>,
+    /// Conversations present in both exports that differ, in the order of the new export.
+    pub changed: Vec<ConversationDiff>,
+}
+
+impl Diff {
+    /// Compare the `old` export to the `new` one.
+    pub fn new(old: &Hangouts, new: &Hangouts) -> Self {
+        let olds: HashMap<&str, &Conversation> = old
+            .conversations
+            .iter()
+            .map(|conversation| (conversation.conversation_id.as_str(), conversation))
+            .collect();
+        let news: HashMap<&str, &Conversation> = new
+            .conversations
+            .iter()
+            .map(|conversation| (conversation.conversation_id.as_str(), conversation))
+            .collect();
+
+        let mut diff = Self::default();
+        for conversation in &new.conversations {
+            match olds.get(conversation.conversation_id.as_str()) {
+                Some(old) => {
+                    let changed = ConversationDiff::new(old, conversation);
+                    if !changed.changes.is_empty() {
+                        diff.changed.push(changed);
+                    }
+                }
+                None => diff.added.push(ConversationSummary::new(conversation)),
+            }
+        }
+        diff.removed = old
+            .conversations
+            .iter()
+            .filter(|conversation| !news.contains_key(conversation.conversation_id.as_str()))
+            .map(ConversationSummary::new)
+            .collect();
+        diff
+    }
+
+    /// Returns `true` if the exports have no differences.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        for conversation in &self.added {
+            writeln!(f, "+ {}", conversation)?;
+        }
+        for conversation in &self.removed {
+            writeln!(f, "- {}", conversation)?;
+        }
+        for conversation in &self.changed {
+            write!(f, "{}", conversation)?;
+        }
+        Ok(())
+    }
+}
+
+/// A conversation added or removed as a whole.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct ConversationSummary {
+    pub conversation_id: String,
+    /// Display name of the conversation.
+    pub name: String,
+    /// Number of events of the conversation.
+    pub events: usize,
+}
+
+impl ConversationSummary {
+    fn new(conversation: &Conversation) -> Self {
+        Self {
+            conversation_id: conversation.conversation_id.clone(),
+            name: conversation.display_name(),
+            events: conversation.events.len(),
+        }
+    }
+}
+
+impl fmt::Display for ConversationSummary {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{} ({}, {} events)",
+            self.name, self.conversation_id, self.events
+        )
+    }
+}
+
+/// Changes within a conversation present in both exports.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct ConversationDiff {
+    pub conversation_id: String,
+    /// Display name of the conversation in the new export.
+    pub name: String,
+    /// Changes to the conversation, then its participants, then its events by time.
+    pub changes: Vec<Change>,
+}
+
+impl ConversationDiff {
+    /// Compare the `old` and `new` copies of a conversation.
+    pub fn new(old: &Conversation, new: &Conversation) -> Self {
+        let mut changes = Vec::new();
+
+        if old.name != new.name {
+            changes.push(Change::Renamed {
+                old: old.name.clone(),
+                new: new.name.clone(),
+            });
+        }
+        let (old_state, new_state) = (&old.self_state, &new.self_state);
+        if old_state.notification_level != new_state.notification_level {
+            changes.push(Change::NotificationLevel {
+                old: old_state.notification_level.clone(),
+                new: new_state.notification_level.clone(),
+            });
+        }
+        if old_state.views != new_state.views {
+            changes.push(Change::Views {
+                old: old_state.views.clone(),
+                new: new_state.views.clone(),
+            });
+        }
+        if old.group_link_sharing_status != new.group_link_sharing_status {
+            changes.push(Change::LinkSharing {
+                old: old.group_link_sharing_status.clone(),
+                new: new.group_link_sharing_status.clone(),
+            });
+        }
+
+        let ids: BTreeSet<&ParticipantId> = old
+            .participants
+            .keys()
+            .chain(new.participants.keys())
+            .collect();
+        for id in ids {
+            let name = |conversation: &Conversation| {
+                conversation.participant_name(id).map(String::to_owned)
+            };
+            let participant = id.clone();
+            match (old.participants.get(id), new.participants.get(id)) {
+                (None, Some(_)) => changes.push(Change::ParticipantAdded {
+                    participant,
+                    name: name(new),
+                }),
+                (Some(_), None) => changes.push(Change::ParticipantRemoved {
+                    participant,
+                    name: name(old),
+                }),
+                (Some(_), Some(_)) if name(old) != name(new) => {
+                    changes.push(Change::ParticipantRenamed {
+                        participant,
+                        old: name(old),
+                        new: name(new),
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        let old_events: HashMap<&str, &Event> = old
+            .events
+            .iter()
+            .map(|event| (event.id.as_str(), event))
+            .collect();
+        let new_events: HashMap<&str, &Event> = new
+            .events
+            .iter()
+            .map(|event| (event.id.as_str(), event))
+            .collect();
+        let mut events: Vec<(DateTime<Utc>, Change)> = Vec::new();
+        for event in &old.events {
+            if !new_events.contains_key(event.id.as_str()) {
+                events.push((
+                    event.timestamp,
+                    Change::EventRemoved {
+                        event_id: event.id.clone(),
+                        timestamp: event.timestamp,
+                    },
+                ));
+            }
+        }
+        for event in &new.events {
+            match old_events.get(event.id.as_str()) {
+                None => events.push((
+                    event.timestamp,
+                    Change::EventAdded {
+                        event_id: event.id.clone(),
+                        timestamp: event.timestamp,
+                    },
+                )),
+                Some(old) if *old != event => {
+                    events.push((event.timestamp, Change::modified(old, event)))
+                }
+                Some(_) => {}
+            }
+        }
+        events.sort_by_key(|(timestamp, _)| *timestamp);
+        changes.extend(events.into_iter().map(|(_, change)| change));
+
+        Self {
+            conversation_id: new.conversation_id.clone(),
+            name: new.display_name(),
+            changes,
+        }
+    }
+}
+
+impl fmt::Display for ConversationDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        writeln!(f, "~ {} ({})", self.name, self.conversation_id)?;
+        for change in &self.changes {
+            writeln!(f, "    {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+/// A change within a conversation.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(
+    feature = "serde-impl",
+    serde(tag = "change", rename_all = "snake_case")
+)]
+pub enum Change {
+    /// The conversation was renamed.
+    Renamed {
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// The notification level of the user changed.
+    NotificationLevel {
+        old: NotificationLevel,
+        new: NotificationLevel,
+    },
+    /// The views the conversation is present in changed.
+    Views { old: Vec<View>, new: Vec<View> },
+    /// The group link sharing status changed.
+    LinkSharing {
+        old: LinkSharingStatus,
+        new: LinkSharingStatus,
+    },
+    /// A participant is only present in the new export.
+    ParticipantAdded {
+        participant: ParticipantId,
+        name: Option<String>,
+    },
+    /// A participant is only present in the old export.
+    ParticipantRemoved {
+        participant: ParticipantId,
+        name: Option<String>,
+    },
+    /// The fallback name of a participant changed.
+    ParticipantRenamed {
+        participant: ParticipantId,
+        old: Option<String>,
+        new: Option<String>,
+    },
+    /// An event is only present in the new export.
+    EventAdded {
+        event_id: String,
+        timestamp: DateTime<Utc>,
+    },
+    /// An event is only present in the old export.
+    EventRemoved {
+        event_id: String,
+        timestamp: DateTime<Utc>,
+    },
+    /// An event is present in both exports with different fields.
+    EventModified {
+        event_id: String,
+        /// Time of the event in the new export.
+        timestamp: DateTime<Utc>,
+        fields: Vec<EventField>,
+        /// Text of the message in the old export, if the text of a chat message changed.
+        old_text: Option<String>,
+        /// Text of the message in the new export, if the text of a chat message changed.
+        new_text: Option<String>,
+    },
+}
+
+impl Change {
+    fn modified(old: &Event, new: &Event) -> Self {
+        let mut fields = Vec::new();
+        if old.sender != new.sender {
+            fields.push(EventField::Sender);
+        }
+        if old.timestamp != new.timestamp {
+            fields.push(EventField::Timestamp);
+        }
+        if old.data != new.data {
+            fields.push(EventField::Data);
+        }
+        if old.self_state != new.self_state {
+            fields.push(EventField::SelfState);
+        }
+        if old.advances_sort_timestamp != new.advances_sort_timestamp {
+            fields.push(EventField::AdvancesSortTimestamp);
+        }
+        if old.version != new.version {
+            fields.push(EventField::Version);
+        }
+
+        let texts = old
+            .data
+            .as_chat_message()
+            .zip(new.data.as_chat_message())
+            .map(|(old, new)| (old.contents_as_str(), new.contents_as_str()))
+            .filter(|(old, new)| old != new);
+        let (old_text, new_text) = match texts {
+            Some((old, new)) => (Some(old), Some(new)),
+            None => (None, None),
+        };
+
+        Self::EventModified {
+            event_id: new.id.clone(),
+            timestamp: new.timestamp,
+            fields,
+            old_text,
+            new_text,
+        }
+    }
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let name = |name: &Option<String>| match name {
+            Some(name) => format!("{:?}", name),
+            None => "no name".to_owned(),
+        };
+        match self {
+            Self::Renamed { old, new } => write!(f, "name: {} -> {}", name(old), name(new)),
+            Self::NotificationLevel { old, new } => {
+                write!(f, "notification level: {:?} -> {:?}", old, new)
+            }
+            Self::Views { old, new } => write!(f, "views: {:?} -> {:?}", old, new),
+            Self::LinkSharing { old, new } => write!(f, "link sharing: {:?} -> {:?}", old, new),
+            Self::ParticipantAdded {
+                participant,
+                name: n,
+            } => {
+                write!(f, "+ participant {} ({})", participant.gaia_id, name(n))
+            }
+            Self::ParticipantRemoved {
+                participant,
+                name: n,
+            } => {
+                write!(f, "- participant {} ({})", participant.gaia_id, name(n))
+            }
+            Self::ParticipantRenamed {
+                participant,
+                old,
+                new,
+            } => write!(
+                f,
+                "participant {}: {} -> {}",
+                participant.gaia_id,
+                name(old),
+                name(new)
+            ),
+            Self::EventAdded {
+                event_id,
+                timestamp,
+            } => write!(f, "+ event {} at {}", event_id, timestamp.to_rfc3339()),
+            Self::EventRemoved {
+                event_id,
+                timestamp,
+            } => write!(f, "- event {} at {}", event_id, timestamp.to_rfc3339()),
+            Self::EventModified {
+                event_id,
+                timestamp,
+                fields,
+                old_text,
+                new_text,
+            } => {
+                let fields: Vec<_> = fields.iter().map(EventField::as_str).collect();
+                write!(
+                    f,
+                    "~ event {} at {}: {}",
+                    event_id,
+                    timestamp.to_rfc3339(),
+                    fields.join(", ")
+                )?;
+                if let (Some(old), Some(new)) = (old_text, new_text) {
+                    write!(f, "\n        - {:?}\n        + {:?}", old, new)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// A field of an [`Event`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde-impl", serde(rename_all = "snake_case"))]
+pub enum EventField {
+    Sender,
+    Timestamp,
+    Data,
+    SelfState,
+    AdvancesSortTimestamp,
+    Version,
+}
+
+impl EventField {
+    /// Get the name of the field.
+    #[inline]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sender => "sender",
+            Self::Timestamp => "timestamp",
+            Self::Data => "data",
+            Self::SelfState => "self_state",
+            Self::AdvancesSortTimestamp => "advances_sort_timestamp",
+            Self::Version => "version",
+        }
+    }
+}
@@ -111,7 +111,7 @@ impl EventData {
 }
 
 /// A regular chat message event body.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 #[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
 pub struct ChatMessage {
     /// The textual contents of the message.
@@ -168,10 +168,10 @@ impl ChatSegment {
     #[inline]
     pub fn text(&self) -> &str {
         match self {
-            Self::Text { text, .. } => &text,
-            Self::Link { text, .. } => &text,
+            Self::Text { text, .. } => text,
+            Self::Link { text, .. } => text,
             Self::LinkBreak { text, .. } => match text {
-                Some(text) => &text,
+                Some(text) => text,
                 None => "\n",
             },
         }
@@ -207,7 +207,7 @@ impl ChatSegment {
 }
 
 /// Formatting data for a text segment.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 #[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
 pub struct Formatting {
     /// Indicates whether the text is bold or not.
@@ -244,6 +244,20 @@ pub struct EmbedItem {
     pub thing: Option<ThingV2>,
 }
 
+impl EmbedItem {
+    /// Returns the URL of the photo, place or thing item, in that order of preference.
+    #[inline]
+    pub fn url(&self) -> Option<&str> {
+        if let Some(photo) = &self.photo {
+            Some(&photo.url)
+        } else if let Some(place) = &self.place {
+            Some(&place.url)
+        } else {
+            self.thing.as_ref().map(|thing| thing.url.as_str())
+        }
+    }
+}
+
 /// Data about a photo or video attachment item.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
@@ -319,6 +333,36 @@ pub struct Address {
     pub postal_code: Option<String>,
 }
 
+impl Address {
+    /// Returns the address on a single line, as in "street, locality, region postal code,
+    /// country", or [`None`] if all parts are missing.
+    pub fn formatted(&self) -> Option<String> {
+        let region = [&self.region, &self.postal_code]
+            .iter()
+            .filter_map(|part| part.as_deref())
+            .filter(|part| !part.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        let parts: Vec<&str> = [
+            self.street.as_deref(),
+            self.locality.as_deref(),
+            Some(region.as_str()),
+            self.country.as_deref(),
+        ]
+        .iter()
+        .flatten()
+        .map(|part| part.trim())
+        .filter(|part| !part.is_empty())
+        .collect();
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join(", "))
+        }
+    }
+}
+
 /// A pair of latitude and longitude values.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
@@ -392,6 +436,28 @@ impl HangoutEventType {
     }
 }
 
+/// A complete hangout call, made of the end event of the call and its start event, if present.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Call<'a> {
+    /// Event signalling the start of the call.
+    pub start_event: Option<&'a Event>,
+    /// Event signalling the end of the call.
+    pub end_event: &'a Event,
+    /// Time the call started.
+    ///
+    /// This is the time of the start event if present, and otherwise derived from the end
+    /// event and the duration.
+    pub start: DateTime<Utc>,
+    /// Time the call ended.
+    pub end: DateTime<Utc>,
+    /// Duration of the call in seconds.
+    pub duration: u64,
+    /// The media type of the call.
+    pub media_type: Option<&'a MediaType>,
+    /// Participants of the start and end events, sorted and without duplicates.
+    pub participants: Vec<&'a ParticipantId>,
+}
+
 /// The type of a media attachment or hangout event.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
@@ -0,0 +1,46 @@
+//! Content lines shared by the iCalendar (RFC 5545) and vCard (RFC 6350) formats.
+
+use crate::export::ExportError;
+
+use std::io::Write;
+
+/// Maximum length of content lines in octets, excluding the line break.
+const MAX_LINE_LEN: usize = 75;
+
+/// Write a content line, folding it to lines of at most [`MAX_LINE_LEN`] octets.
+pub(crate) fn write_line<W: Write>(writer: &mut W, line: &str) -> Result<(), ExportError> {
+    let mut rest = line;
+    let mut max = MAX_LINE_LEN;
+    loop {
+        if rest.len() <= max {
+            writer.write_all(rest.as_bytes())?;
+            writer.write_all(b"\r\n")?;
+            return Ok(());
+        }
+        let mut split = max;
+        while !rest.is_char_boundary(split) {
+            split -= 1;
+        }
+        writer.write_all(&rest.as_bytes()[..split])?;
+        // Continuation lines start with a space, which counts towards their length.
+        writer.write_all(b"\r\n ")?;
+        rest = &rest[split..];
+        max = MAX_LINE_LEN - 1;
+    }
+}
+
+/// Escape `s` for use in a TEXT property value.
+pub(crate) fn escape_text(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            ';' => out.push_str("\\;"),
+            ',' => out.push_str("\\,"),
+            '\n' => out.push_str("\\n"),
+            '\r' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,205 @@
+//! GeoJSON and KML export of shared places.
+//!
+//! Every place attachment becomes a point feature carrying the sender, time, conversation,
+//! place name, formatted address and URL of the share, ready to be loaded into GIS tools such
+//! as QGIS or uMap. GeoJSON output requires the `json` feature.
+
+use crate::export::{escape_xml, ExportError};
+use crate::{Hangouts, ParticipantId, PlaceV2};
+
+use std::io::Write;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+
+/// A place shared in a chat message.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SharedPlace<'a> {
+    /// The shared place.
+    pub place: &'a PlaceV2,
+    /// ID of the attachment.
+    pub attachment_id: &'a str,
+    /// ID of the conversation the place was shared in.
+    pub conversation_id: &'a str,
+    /// Display name of the conversation.
+    pub conversation_name: String,
+    /// Sender of the place.
+    pub sender: &'a ParticipantId,
+    /// Name of the sender, if known.
+    pub sender_name: Option<&'a str>,
+    /// Time the place was shared.
+    pub timestamp: DateTime<Utc>,
+}
+
+impl SharedPlace<'_> {
+    /// Name of the place, falling back to the name of its address.
+    #[inline]
+    pub fn name(&self) -> Option<&str> {
+        self.place
+            .name
+            .as_deref()
+            .or(self.place.address.name.as_deref())
+    }
+}
+
+/// Collect all places shared in `hangouts`, in conversation and event order.
+pub fn shared_places(hangouts: &Hangouts) -> Vec<SharedPlace<'_>> {
+    let mut places = Vec::new();
+    for conversation in &hangouts.conversations {
+        let conversation_name = conversation.display_name();
+        for event in &conversation.events {
+            let message = match event.data.as_chat_message() {
+                Some(message) => message,
+                None => continue,
+            };
+            for attachment in &message.attachments {
+                if let Some(place) = &attachment.item.place {
+                    places.push(SharedPlace {
+                        place,
+                        attachment_id: &attachment.id,
+                        conversation_id: &conversation.conversation_id,
+                        conversation_name: conversation_name.clone(),
+                        sender: &event.sender,
+                        sender_name: conversation
+                            .participant_name(&event.sender)
+                            .map(String::as_str),
+                        timestamp: event.timestamp,
+                    });
+                }
+            }
+        }
+    }
+    places
+}
+
+/// Write all shared places in `hangouts` as a GeoJSON FeatureCollection.
+#[cfg(feature = "json")]
+pub fn write_geojson<W: Write>(mut writer: W, hangouts: &Hangouts) -> Result<(), ExportError> {
+    use serde_json::json;
+
+    let features: Vec<_> = shared_places(hangouts)
+        .iter()
+        .map(|shared| {
+            json!({
+                "type": "Feature",
+                "id": shared.attachment_id,
+                // GeoJSON positions are longitude first.
+                "geometry": {
+                    "type": "Point",
+                    "coordinates": [shared.place.geo.longitude, shared.place.geo.latitude],
+                },
+                "properties": {
+                    "name": shared.name(),
+                    "address": shared.place.address.formatted(),
+                    "url": shared.place.url,
+                    "sender_gaia_id": shared.sender.gaia_id,
+                    "sender_name": shared.sender_name,
+                    "timestamp": timestamp(&shared.timestamp),
+                    "conversation_id": shared.conversation_id,
+                    "conversation_name": shared.conversation_name,
+                },
+            })
+        })
+        .collect();
+
+    serde_json::to_writer_pretty(
+        &mut writer,
+        &json!({
+            "type": "FeatureCollection",
+            "features": features,
+        }),
+    )?;
+    writeln!(writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write all shared places in `hangouts` as a KML document, with one folder per conversation.
+pub fn write_kml<W: Write>(mut writer: W, hangouts: &Hangouts) -> Result<(), ExportError> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<kml xmlns="http://www.opengis.net/kml/2.2">"#)?;
+    writeln!(writer, "  <Document>")?;
+    writeln!(writer, "    <name>Hangouts places</name>")?;
+
+    let places = shared_places(hangouts);
+    let mut current = None;
+    for shared in &places {
+        if current != Some(shared.conversation_id) {
+            if current.is_some() {
+                writeln!(writer, "    </Folder>")?;
+            }
+            writeln!(writer, "    <Folder>")?;
+            writeln!(
+                writer,
+                "      <name>{}</name>",
+                escape_xml(&shared.conversation_name)
+            )?;
+            current = Some(shared.conversation_id);
+        }
+        write_placemark(&mut writer, shared)?;
+    }
+    if current.is_some() {
+        writeln!(writer, "    </Folder>")?;
+    }
+
+    writeln!(writer, "  </Document>")?;
+    writeln!(writer, "</kml>")?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_placemark<W: Write>(writer: &mut W, shared: &SharedPlace) -> Result<(), ExportError> {
+    writeln!(
+        writer,
+        r#"      <Placemark id="{}">"#,
+        escape_xml(shared.attachment_id)
+    )?;
+    if let Some(name) = shared.name() {
+        writeln!(writer, "        <name>{}</name>", escape_xml(name))?;
+    }
+    if let Some(address) = shared.place.address.formatted() {
+        writeln!(
+            writer,
+            "        <address>{}</address>",
+            escape_xml(&address)
+        )?;
+    }
+    writeln!(
+        writer,
+        "        <TimeStamp><when>{}</when></TimeStamp>",
+        timestamp(&shared.timestamp)
+    )?;
+
+    writeln!(writer, "        <ExtendedData>")?;
+    let data = [
+        ("url", Some(shared.place.url.as_str())),
+        ("sender_gaia_id", Some(shared.sender.gaia_id.as_str())),
+        ("sender_name", shared.sender_name),
+        ("conversation_id", Some(shared.conversation_id)),
+        ("conversation_name", Some(shared.conversation_name.as_str())),
+    ];
+    for (name, value) in data.iter() {
+        if let Some(value) = value {
+            writeln!(
+                writer,
+                r#"          <Data name="{}"><value>{}</value></Data>"#,
+                name,
+                escape_xml(value)
+            )?;
+        }
+    }
+    writeln!(writer, "        </ExtendedData>")?;
+
+    // KML coordinates are longitude first.
+    writeln!(
+        writer,
+        "        <Point><coordinates>{},{}</coordinates></Point>",
+        shared.place.geo.longitude, shared.place.geo.latitude
+    )?;
+    writeln!(writer, "      </Placemark>")?;
+    Ok(())
+}
+
+#[inline]
+fn timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
@@ -0,0 +1,134 @@
+//! iCalendar export of hangout calls.
+//!
+//! Every call (see [`Conversation::calls`]) becomes an RFC 5545 `VEVENT` spanning the call,
+//! summarized with the conversation name. The participants of the call are listed as
+//! attendees addressed as `<gaia_id>@<domain>`, with the initiator of the call as organizer,
+//! and the media type of the call is given in the categories.
+
+use crate::export::contentline::{escape_text, write_line};
+use crate::export::ExportError;
+use crate::util::format_duration;
+use crate::{Call, Conversation, Hangouts, MediaType, ParticipantId};
+
+use std::io::Write;
+
+use chrono::{DateTime, Utc};
+
+/// Options for iCalendar export.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IcalOptions {
+    /// Domain used for attendee addresses and event UIDs.
+    pub domain: String,
+}
+
+impl Default for IcalOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            domain: "hangouts.invalid".to_owned(),
+        }
+    }
+}
+
+/// Write a calendar of all calls in `hangouts`.
+pub fn write_calendar<W: Write>(
+    mut writer: W,
+    hangouts: &Hangouts,
+    options: &IcalOptions,
+) -> Result<(), ExportError> {
+    write_line(&mut writer, "BEGIN:VCALENDAR")?;
+    write_line(&mut writer, "VERSION:2.0")?;
+    write_line(&mut writer, "PRODID:-//hangouts-rs//Hangouts calls//EN")?;
+    write_line(&mut writer, "CALSCALE:GREGORIAN")?;
+    for conversation in &hangouts.conversations {
+        for call in conversation.calls() {
+            write_event(&mut writer, conversation, &call, options)?;
+        }
+    }
+    write_line(&mut writer, "END:VCALENDAR")?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_event<W: Write>(
+    writer: &mut W,
+    conversation: &Conversation,
+    call: &Call,
+    options: &IcalOptions,
+) -> Result<(), ExportError> {
+    let kind = match call.media_type {
+        Some(MediaType::Audio) => Some("Audio"),
+        Some(MediaType::Video) | Some(MediaType::AudioVideo) => Some("Video"),
+        _ => None,
+    };
+
+    write_line(writer, "BEGIN:VEVENT")?;
+    write_line(
+        writer,
+        &format!(
+            "UID:{}@{}",
+            escape_text(&call.end_event.id),
+            escape_text(&options.domain)
+        ),
+    )?;
+    write_line(writer, &format!("DTSTAMP:{}", timestamp(&call.end)))?;
+    write_line(writer, &format!("DTSTART:{}", timestamp(&call.start)))?;
+    write_line(writer, &format!("DTEND:{}", timestamp(&call.end)))?;
+    write_line(
+        writer,
+        &format!("SUMMARY:{}", escape_text(&conversation.display_name())),
+    )?;
+    write_line(
+        writer,
+        &format!(
+            "DESCRIPTION:{}",
+            escape_text(&format!(
+                "Hangouts {}call, {}",
+                kind.map(|kind| format!("{} ", kind.to_lowercase()))
+                    .unwrap_or_default(),
+                format_duration(call.duration)
+            ))
+        ),
+    )?;
+    match kind {
+        Some(kind) => write_line(writer, &format!("CATEGORIES:Hangouts,{}", kind))?,
+        None => write_line(writer, "CATEGORIES:Hangouts")?,
+    }
+
+    let organizer = call
+        .start_event
+        .map(|event| &event.sender)
+        .unwrap_or(&call.end_event.sender);
+    write_line(
+        writer,
+        &format!("ORGANIZER{}", cal_address(conversation, organizer, options)),
+    )?;
+    for id in &call.participants {
+        write_line(
+            writer,
+            &format!("ATTENDEE{}", cal_address(conversation, id, options)),
+        )?;
+    }
+    write_line(writer, "END:VEVENT")?;
+    Ok(())
+}
+
+/// Parameters and value of an organizer or attendee property, starting with `;` or `:`.
+fn cal_address(conversation: &Conversation, id: &ParticipantId, options: &IcalOptions) -> String {
+    let address = format!("mailto:{}@{}", id.gaia_id, options.domain);
+    match conversation.participant_name(id) {
+        // Parameter values cannot contain double quotes, even when quoted.
+        Some(name) => format!(
+            ";CN=\"{}\":{}",
+            name.replace('"', "'")
+                .replace(|c: char| c.is_control(), " "),
+            address
+        ),
+        None => format!(":{}", address),
+    }
+}
+
+#[inline]
+fn timestamp(timestamp: &DateTime<Utc>) -> String {
+    timestamp.format("%Y%m%dT%H%M%SZ").to_string()
+}
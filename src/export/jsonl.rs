@@ -0,0 +1,228 @@
+//! JSON Lines event stream export.
+//!
+//! Every [`Event`] is written as one JSON object on its own line, denormalized with the
+//! conversation and sender it belongs to, so the output can be fed directly into tools such
+//! as `jq` or DuckDB.
+//!
+//! # Schema
+//!
+//! Every record carries these fields:
+//!
+//! | Field               | Type             | Description                                  |
+//! |---------------------|------------------|----------------------------------------------|
+//! | `version`           | number           | Schema version, currently [`SCHEMA_VERSION`]. |
+//! | `conversation_id`   | string           | ID of the enclosing conversation.            |
+//! | `conversation_name` | string or `null` | Name of the conversation, if it has one.     |
+//! | `event_id`          | string           | ID of the event.                             |
+//! | `timestamp`         | string           | RFC 3339 time of the event, in UTC.          |
+//! | `sender_gaia_id`    | string           | Gaia ID of the sender.                       |
+//! | `sender_chat_id`    | string           | Chat ID of the sender.                       |
+//! | `sender_name`       | string or `null` | Fallback name of the sender, if known.       |
+//! | `kind`              | string           | One of the event kinds listed below.         |
+//!
+//! The remaining fields depend on `kind`:
+//!
+//! - `chat_message`: `text` (string), `links` (list of link targets) and `attachments` (list
+//!   of objects with `id`, `type` — one of `photo`, `place`, `thing` or `unknown` — and
+//!   `url`, which may be `null`).
+//! - `hangout_event`: `call` (`start` or `end`), `duration_secs` (number, or `null` for
+//!   `start`), `media_type` (see below, or `null`) and `participants` (list of Gaia IDs).
+//! - `membership_change`: `change` (`join` or `leave`) and `participants` (list of Gaia IDs).
+//! - `conversation_rename`: `old_name` and `new_name` (strings).
+//!
+//! Media types are written as `audio`, `video`, `audio_video`, `photo` or `animated_photo`.
+
+use crate::export::ExportError;
+use crate::raw::stream::{self, StreamError};
+use crate::{
+    AttachmentSegment, ChatSegment, Conversation, Event, EventData, HangoutEventType, Hangouts,
+    MediaType, MembershipChangeType, ParticipantId,
+};
+
+use std::convert::TryFrom;
+use std::io::{Read, Write};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Version of the record schema written by this module.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// Write one record per event of every conversation in `hangouts`.
+#[inline]
+pub fn write_hangouts<W: Write>(mut writer: W, hangouts: &Hangouts) -> Result<(), ExportError> {
+    for conversation in &hangouts.conversations {
+        write_conversation(&mut writer, conversation)?;
+    }
+    Ok(())
+}
+
+/// Write one record per event of `conversation`.
+pub fn write_conversation<W: Write>(
+    mut writer: W,
+    conversation: &Conversation,
+) -> Result<(), ExportError> {
+    for event in &conversation.events {
+        serde_json::to_writer(&mut writer, &Record::new(conversation, event))?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+/// Parse Hangouts.json from `reader` and write its events to `writer` without holding more
+/// than a single conversation in memory.
+pub fn write_stream<R: Read, W: Write>(reader: R, mut writer: W) -> Result<(), ExportError> {
+    let res = stream::for_each_conversation(reader, |raw| -> Result<(), ExportError> {
+        let conversation = Conversation::try_from(raw)?;
+        write_conversation(&mut writer, &conversation)
+    });
+
+    match res {
+        Ok(()) => writer.flush().map_err(From::from),
+        Err(StreamError::Json(err)) => Err(err.into()),
+        Err(StreamError::Callback(err)) => Err(err),
+    }
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    version: u32,
+    conversation_id: &'a str,
+    conversation_name: Option<&'a str>,
+    event_id: &'a str,
+    timestamp: &'a DateTime<Utc>,
+    sender_gaia_id: &'a str,
+    sender_chat_id: &'a str,
+    sender_name: Option<&'a str>,
+    #[serde(flatten)]
+    body: Body<'a>,
+}
+
+impl<'a> Record<'a> {
+    fn new(conversation: &'a Conversation, event: &'a Event) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            conversation_id: &conversation.conversation_id,
+            conversation_name: conversation.name.as_deref(),
+            event_id: &event.id,
+            timestamp: &event.timestamp,
+            sender_gaia_id: &event.sender.gaia_id,
+            sender_chat_id: &event.sender.chat_id,
+            sender_name: conversation
+                .participant_name(&event.sender)
+                .map(String::as_str),
+            body: Body::new(&event.data),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Body<'a> {
+    ChatMessage {
+        text: String,
+        links: Vec<&'a str>,
+        attachments: Vec<Attachment<'a>>,
+    },
+    HangoutEvent {
+        call: &'static str,
+        duration_secs: Option<u64>,
+        media_type: Option<&'static str>,
+        participants: Vec<&'a str>,
+    },
+    MembershipChange {
+        change: &'static str,
+        participants: Vec<&'a str>,
+    },
+    ConversationRename {
+        old_name: &'a str,
+        new_name: &'a str,
+    },
+}
+
+impl<'a> Body<'a> {
+    fn new(data: &'a EventData) -> Self {
+        match data {
+            EventData::ChatMessage(message) => Self::ChatMessage {
+                text: message.contents_as_str(),
+                links: message
+                    .contents
+                    .iter()
+                    .filter_map(|seg| match seg {
+                        ChatSegment::Link { target, .. } => Some(target.as_str()),
+                        _ => None,
+                    })
+                    .collect(),
+                attachments: message.attachments.iter().map(Attachment::new).collect(),
+            },
+            EventData::HangoutEvent(hangout) => {
+                let (call, duration_secs) = match hangout.typ {
+                    HangoutEventType::Start => ("start", None),
+                    HangoutEventType::End { duration } => ("end", Some(duration)),
+                };
+                Self::HangoutEvent {
+                    call,
+                    duration_secs,
+                    media_type: hangout.media_type.as_ref().map(media_type_str),
+                    participants: gaia_ids(&hangout.participants),
+                }
+            }
+            EventData::MembershipChange(change) => Self::MembershipChange {
+                change: match change.typ {
+                    MembershipChangeType::Join => "join",
+                    MembershipChangeType::Leave => "leave",
+                },
+                participants: gaia_ids(&change.participants),
+            },
+            EventData::ConversationRename(rename) => Self::ConversationRename {
+                old_name: &rename.old,
+                new_name: &rename.new,
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Attachment<'a> {
+    id: &'a str,
+    #[serde(rename = "type")]
+    typ: &'static str,
+    url: Option<&'a str>,
+}
+
+impl<'a> Attachment<'a> {
+    fn new(attachment: &'a AttachmentSegment) -> Self {
+        let item = &attachment.item;
+        let (typ, url) = if let Some(photo) = &item.photo {
+            ("photo", Some(photo.url.as_str()))
+        } else if let Some(place) = &item.place {
+            ("place", Some(place.url.as_str()))
+        } else if let Some(thing) = &item.thing {
+            ("thing", Some(thing.url.as_str()))
+        } else {
+            ("unknown", None)
+        };
+
+        Self {
+            id: &attachment.id,
+            typ,
+            url,
+        }
+    }
+}
+
+#[inline]
+fn gaia_ids(ids: &[ParticipantId]) -> Vec<&str> {
+    ids.iter().map(|id| id.gaia_id.as_str()).collect()
+}
+
+#[inline]
+pub(crate) fn media_type_str(media_type: &MediaType) -> &'static str {
+    match media_type {
+        MediaType::Audio => "audio",
+        MediaType::Video => "video",
+        MediaType::AudioVideo => "audio_video",
+        MediaType::Photo => "photo",
+        MediaType::AnimatedPhoto => "animated_photo",
+    }
+}
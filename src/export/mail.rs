@@ -0,0 +1,430 @@
+//! mbox and Maildir export.
+//!
+//! Every chat message of a conversation (or every day of chat messages, see [`MailBatch`])
+//! becomes an RFC 5322 message, so that conversations can be read and searched with regular
+//! mail clients. Participants are addressed as `<gaia_id>@<domain>` with their fallback name
+//! as display name, and messages are threaded with `In-Reply-To` and `References` headers.
+
+use crate::export::{sanitize_file_name, ExportError};
+use crate::{AttachmentSegment, ChatMessage, Conversation, Event, Hangouts, ParticipantId};
+
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// How chat messages are grouped into mail messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MailBatch {
+    /// Each chat message becomes its own mail message.
+    Message,
+    /// All chat messages sent on the same day (in UTC) are combined into one mail message.
+    Day,
+}
+
+/// Options for mail export.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MailOptions {
+    /// Grouping of chat messages into mail messages.
+    pub batch: MailBatch,
+    /// Domain used for participant addresses and message IDs.
+    pub domain: String,
+}
+
+impl Default for MailOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            batch: MailBatch::Message,
+            domain: "hangouts.invalid".to_owned(),
+        }
+    }
+}
+
+/// Write every conversation in `hangouts` to its own mbox file in `dir`.
+///
+/// Files are named after [`Conversation::display_name`].
+pub fn export_mbox_files<P: AsRef<Path>>(
+    dir: P,
+    hangouts: &Hangouts,
+    options: &MailOptions,
+) -> Result<(), ExportError> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+
+    let mut used = HashSet::new();
+    for conversation in &hangouts.conversations {
+        let name = unique_name(&mut used, &conversation.display_name());
+        let file = File::create(dir.join(format!("{}.mbox", name)))?;
+        write_mbox(BufWriter::new(file), conversation, options)?;
+    }
+    Ok(())
+}
+
+/// Write every conversation in `hangouts` to its own Maildir folder in `dir`.
+///
+/// Folders are named after [`Conversation::display_name`].
+pub fn export_maildirs<P: AsRef<Path>>(
+    dir: P,
+    hangouts: &Hangouts,
+    options: &MailOptions,
+) -> Result<(), ExportError> {
+    let dir = dir.as_ref();
+
+    let mut used = HashSet::new();
+    for conversation in &hangouts.conversations {
+        let name = unique_name(&mut used, &conversation.display_name());
+        write_maildir(dir.join(name), conversation, options)?;
+    }
+    Ok(())
+}
+
+/// Write the chat messages of `conversation` to `writer` in mboxrd format.
+pub fn write_mbox<W: Write>(
+    mut writer: W,
+    conversation: &Conversation,
+    options: &MailOptions,
+) -> Result<(), ExportError> {
+    for mail in mails(conversation, options) {
+        writeln!(
+            writer,
+            "From {} {}",
+            address(mail.from, options),
+            mail.date.format("%a %b %e %H:%M:%S %Y")
+        )?;
+
+        let message = render(conversation, &mail, options);
+        for line in message.lines() {
+            // mboxrd quoting: any line matching /^>*From / gains an extra '>'.
+            if line.trim_start_matches('>').starts_with("From ") {
+                writer.write_all(b">")?;
+            }
+            writeln!(writer, "{}", line)?;
+        }
+        writeln!(writer)?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write the chat messages of `conversation` to a Maildir folder at `dir`.
+///
+/// The `cur`, `new` and `tmp` subdirectories are created if necessary, and all messages are
+/// stored in `cur` marked as seen.
+pub fn write_maildir<P: AsRef<Path>>(
+    dir: P,
+    conversation: &Conversation,
+    options: &MailOptions,
+) -> Result<(), ExportError> {
+    let dir = dir.as_ref();
+    for sub in &["cur", "new", "tmp"] {
+        fs::create_dir_all(dir.join(sub))?;
+    }
+
+    for mail in mails(conversation, options) {
+        let file_name = format!(
+            "{}.{}.hangouts:2,S",
+            mail.date.timestamp(),
+            sanitize_file_name(&mail.id)
+        );
+
+        // Deliver through tmp so that readers never see partially written messages.
+        let tmp = dir.join("tmp").join(&file_name);
+        fs::write(&tmp, render(conversation, &mail, options))?;
+        fs::rename(&tmp, dir.join("cur").join(&file_name))?;
+    }
+    Ok(())
+}
+
+struct Mail<'a> {
+    /// Unique ID, used as local part of the `Message-ID`.
+    id: String,
+    from: &'a ParticipantId,
+    date: DateTime<Utc>,
+    body: String,
+    in_reply_to: Option<String>,
+    references: Vec<String>,
+}
+
+fn mails<'a>(conversation: &'a Conversation, options: &MailOptions) -> Vec<Mail<'a>> {
+    let mut messages: Vec<(&Event, &ChatMessage)> = conversation
+        .events
+        .iter()
+        .filter_map(|event| event.data.as_chat_message().map(|m| (event, m)))
+        .collect();
+    messages.sort_by_key(|(event, _)| event.timestamp);
+
+    let mut mails: Vec<Mail<'a>> = match options.batch {
+        MailBatch::Message => messages
+            .into_iter()
+            .map(|(event, message)| Mail {
+                id: message_id_part(&event.id),
+                from: &event.sender,
+                date: event.timestamp,
+                body: message_body(message),
+                in_reply_to: None,
+                references: Vec::new(),
+            })
+            .collect(),
+        MailBatch::Day => {
+            let mut mails: Vec<Mail<'a>> = Vec::new();
+            let mut current: Option<NaiveDate> = None;
+            for (event, message) in messages {
+                let day = event.timestamp.date_naive();
+                if current != Some(day) {
+                    current = Some(day);
+                    mails.push(Mail {
+                        id: message_id_part(&format!(
+                            "{}.{}",
+                            conversation.conversation_id,
+                            day.format("%Y%m%d")
+                        )),
+                        from: &event.sender,
+                        date: event.timestamp,
+                        body: String::new(),
+                        in_reply_to: None,
+                        references: Vec::new(),
+                    });
+                }
+
+                let mail = mails.last_mut().unwrap();
+                let sender = conversation
+                    .participant_name(&event.sender)
+                    .map(String::as_str)
+                    .unwrap_or(&event.sender.gaia_id);
+                mail.body.push_str(&format!(
+                    "[{}] {}: ",
+                    event.timestamp.format("%H:%M:%S"),
+                    sender
+                ));
+                mail.body.push_str(&message_body(message));
+            }
+            mails
+        }
+    };
+
+    // Thread every message as a reply to the one before it.
+    let root = mails.first().map(|mail| mail.id.clone());
+    let mut previous: Option<String> = None;
+    for mail in &mut mails {
+        if let (Some(root), Some(previous)) = (&root, &previous) {
+            mail.in_reply_to = Some(previous.clone());
+            mail.references.push(root.clone());
+            if previous != root {
+                mail.references.push(previous.clone());
+            }
+        }
+        previous = Some(mail.id.clone());
+    }
+
+    mails
+}
+
+fn message_body(message: &ChatMessage) -> String {
+    let mut body = message.contents_as_str();
+    if !body.ends_with('\n') {
+        body.push('\n');
+    }
+    for attachment in &message.attachments {
+        if let Some(line) = attachment_line(attachment) {
+            body.push_str(&line);
+            body.push('\n');
+        }
+    }
+    body
+}
+
+fn attachment_line(attachment: &AttachmentSegment) -> Option<String> {
+    let item = &attachment.item;
+    let url = item.url()?;
+    let (label, name) = if item.photo.is_some() {
+        ("Photo", None)
+    } else if let Some(place) = &item.place {
+        ("Place", place.name.as_ref())
+    } else {
+        (
+            "Link",
+            item.thing.as_ref().and_then(|thing| thing.name.as_ref()),
+        )
+    };
+    Some(match name {
+        Some(name) => format!("{}: {} <{}>", label, name, url),
+        None => format!("{}: {}", label, url),
+    })
+}
+
+fn render(conversation: &Conversation, mail: &Mail, options: &MailOptions) -> String {
+    let mut out = String::new();
+
+    let recipients: Vec<String> = {
+        let mut ids: Vec<_> = conversation
+            .participants
+            .keys()
+            .filter(|id| *id != mail.from)
+            .collect();
+        ids.sort();
+        ids.into_iter()
+            .map(|id| mailbox(conversation, id, options))
+            .collect()
+    };
+
+    out.push_str(&format!(
+        "From: {}\n",
+        mailbox(conversation, mail.from, options)
+    ));
+    if !recipients.is_empty() {
+        out.push_str(&format!("To: {}\n", recipients.join(",\n ")));
+    }
+    out.push_str(&fold(&format!(
+        "Subject: {}",
+        encode_word(&conversation.display_name())
+    )));
+    out.push('\n');
+    out.push_str(&format!("Date: {}\n", mail.date.to_rfc2822()));
+    out.push_str(&format!("Message-ID: <{}@{}>\n", mail.id, options.domain));
+    if let Some(parent) = &mail.in_reply_to {
+        out.push_str(&format!("In-Reply-To: <{}@{}>\n", parent, options.domain));
+    }
+    if !mail.references.is_empty() {
+        let references: Vec<_> = mail
+            .references
+            .iter()
+            .map(|id| format!("<{}@{}>", id, options.domain))
+            .collect();
+        out.push_str(&format!("References: {}\n", references.join(" ")));
+    }
+    out.push_str(&format!(
+        "X-Hangouts-Conversation-Id: {}\n",
+        conversation.conversation_id
+    ));
+    out.push_str("MIME-Version: 1.0\n");
+    out.push_str("Content-Type: text/plain; charset=utf-8\n");
+    out.push_str("Content-Transfer-Encoding: 8bit\n");
+    out.push('\n');
+    out.push_str(&mail.body);
+
+    out
+}
+
+#[inline]
+fn address(id: &ParticipantId, options: &MailOptions) -> String {
+    format!("{}@{}", message_id_part(&id.gaia_id), options.domain)
+}
+
+fn mailbox(conversation: &Conversation, id: &ParticipantId, options: &MailOptions) -> String {
+    let address = address(id, options);
+    match conversation.participant_name(id) {
+        Some(name) if name.is_ascii() => {
+            let escaped = name.replace('\\', "\\\\").replace('"', "\\\"");
+            format!("\"{}\" <{}>", escaped, address)
+        }
+        Some(name) => format!("{} <{}>", encode_word(name), address),
+        None => format!("<{}>", address),
+    }
+}
+
+/// Restrict `s` to characters allowed in the dot-atom of a message ID.
+fn message_id_part(s: &str) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~.".contains(c) {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Encode `s` as RFC 2047 encoded-words if it contains non-ASCII characters.
+///
+/// Long text is split into several encoded-words on character boundaries, each on its own
+/// folded line.
+fn encode_word(s: &str) -> String {
+    if s.is_ascii() {
+        return s.to_owned();
+    }
+
+    // 42 bytes encode to 56 base64 characters, which keeps every word at 68 characters, below
+    // the 75 allowed by RFC 2047, and a `Subject` line holding one within 78 characters.
+    const MAX_BYTES: usize = 42;
+    let mut words = Vec::new();
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        if i + c.len_utf8() - start > MAX_BYTES {
+            words.push(&s[start..i]);
+            start = i;
+        }
+    }
+    words.push(&s[start..]);
+
+    let words: Vec<_> = words
+        .into_iter()
+        .map(|word| format!("=?utf-8?B?{}?=", base64(word.as_bytes())))
+        .collect();
+    words.join("\n ")
+}
+
+/// Fold the lines of an ASCII header longer than 78 characters at their last fitting space.
+fn fold(header: &str) -> String {
+    const MAX_LINE: usize = 78;
+
+    let mut out = String::with_capacity(header.len());
+    for (i, line) in header.split('\n').enumerate() {
+        if i > 0 {
+            out.push('\n');
+        }
+        let mut line = line;
+        while line.len() > MAX_LINE {
+            // The space starts the continuation line. A leading one is skipped, which would
+            // leave an empty line behind.
+            match line[1..=MAX_LINE].rfind(' ') {
+                Some(at) => {
+                    out.push_str(&line[..=at]);
+                    out.push('\n');
+                    line = &line[at + 1..];
+                }
+                None => break,
+            }
+        }
+        out.push_str(line);
+    }
+    out
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let b = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let n = (u32::from(b[0]) << 16) | (u32::from(b[1]) << 8) | u32::from(b[2]);
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// Make `name` a unique file name among those in `used`.
+fn unique_name(used: &mut HashSet<String>, name: &str) -> String {
+    let base = sanitize_file_name(name);
+    let mut candidate = base.clone();
+    let mut n = 2;
+    while !used.insert(candidate.clone()) {
+        candidate = format!("{} ({})", base, n);
+        n += 1;
+    }
+    candidate
+}
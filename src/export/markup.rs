@@ -0,0 +1,195 @@
+//! Markdown rendering and user naming shared by the team chat exporters.
+
+use crate::{ChatMessage, ChatSegment, Formatting, Hangouts, ParticipantId};
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Markdown dialect to render messages in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Flavor {
+    /// CommonMark with strikethrough, as used by Mattermost.
+    Markdown,
+    /// Slack's `mrkdwn`.
+    Mrkdwn,
+}
+
+/// Render the text segments of `message` with inline formatting.
+///
+/// Text is escaped so that it reads the same after rendering. Underlining has no markdown
+/// equivalent and is dropped.
+pub(crate) fn render(message: &ChatMessage, flavor: Flavor) -> String {
+    let mut out = String::new();
+    for segment in &message.contents {
+        match segment {
+            ChatSegment::Text { text, format } => {
+                push_formatted(&mut out, format, &escape(text, flavor), flavor)
+            }
+            ChatSegment::Link {
+                text,
+                target,
+                format,
+                ..
+            } => {
+                let link = match flavor {
+                    Flavor::Markdown if text != target => {
+                        format!("[{}]({})", escape(text, flavor), escape_url(target, flavor))
+                    }
+                    Flavor::Mrkdwn if text != target => {
+                        format!("<{}|{}>", escape_url(target, flavor), escape(text, flavor))
+                    }
+                    _ => escape_url(target, flavor),
+                };
+                push_formatted(&mut out, format, &link, flavor);
+            }
+            ChatSegment::LinkBreak { .. } => out.push('\n'),
+        }
+    }
+    out
+}
+
+/// Escape `text` so that it is shown literally.
+///
+/// Markdown control characters are backslash-escaped. Slack has no escapes for its
+/// formatting characters, but `&`, `<` and `>` must be written as entities so that text
+/// such as `<!channel>` is not turned into a mention.
+pub(crate) fn escape(text: &str, flavor: Flavor) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match (flavor, c) {
+            (Flavor::Markdown, '\\' | '`' | '*' | '_' | '[' | ']' | '~' | '<') => {
+                out.push('\\');
+                out.push(c);
+            }
+            (Flavor::Mrkdwn, '&') => out.push_str("&amp;"),
+            (Flavor::Mrkdwn, '<') => out.push_str("&lt;"),
+            (Flavor::Mrkdwn, '>') => out.push_str("&gt;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Escape `url` for use as a link target.
+///
+/// Characters that would end the target early are percent-encoded.
+pub(crate) fn escape_url(url: &str, flavor: Flavor) -> String {
+    let mut out = String::with_capacity(url.len());
+    for c in url.chars() {
+        match (flavor, c) {
+            (_, ' ') => out.push_str("%20"),
+            (_, '<') => out.push_str("%3C"),
+            (_, '>') => out.push_str("%3E"),
+            (Flavor::Markdown, '(') => out.push_str("%28"),
+            (Flavor::Markdown, ')') => out.push_str("%29"),
+            (Flavor::Mrkdwn, '|') => out.push_str("%7C"),
+            (Flavor::Mrkdwn, '&') => out.push_str("&amp;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+fn push_formatted(out: &mut String, format: &Formatting, text: &str, flavor: Flavor) {
+    // Emphasis markers must hug non-whitespace text to be recognized, so keep surrounding
+    // whitespace outside of them.
+    let inner = text.trim();
+    if inner.is_empty() {
+        out.push_str(text);
+        return;
+    }
+    let start = text.len() - text.trim_start().len();
+    let end = start + inner.len();
+
+    let markers: Vec<&str> = [
+        (format.bold, "**", "*"),
+        (format.italics, "_", "_"),
+        (format.strikethrough, "~~", "~"),
+    ]
+    .iter()
+    .filter(|(set, _, _)| *set)
+    .map(|(_, markdown, mrkdwn)| match flavor {
+        Flavor::Markdown => *markdown,
+        Flavor::Mrkdwn => *mrkdwn,
+    })
+    .collect();
+
+    out.push_str(&text[..start]);
+    for marker in &markers {
+        out.push_str(marker);
+    }
+    out.push_str(inner);
+    for marker in markers.iter().rev() {
+        out.push_str(marker);
+    }
+    out.push_str(&text[end..]);
+}
+
+/// Assign unique usernames to every participant in `hangouts`.
+///
+/// Usernames are derived from participant names, lowercased and limited to `[a-z0-9._-]`,
+/// at most `max_len` characters long and starting with a letter. Entries of `overrides` are
+/// used as is.
+pub(crate) fn usernames(
+    hangouts: &Hangouts,
+    overrides: &HashMap<ParticipantId, String>,
+    max_len: usize,
+) -> HashMap<ParticipantId, String> {
+    // Collect every participant once, preferring the first name seen.
+    let mut names: BTreeMap<&ParticipantId, Option<&str>> = BTreeMap::new();
+    for conversation in &hangouts.conversations {
+        for (id, participant) in &conversation.participants {
+            let name = names.entry(id).or_insert(None);
+            if name.is_none() {
+                *name = participant.name().map(String::as_str);
+            }
+        }
+        for event in &conversation.events {
+            names.entry(&event.sender).or_insert(None);
+        }
+    }
+
+    let mut used: HashSet<String> = overrides.values().cloned().collect();
+    names
+        .into_iter()
+        .map(|(id, name)| {
+            let username = match overrides.get(id) {
+                Some(username) => username.clone(),
+                None => unique(&mut used, &base_username(id, name, max_len), max_len),
+            };
+            (id.clone(), username)
+        })
+        .collect()
+}
+
+fn base_username(id: &ParticipantId, name: Option<&str>, max_len: usize) -> String {
+    let mut base: String = name
+        .unwrap_or("")
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            'a'..='z' | '0'..='9' | '.' | '_' | '-' => Some(c),
+            c if c.is_whitespace() => Some('.'),
+            _ => None,
+        })
+        .collect();
+
+    if !base.starts_with(|c: char| c.is_ascii_lowercase()) || base.len() < 3 {
+        base = format!("user{}", id.gaia_id.to_lowercase());
+    }
+    base.truncate(max_len);
+    base
+}
+
+fn unique(used: &mut HashSet<String>, base: &str, max_len: usize) -> String {
+    let mut candidate = base.to_owned();
+    let mut n = 2;
+    while !used.insert(candidate.clone()) {
+        let suffix = n.to_string();
+        let mut prefix = base.to_owned();
+        prefix.truncate(max_len - suffix.len());
+        candidate = format!("{}{}", prefix, suffix);
+        n += 1;
+    }
+    candidate
+}
@@ -0,0 +1,392 @@
+//! Matrix room history export.
+//!
+//! Conversations are converted into Matrix client-server events suitable for importing with
+//! an application service: chat messages become `m.room.message` events with an
+//! `org.matrix.custom.html` formatted body, membership changes become `m.room.member` state
+//! events and renames become `m.room.name` state events. Hangout calls are kept as
+//! `m.notice` messages.
+//!
+//! Matrix only accepts a join sent by the joining user, so a participant added by someone
+//! else becomes an invite sent by the adder followed by a join sent by the participant.
+//!
+//! Every event carries a transaction ID derived from the Hangouts event ID, so re-running an
+//! import does not duplicate messages, and [`MatrixEvent::request_path`] gives the
+//! application service request that sends the event with its original timestamp.
+
+use crate::export::ExportError;
+use crate::util::{format_duration, percent_encode};
+use crate::{
+    AttachmentSegment, ChatMessage, ChatSegment, Conversation, Event, EventData, Formatting,
+    HangoutEventType, MembershipChangeType, ParticipantId,
+};
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+
+use serde::Serialize;
+
+/// Options for Matrix export.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatrixOptions {
+    /// Server name used for generated user IDs.
+    pub server_name: String,
+    /// Localpart prefix of generated user IDs, typically the application service namespace.
+    pub user_prefix: String,
+    /// Explicit user IDs for participants, taking precedence over generated ones.
+    pub user_ids: HashMap<ParticipantId, String>,
+}
+
+impl Default for MatrixOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            server_name: "localhost".to_owned(),
+            user_prefix: "hangouts_".to_owned(),
+            user_ids: HashMap::new(),
+        }
+    }
+}
+
+impl MatrixOptions {
+    /// Get the Matrix user ID for a participant.
+    #[inline]
+    pub fn user_id(&self, id: &ParticipantId) -> String {
+        match self.user_ids.get(id) {
+            Some(user_id) => user_id.clone(),
+            None => format!(
+                "@{}{}:{}",
+                self.user_prefix,
+                id.gaia_id.to_lowercase(),
+                self.server_name
+            ),
+        }
+    }
+}
+
+/// A Matrix room event.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatrixEvent {
+    /// Event type, such as `m.room.message`.
+    #[serde(rename = "type")]
+    pub typ: &'static str,
+    /// User ID of the sender.
+    pub sender: String,
+    /// Original time of the event, in milliseconds since the Unix epoch.
+    pub origin_server_ts: i64,
+    /// State key, set for state events only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub state_key: Option<String>,
+    /// Transaction ID used to deduplicate sends.
+    pub txn_id: String,
+    /// Event content.
+    pub content: MatrixContent,
+}
+
+impl MatrixEvent {
+    /// Returns `true` if the event is a state event.
+    #[inline]
+    pub fn is_state(&self) -> bool {
+        self.state_key.is_some()
+    }
+
+    /// Get the path and query of the client-server API request an application service uses
+    /// to send this event into `room_id` as its sender and with its original timestamp.
+    ///
+    /// The request method is `PUT` and its body is [`Self::content`].
+    pub fn request_path(&self, room_id: &str) -> String {
+        let base = match &self.state_key {
+            Some(state_key) => format!(
+                "/_matrix/client/v3/rooms/{}/state/{}/{}",
+                percent_encode(room_id),
+                self.typ,
+                percent_encode(state_key)
+            ),
+            None => format!(
+                "/_matrix/client/v3/rooms/{}/send/{}/{}",
+                percent_encode(room_id),
+                self.typ,
+                percent_encode(&self.txn_id)
+            ),
+        };
+        format!(
+            "{}?user_id={}&ts={}",
+            base,
+            percent_encode(&self.sender),
+            self.origin_server_ts
+        )
+    }
+}
+
+/// Content of a [`MatrixEvent`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum MatrixContent {
+    /// Content of an `m.room.message` event.
+    Message {
+        msgtype: &'static str,
+        body: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        format: Option<&'static str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        formatted_body: Option<String>,
+    },
+    /// Content of an `m.room.member` event.
+    Member {
+        membership: &'static str,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        displayname: Option<String>,
+    },
+    /// Content of an `m.room.name` event.
+    Name { name: String },
+}
+
+/// A conversation converted into a Matrix room history.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MatrixRoom {
+    /// ID of the source conversation.
+    pub conversation_id: String,
+    /// Name for the room.
+    pub name: String,
+    /// User IDs of all participants, which need to be registered before importing.
+    pub members: Vec<String>,
+    /// Room events, from oldest to newest.
+    pub events: Vec<MatrixEvent>,
+}
+
+impl MatrixRoom {
+    /// Convert `conversation` into a room history.
+    pub fn new(conversation: &Conversation, options: &MatrixOptions) -> Self {
+        let mut members: Vec<_> = conversation
+            .participants
+            .keys()
+            .map(|id| options.user_id(id))
+            .collect();
+        members.sort();
+
+        let mut events: Vec<&Event> = conversation.events.iter().collect();
+        events.sort_by_key(|event| event.timestamp);
+
+        Self {
+            conversation_id: conversation.conversation_id.clone(),
+            name: conversation.display_name(),
+            members,
+            events: events
+                .into_iter()
+                .flat_map(|event| convert_event(conversation, event, options))
+                .collect(),
+        }
+    }
+}
+
+/// Write the room history of `conversation` to `writer` as a JSON document.
+#[inline]
+pub fn write_room<W: Write>(
+    writer: W,
+    conversation: &Conversation,
+    options: &MatrixOptions,
+) -> Result<(), ExportError> {
+    serde_json::to_writer(writer, &MatrixRoom::new(conversation, options))?;
+    Ok(())
+}
+
+fn convert_event(
+    conversation: &Conversation,
+    event: &Event,
+    options: &MatrixOptions,
+) -> Vec<MatrixEvent> {
+    let sender = options.user_id(&event.sender);
+    let origin_server_ts = event.timestamp.timestamp_millis();
+    let make = |typ, state_key, txn_id, content| MatrixEvent {
+        typ,
+        sender: sender.clone(),
+        origin_server_ts,
+        state_key,
+        txn_id,
+        content,
+    };
+
+    match &event.data {
+        EventData::ChatMessage(message) => {
+            vec![make(
+                "m.room.message",
+                None,
+                event.id.clone(),
+                message_content(message),
+            )]
+        }
+        EventData::HangoutEvent(hangout) => {
+            let body = match hangout.typ {
+                HangoutEventType::Start => "Call started".to_owned(),
+                HangoutEventType::End { duration } => {
+                    format!("Call ended after {}", format_duration(duration))
+                }
+            };
+            vec![make(
+                "m.room.message",
+                None,
+                event.id.clone(),
+                MatrixContent::Message {
+                    msgtype: "m.notice",
+                    body,
+                    format: None,
+                    formatted_body: None,
+                },
+            )]
+        }
+        EventData::MembershipChange(change) => {
+            let mut events = Vec::new();
+            for id in &change.participants {
+                let user_id = options.user_id(id);
+                let member = |membership| MatrixContent::Member {
+                    membership,
+                    displayname: conversation.participant_name(id).cloned(),
+                };
+                match change.typ {
+                    // Only the joining user may send a join, so a member added by someone
+                    // else is invited by them first.
+                    MembershipChangeType::Join if user_id != sender => {
+                        events.push(make(
+                            "m.room.member",
+                            Some(user_id.clone()),
+                            format!("{}.{}.invite", event.id, user_id),
+                            member("invite"),
+                        ));
+                        events.push(MatrixEvent {
+                            sender: user_id.clone(),
+                            ..make(
+                                "m.room.member",
+                                Some(user_id.clone()),
+                                format!("{}.{}", event.id, user_id),
+                                member("join"),
+                            )
+                        });
+                    }
+                    MembershipChangeType::Join => events.push(make(
+                        "m.room.member",
+                        Some(user_id.clone()),
+                        format!("{}.{}", event.id, user_id),
+                        member("join"),
+                    )),
+                    // A leave sent by someone else is a kick, which the sender may send.
+                    MembershipChangeType::Leave => events.push(make(
+                        "m.room.member",
+                        Some(user_id.clone()),
+                        format!("{}.{}", event.id, user_id),
+                        member("leave"),
+                    )),
+                }
+            }
+            events
+        }
+        EventData::ConversationRename(rename) => vec![make(
+            "m.room.name",
+            Some(String::new()),
+            event.id.clone(),
+            MatrixContent::Name {
+                name: rename.new.clone(),
+            },
+        )],
+    }
+}
+
+fn message_content(message: &ChatMessage) -> MatrixContent {
+    let mut body = message.contents_as_str();
+    let mut html = String::new();
+    for segment in &message.contents {
+        match segment {
+            ChatSegment::Text { text, format } => {
+                push_formatted(&mut html, format, &escape_html(text));
+            }
+            ChatSegment::Link {
+                text,
+                target,
+                format,
+                ..
+            } => {
+                let link = format!(
+                    "<a href=\"{}\">{}</a>",
+                    escape_html(target),
+                    escape_html(text)
+                );
+                push_formatted(&mut html, format, &link);
+            }
+            ChatSegment::LinkBreak { .. } => html.push_str("<br>"),
+        }
+    }
+
+    for attachment in &message.attachments {
+        if let Some((label, url)) = attachment_link(attachment) {
+            if !body.is_empty() {
+                body.push('\n');
+            }
+            body.push_str(url);
+            if !html.is_empty() {
+                html.push_str("<br>");
+            }
+            let _ = write!(
+                html,
+                "<a href=\"{}\">{}</a>",
+                escape_html(url),
+                escape_html(label)
+            );
+        }
+    }
+
+    MatrixContent::Message {
+        msgtype: "m.text",
+        body,
+        format: Some("org.matrix.custom.html"),
+        formatted_body: Some(html),
+    }
+}
+
+fn attachment_link(attachment: &AttachmentSegment) -> Option<(&str, &str)> {
+    let item = &attachment.item;
+    if let Some(photo) = &item.photo {
+        Some(("Photo", &photo.url))
+    } else if let Some(place) = &item.place {
+        Some((place.name.as_deref().unwrap_or("Place"), &place.url))
+    } else {
+        item.thing
+            .as_ref()
+            .map(|thing| (thing.name.as_deref().unwrap_or("Link"), thing.url.as_str()))
+    }
+}
+
+fn push_formatted(html: &mut String, format: &Formatting, inner: &str) {
+    let tags: Vec<&str> = [
+        (format.bold, "strong"),
+        (format.italics, "em"),
+        (format.strikethrough, "del"),
+        (format.underline, "u"),
+    ]
+    .iter()
+    .filter(|(set, _)| *set)
+    .map(|(_, tag)| *tag)
+    .collect();
+
+    for tag in &tags {
+        let _ = write!(html, "<{}>", tag);
+    }
+    html.push_str(inner);
+    for tag in tags.iter().rev() {
+        let _ = write!(html, "</{}>", tag);
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            '\n' => out.push_str("<br>"),
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,309 @@
+//! Mattermost bulk import export.
+//!
+//! Produces a bulk import JSONL file as consumed by `mmctl import`. All conversations are
+//! placed in a single team: group conversations become private channels and one-on-one
+//! conversations become direct message channels. Participants become users with usernames
+//! derived from their names, and chat messages become posts with their formatting rendered
+//! as Markdown and their original timestamps preserved.
+//!
+//! Attachments listed in [`MattermostOptions::media`] are attached to their posts from the
+//! local file system; all others are linked by URL in the message text.
+
+use crate::export::markup::{self, Flavor};
+use crate::export::ExportError;
+use crate::{ChatMessage, Conversation, Hangouts, ParticipantId};
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::io::Write;
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+/// Maximum length of Mattermost usernames.
+const MAX_USERNAME_LEN: usize = 22;
+/// Maximum length of Mattermost channel names and display names.
+const MAX_CHANNEL_NAME_LEN: usize = 64;
+
+/// Options for Mattermost export.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MattermostOptions {
+    /// Name of the team to import into.
+    pub team: String,
+    /// Display name of the team, used if the team does not exist yet.
+    pub team_display_name: String,
+    /// Domain of the placeholder email addresses generated for users.
+    pub email_domain: String,
+    /// Explicit usernames for participants, taking precedence over generated ones.
+    pub usernames: HashMap<ParticipantId, String>,
+    /// Local files for attachments, by attachment ID.
+    pub media: HashMap<String, PathBuf>,
+}
+
+impl Default for MattermostOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            team: "hangouts".to_owned(),
+            team_display_name: "Hangouts".to_owned(),
+            email_domain: "hangouts.invalid".to_owned(),
+            usernames: HashMap::new(),
+            media: HashMap::new(),
+        }
+    }
+}
+
+/// Write a Mattermost bulk import file for `hangouts`.
+pub fn write_bulk_import<W: Write>(
+    mut writer: W,
+    hangouts: &Hangouts,
+    options: &MattermostOptions,
+) -> Result<(), ExportError> {
+    let usernames = markup::usernames(hangouts, &options.usernames, MAX_USERNAME_LEN);
+    let username = |id: &ParticipantId| usernames[id].as_str();
+
+    let mut write_line = |line: &Line| -> Result<(), ExportError> {
+        serde_json::to_writer(&mut writer, line)?;
+        writer.write_all(b"\n")?;
+        Ok(())
+    };
+
+    write_line(&Line::Version { version: 1 })?;
+    write_line(&Line::Team {
+        team: Team {
+            name: &options.team,
+            display_name: &options.team_display_name,
+            typ: "I",
+        },
+    })?;
+
+    // Channels for group conversations, and the channels every user is a member of.
+    let mut channel_names = HashMap::new();
+    let mut used = HashSet::new();
+    let mut memberships: BTreeMap<&ParticipantId, BTreeSet<&str>> = BTreeMap::new();
+    for conversation in hangouts.conversations.iter().filter(|c| c.is_group()) {
+        let name = channel_name(&mut used, &conversation.conversation_id);
+        let mut display_name = conversation.display_name();
+        truncate(&mut display_name, MAX_CHANNEL_NAME_LEN);
+
+        write_line(&Line::Channel {
+            channel: Channel {
+                team: &options.team,
+                name: &name,
+                display_name: &display_name,
+                typ: "P",
+            },
+        })?;
+        channel_names.insert(conversation.conversation_id.as_str(), name);
+    }
+    for conversation in hangouts.conversations.iter().filter(|c| c.is_group()) {
+        let name = channel_names[conversation.conversation_id.as_str()].as_str();
+        for id in conversation.participants.keys() {
+            memberships.entry(id).or_default().insert(name);
+        }
+    }
+
+    let mut ids: Vec<_> = usernames.keys().collect();
+    ids.sort();
+    for id in ids {
+        let channels = memberships
+            .get(id)
+            .map(|names| names.iter().map(|name| ChannelRef { name }).collect())
+            .unwrap_or_default();
+        write_line(&Line::User {
+            user: User {
+                username: username(id),
+                email: format!("{}@{}", username(id), options.email_domain),
+                teams: vec![TeamMembership {
+                    name: &options.team,
+                    channels,
+                }],
+            },
+        })?;
+    }
+
+    for conversation in &hangouts.conversations {
+        if conversation.is_group() {
+            let channel = channel_names[conversation.conversation_id.as_str()].as_str();
+            for (sender, message, create_at) in posts(conversation) {
+                let (message, attachments) = post_body(message, options);
+                write_line(&Line::Post {
+                    post: Post {
+                        team: &options.team,
+                        channel,
+                        user: username(sender),
+                        message,
+                        create_at,
+                        attachments,
+                    },
+                })?;
+            }
+        } else {
+            let mut members: Vec<&str> = conversation.participants.keys().map(&username).collect();
+            members.sort_unstable();
+            // Self-conversations still need two members.
+            if members.len() == 1 {
+                members.push(members[0]);
+            }
+
+            write_line(&Line::DirectChannel {
+                direct_channel: DirectChannel {
+                    members: members.clone(),
+                },
+            })?;
+            for (sender, message, create_at) in posts(conversation) {
+                let (message, attachments) = post_body(message, options);
+                write_line(&Line::DirectPost {
+                    direct_post: DirectPost {
+                        channel_members: members.clone(),
+                        user: username(sender),
+                        message,
+                        create_at,
+                        attachments,
+                    },
+                })?;
+            }
+        }
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+/// Chat messages of `conversation` with their sender and creation time in milliseconds.
+fn posts(conversation: &Conversation) -> Vec<(&ParticipantId, &ChatMessage, i64)> {
+    let mut posts: Vec<_> = conversation
+        .events
+        .iter()
+        .filter_map(|event| {
+            event
+                .data
+                .as_chat_message()
+                .map(|message| (&event.sender, message, event.timestamp.timestamp_millis()))
+        })
+        .collect();
+    posts.sort_by_key(|(_, _, create_at)| *create_at);
+    posts
+}
+
+fn post_body(message: &ChatMessage, options: &MattermostOptions) -> (String, Vec<Attachment>) {
+    let mut text = markup::render(message, Flavor::Markdown);
+    let mut attachments = Vec::new();
+    for attachment in &message.attachments {
+        if let Some(path) = options.media.get(&attachment.id) {
+            attachments.push(Attachment {
+                path: path.to_string_lossy().into_owned(),
+            });
+        } else if let Some(url) = attachment.item.url() {
+            if !text.is_empty() {
+                text.push('\n');
+            }
+            text.push_str(url);
+        }
+    }
+    (text, attachments)
+}
+
+fn channel_name(used: &mut HashSet<String>, conversation_id: &str) -> String {
+    let mut base: String = conversation_id
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | '0'..='9' | '-' | '_' => c,
+            _ => '-',
+        })
+        .collect();
+    truncate(&mut base, MAX_CHANNEL_NAME_LEN - 4);
+
+    let mut name = base.clone();
+    let mut n = 2;
+    while !used.insert(name.clone()) {
+        name = format!("{}-{}", base, n);
+        n += 1;
+    }
+    name
+}
+
+/// Truncate `s` to at most `max` characters.
+fn truncate(s: &mut String, max: usize) {
+    if let Some((i, _)) = s.char_indices().nth(max) {
+        s.truncate(i);
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum Line<'a> {
+    Version { version: u32 },
+    Team { team: Team<'a> },
+    Channel { channel: Channel<'a> },
+    User { user: User<'a> },
+    Post { post: Post<'a> },
+    DirectChannel { direct_channel: DirectChannel<'a> },
+    DirectPost { direct_post: DirectPost<'a> },
+}
+
+#[derive(Serialize)]
+struct Team<'a> {
+    name: &'a str,
+    display_name: &'a str,
+    #[serde(rename = "type")]
+    typ: &'static str,
+}
+
+#[derive(Serialize)]
+struct Channel<'a> {
+    team: &'a str,
+    name: &'a str,
+    display_name: &'a str,
+    #[serde(rename = "type")]
+    typ: &'static str,
+}
+
+#[derive(Serialize)]
+struct User<'a> {
+    username: &'a str,
+    email: String,
+    teams: Vec<TeamMembership<'a>>,
+}
+
+#[derive(Serialize)]
+struct TeamMembership<'a> {
+    name: &'a str,
+    channels: Vec<ChannelRef<'a>>,
+}
+
+#[derive(Serialize)]
+struct ChannelRef<'a> {
+    name: &'a str,
+}
+
+#[derive(Serialize)]
+struct Post<'a> {
+    team: &'a str,
+    channel: &'a str,
+    user: &'a str,
+    message: String,
+    create_at: i64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    attachments: Vec<Attachment>,
+}
+
+#[derive(Serialize)]
+struct DirectChannel<'a> {
+    members: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct DirectPost<'a> {
+    channel_members: Vec<&'a str>,
+    user: &'a str,
+    message: String,
+    create_at: i64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    attachments: Vec<Attachment>,
+}
+
+#[derive(Serialize)]
+struct Attachment {
+    path: String,
+}
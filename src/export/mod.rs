@@ -0,0 +1,90 @@
+//! Exporters for converting Hangouts data into other formats.
+
+mod contentline;
+pub mod geo;
+pub mod ical;
+#[cfg(feature = "json")]
+pub mod jsonl;
+pub mod mail;
+#[cfg(feature = "json")]
+mod markup;
+#[cfg(feature = "json")]
+pub mod matrix;
+#[cfg(feature = "json")]
+pub mod mattermost;
+#[cfg(feature = "json")]
+pub mod slack;
+pub mod vcard;
+pub mod xmpp;
+
+#[cfg(feature = "raw")]
+use crate::raw::convert::ConversionError;
+
+use std::fmt;
+use std::io;
+
+/// Error produced by an exporter.
+#[derive(Debug, thiserror::Error)]
+pub enum ExportError {
+    Io(#[from] io::Error),
+    #[cfg(feature = "json")]
+    Json(#[from] serde_json::Error),
+    #[cfg(feature = "raw")]
+    Conversion(#[from] ConversionError),
+    #[cfg(feature = "zip")]
+    Zip(#[from] zip::result::ZipError),
+}
+
+impl fmt::Display for ExportError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Io(err) => write!(f, "io: {}", err),
+            #[cfg(feature = "json")]
+            Self::Json(err) => write!(f, "json: {}", err),
+            #[cfg(feature = "raw")]
+            Self::Conversion(err) => write!(f, "conversion: {}", err),
+            #[cfg(feature = "zip")]
+            Self::Zip(err) => write!(f, "zip: {}", err),
+        }
+    }
+}
+
+/// Replace characters that are not allowed in file names on common platforms.
+///
+/// The result never contains path separators or starts with a dot, so it stays inside the
+/// directory it is joined to.
+pub fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    match sanitized.trim_start_matches('.') {
+        "" => "_".to_owned(),
+        s => s.to_owned(),
+    }
+}
+
+/// Escape `s` for use in XML text and attribute values.
+pub(crate) fn escape_xml(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            // Control characters other than whitespace are not allowed in XML 1.0.
+            c if c.is_control() && !matches!(c, '\n' | '\r' | '\t') => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,411 @@
+//! Slack export archive.
+//!
+//! Produces the layout of a Slack workspace export, which most chat platforms can import:
+//! `users.json`, `channels.json` and `dms.json` at the top level, and one JSON file of
+//! messages per day in a folder for each channel or direct message. Group conversations
+//! become channels and one-on-one conversations become direct messages.
+//!
+//! Attachments listed in [`SlackOptions::media`] are copied into the archive under
+//! `__uploads/<attachment id>/` and referenced from their messages; all others are linked by
+//! URL in the message text.
+
+use crate::export::markup::{self, Flavor};
+use crate::export::{sanitize_file_name, ExportError};
+use crate::{Conversation, EventData, Hangouts, MembershipChangeType, ParticipantId};
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Maximum length of Slack usernames.
+const MAX_USERNAME_LEN: usize = 21;
+/// Maximum length of Slack channel names.
+const MAX_CHANNEL_NAME_LEN: usize = 80;
+
+/// Options for Slack export.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SlackOptions {
+    /// Explicit usernames for participants, taking precedence over generated ones.
+    pub usernames: HashMap<ParticipantId, String>,
+    /// Local files for attachments, by attachment ID.
+    pub media: HashMap<String, PathBuf>,
+}
+
+/// A Slack export archive, ready to be written as a directory or zip file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlackExport {
+    entries: BTreeMap<String, Entry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Entry {
+    /// Generated file contents.
+    Data(Vec<u8>),
+    /// Local file copied into the archive.
+    File(PathBuf),
+}
+
+impl SlackExport {
+    /// Build the archive for `hangouts`.
+    pub fn new(hangouts: &Hangouts, options: &SlackOptions) -> Result<Self, ExportError> {
+        let usernames = markup::usernames(hangouts, &options.usernames, MAX_USERNAME_LEN);
+
+        let mut entries = BTreeMap::new();
+
+        let mut users: Vec<User> = usernames
+            .iter()
+            .map(|(id, name)| {
+                let real_name = hangouts
+                    .conversations
+                    .iter()
+                    .find_map(|c| c.participant_name(id))
+                    .cloned()
+                    .unwrap_or_else(|| name.clone());
+                User {
+                    id: user_id(id),
+                    name: name.clone(),
+                    real_name: real_name.clone(),
+                    deleted: false,
+                    is_bot: false,
+                    profile: Profile {
+                        display_name: real_name.clone(),
+                        real_name,
+                    },
+                }
+            })
+            .collect();
+        users.sort_by(|a, b| a.id.cmp(&b.id));
+        entries.insert("users.json".to_owned(), json(&users)?);
+
+        let mut channels = Vec::new();
+        let mut dms = Vec::new();
+        let mut used = HashSet::new();
+        for conversation in &hangouts.conversations {
+            let mut members: Vec<String> = conversation.participants.keys().map(user_id).collect();
+            members.sort();
+            let created = created(conversation).timestamp();
+            let creator = conversation
+                .events
+                .iter()
+                .min_by_key(|event| event.timestamp)
+                .map(|event| user_id(&event.sender))
+                .unwrap_or_default();
+
+            let folder = if conversation.is_group() {
+                let name = channel_name(&mut used, &conversation.display_name());
+                channels.push(Channel {
+                    id: format!("C{}", conversation.conversation_id),
+                    name: name.clone(),
+                    created,
+                    creator,
+                    is_archived: false,
+                    is_general: false,
+                    members,
+                    topic: Topic::default(),
+                    purpose: Topic::default(),
+                });
+                name
+            } else {
+                // The ID names the folder of the conversation, so it must be a safe file name.
+                let id = unique(
+                    &mut used,
+                    format!("D{}", sanitize_file_name(&conversation.conversation_id)),
+                );
+                dms.push(Dm {
+                    id: id.clone(),
+                    created,
+                    members,
+                });
+                id
+            };
+
+            for (day, messages) in messages(conversation, options) {
+                for message in &messages {
+                    for file in &message.files {
+                        if let Some(path) = options.media.get(&file.id) {
+                            entries.insert(file.url_private.clone(), Entry::File(path.clone()));
+                        }
+                    }
+                }
+                entries.insert(format!("{}/{}.json", folder, day), json(&messages)?);
+            }
+        }
+        entries.insert("channels.json".to_owned(), json(&channels)?);
+        entries.insert("dms.json".to_owned(), json(&dms)?);
+
+        Ok(Self { entries })
+    }
+
+    /// Paths of all files in the archive.
+    #[inline]
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(String::as_str)
+    }
+
+    /// Write the archive as a directory tree rooted at `dir`.
+    pub fn write_dir<P: AsRef<Path>>(&self, dir: P) -> Result<(), ExportError> {
+        let dir = dir.as_ref();
+        for (path, entry) in &self.entries {
+            let target = dir.join(path);
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            match entry {
+                Entry::Data(data) => fs::write(&target, data)?,
+                Entry::File(source) => {
+                    fs::copy(source, &target)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the archive as a zip file.
+    #[cfg(feature = "zip")]
+    pub fn write_zip<W: std::io::Write + std::io::Seek>(
+        &self,
+        writer: W,
+    ) -> Result<(), ExportError> {
+        use std::io::Write;
+        use zip::write::SimpleFileOptions;
+
+        let mut zip = zip::ZipWriter::new(writer);
+        let options = SimpleFileOptions::default();
+        for (path, entry) in &self.entries {
+            zip.start_file(path.as_str(), options)?;
+            match entry {
+                Entry::Data(data) => zip.write_all(data)?,
+                Entry::File(source) => {
+                    std::io::copy(&mut fs::File::open(source)?, &mut zip)?;
+                }
+            }
+        }
+        zip.finish()?;
+        Ok(())
+    }
+}
+
+/// Messages of `conversation` grouped by day (in UTC) in the Slack message format.
+fn messages(conversation: &Conversation, options: &SlackOptions) -> BTreeMap<String, Vec<Message>> {
+    let mut events: Vec<_> = conversation.events.iter().collect();
+    events.sort_by_key(|event| event.timestamp);
+
+    // Slack identifies messages by their `ts`, so messages sharing a time are moved apart by
+    // a microsecond.
+    let mut last = i64::MIN;
+    let mut ts = |timestamp: &DateTime<Utc>| {
+        let micros = timestamp.timestamp_micros().max(last.saturating_add(1));
+        last = micros;
+        format!(
+            "{}.{:06}",
+            micros.div_euclid(1_000_000),
+            micros.rem_euclid(1_000_000)
+        )
+    };
+
+    let mut days: BTreeMap<String, Vec<Message>> = BTreeMap::new();
+    for event in events {
+        let user = user_id(&event.sender);
+        let mut message = Message {
+            typ: "message",
+            subtype: None,
+            user: user.clone(),
+            text: String::new(),
+            ts: ts(&event.timestamp),
+            name: None,
+            old_name: None,
+            files: Vec::new(),
+        };
+
+        match &event.data {
+            EventData::ChatMessage(chat) => {
+                message.text = markup::render(chat, Flavor::Mrkdwn);
+                for attachment in &chat.attachments {
+                    if let Some(path) = options.media.get(&attachment.id) {
+                        let name = path
+                            .file_name()
+                            .map(|name| name.to_string_lossy().into_owned())
+                            .unwrap_or_else(|| attachment.id.clone());
+                        message.files.push(File {
+                            url_private: format!(
+                                "__uploads/{}/{}",
+                                sanitize_file_name(&attachment.id),
+                                sanitize_file_name(&name)
+                            ),
+                            id: attachment.id.clone(),
+                            name,
+                        });
+                    } else if let Some(url) = attachment.item.url() {
+                        if !message.text.is_empty() {
+                            message.text.push('\n');
+                        }
+                        message
+                            .text
+                            .push_str(&format!("<{}>", markup::escape_url(url, Flavor::Mrkdwn)));
+                    }
+                }
+            }
+            EventData::MembershipChange(change) => {
+                let (subtype, verb) = match change.typ {
+                    MembershipChangeType::Join => ("channel_join", "joined"),
+                    MembershipChangeType::Leave => ("channel_leave", "left"),
+                };
+                // Slack records one membership message per user.
+                for (i, id) in change.participants.iter().enumerate() {
+                    let user = user_id(id);
+                    days.entry(day(&event.timestamp))
+                        .or_default()
+                        .push(Message {
+                            subtype: Some(subtype),
+                            text: format!("<@{}> has {} the channel", user, verb),
+                            user,
+                            ts: if i == 0 {
+                                message.ts.clone()
+                            } else {
+                                ts(&event.timestamp)
+                            },
+                            ..message.clone()
+                        });
+                }
+                continue;
+            }
+            EventData::ConversationRename(rename) => {
+                message.subtype = Some("channel_name");
+                message.text = format!(
+                    "<@{}> has renamed the channel from \"{}\" to \"{}\"",
+                    user,
+                    markup::escape(&rename.old, Flavor::Mrkdwn),
+                    markup::escape(&rename.new, Flavor::Mrkdwn)
+                );
+                message.old_name = Some(rename.old.clone());
+                message.name = Some(rename.new.clone());
+            }
+            EventData::HangoutEvent(_) => continue,
+        }
+
+        days.entry(day(&event.timestamp)).or_default().push(message);
+    }
+    days
+}
+
+#[inline]
+fn user_id(id: &ParticipantId) -> String {
+    format!("U{}", id.gaia_id)
+}
+
+#[inline]
+fn day(timestamp: &DateTime<Utc>) -> String {
+    timestamp.format("%Y-%m-%d").to_string()
+}
+
+fn created(conversation: &Conversation) -> DateTime<Utc> {
+    conversation
+        .events
+        .iter()
+        .map(|event| event.timestamp)
+        .min()
+        .unwrap_or(conversation.sort_timestamp)
+}
+
+fn channel_name(used: &mut HashSet<String>, display_name: &str) -> String {
+    let mut base = String::new();
+    for c in display_name.to_lowercase().chars() {
+        match c {
+            'a'..='z' | '0'..='9' | '_' => base.push(c),
+            _ if !base.is_empty() && !base.ends_with('-') => base.push('-'),
+            _ => {}
+        }
+    }
+    let mut base = base.trim_end_matches('-').to_owned();
+    if base.is_empty() {
+        base.push_str("channel");
+    }
+    base.truncate(MAX_CHANNEL_NAME_LEN - 4);
+    unique(used, base)
+}
+
+/// Make `base` unique among the `used` folder names by appending a number.
+fn unique(used: &mut HashSet<String>, base: String) -> String {
+    let mut name = base.clone();
+    let mut n = 2;
+    while !used.insert(name.clone()) {
+        name = format!("{}-{}", base, n);
+        n += 1;
+    }
+    name
+}
+
+#[inline]
+fn json<T: Serialize>(value: &T) -> Result<Entry, ExportError> {
+    Ok(Entry::Data(serde_json::to_vec_pretty(value)?))
+}
+
+#[derive(Serialize)]
+struct User {
+    id: String,
+    name: String,
+    real_name: String,
+    deleted: bool,
+    is_bot: bool,
+    profile: Profile,
+}
+
+#[derive(Serialize)]
+struct Profile {
+    real_name: String,
+    display_name: String,
+}
+
+#[derive(Serialize)]
+struct Channel {
+    id: String,
+    name: String,
+    created: i64,
+    creator: String,
+    is_archived: bool,
+    is_general: bool,
+    members: Vec<String>,
+    topic: Topic,
+    purpose: Topic,
+}
+
+#[derive(Serialize, Default)]
+struct Topic {
+    value: String,
+    creator: String,
+    last_set: i64,
+}
+
+#[derive(Serialize)]
+struct Dm {
+    id: String,
+    created: i64,
+    members: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+struct Message {
+    #[serde(rename = "type")]
+    typ: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    subtype: Option<&'static str>,
+    user: String,
+    text: String,
+    ts: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    old_name: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    files: Vec<File>,
+}
+
+#[derive(Serialize, Clone)]
+struct File {
+    id: String,
+    name: String,
+    url_private: String,
+}
@@ -0,0 +1,148 @@
+//! vCard export of participants.
+//!
+//! Every participant seen in any conversation becomes one RFC 6350 vCard 4.0, merged across
+//! conversations by [`ParticipantId`]. Cards carry the fallback name of the participant, their
+//! gaia ID as UID, their phone number if known and a note with the dates of the first and last
+//! events of the conversations they took part in.
+
+use crate::export::contentline::{escape_text, write_line};
+use crate::export::ExportError;
+use crate::{Hangouts, ParticipantId, ParticipantType, PhoneNumber};
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use chrono::{DateTime, Utc};
+
+/// A participant merged across all conversations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Contact<'a> {
+    /// ID of the participant.
+    pub id: &'a ParticipantId,
+    /// First fallback name found for the participant.
+    pub name: Option<&'a str>,
+    /// First type found for the participant.
+    pub typ: Option<&'a ParticipantType>,
+    /// First phone number found for the participant.
+    pub phone_number: Option<&'a PhoneNumber>,
+    /// Names of the conversations the participant took part in.
+    pub conversations: Vec<String>,
+    /// Time of the first event in the conversations of the participant.
+    pub first_seen: Option<DateTime<Utc>>,
+    /// Time of the last event in the conversations of the participant.
+    pub last_seen: Option<DateTime<Utc>>,
+}
+
+impl Contact<'_> {
+    /// Get the phone number of the contact in E.164 format, if known.
+    ///
+    /// Off-network phone participants without phone number data are commonly named after
+    /// their number, in which case the name is used.
+    pub fn tel(&self) -> Option<&str> {
+        let number = self
+            .phone_number
+            .and_then(|number| number.e164.as_deref().or(number.international.as_deref()));
+        if number.is_some() {
+            return number;
+        }
+
+        match (self.typ, self.name) {
+            (Some(ParticipantType::OffNetworkPhone), Some(name)) => {
+                let digits = name.strip_prefix('+')?;
+                let is_number = digits.chars().any(|c| c.is_ascii_digit())
+                    && digits.chars().all(|c| c.is_ascii_digit() || c == ' ');
+                if is_number {
+                    Some(name)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Collect all participants in `hangouts`, merged by ID and sorted by ID.
+pub fn contacts(hangouts: &Hangouts) -> Vec<Contact<'_>> {
+    let mut contacts: BTreeMap<&ParticipantId, Contact> = BTreeMap::new();
+    for conversation in &hangouts.conversations {
+        let times = conversation.events.iter().map(|event| event.timestamp);
+        let (first, last) = (times.clone().min(), times.max());
+        let conversation_name = conversation.display_name();
+
+        for (id, participant) in &conversation.participants {
+            let contact = contacts.entry(id).or_insert_with(|| Contact {
+                id,
+                name: None,
+                typ: None,
+                phone_number: None,
+                conversations: Vec::new(),
+                first_seen: None,
+                last_seen: None,
+            });
+            contact.name = contact.name.or(participant.name().map(String::as_str));
+            contact.typ = contact.typ.or(participant.typ.as_ref());
+            contact.phone_number = contact.phone_number.or(participant.phone_number.as_ref());
+            contact.conversations.push(conversation_name.clone());
+            contact.first_seen = match (contact.first_seen, first) {
+                (Some(a), Some(b)) => Some(a.min(b)),
+                (a, b) => a.or(b),
+            };
+            contact.last_seen = match (contact.last_seen, last) {
+                (Some(a), Some(b)) => Some(a.max(b)),
+                (a, b) => a.or(b),
+            };
+        }
+    }
+    contacts.into_values().collect()
+}
+
+/// Write a vCard for every participant in `hangouts`.
+pub fn write_vcards<W: Write>(mut writer: W, hangouts: &Hangouts) -> Result<(), ExportError> {
+    for contact in contacts(hangouts) {
+        write_vcard(&mut writer, &contact)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_vcard<W: Write>(writer: &mut W, contact: &Contact) -> Result<(), ExportError> {
+    let tel = contact.tel();
+    // FN is required, so fall back to the phone number or the ID.
+    let name = contact.name.or(tel).unwrap_or(&contact.id.gaia_id);
+
+    write_line(writer, "BEGIN:VCARD")?;
+    write_line(writer, "VERSION:4.0")?;
+    write_line(writer, "KIND:individual")?;
+    write_line(writer, &format!("FN:{}", escape_text(name)))?;
+    write_line(
+        writer,
+        &format!("UID;VALUE=text:{}", escape_text(&contact.id.gaia_id)),
+    )?;
+    if let Some(tel) = tel {
+        let uri: String = tel.chars().filter(|c| !c.is_whitespace()).collect();
+        write_line(writer, &format!("TEL;VALUE=uri:tel:{}", uri))?;
+    }
+
+    let mut note = match (contact.first_seen, contact.last_seen) {
+        (Some(first), Some(last)) => format!(
+            "Seen in Hangouts from {} to {}",
+            first.format("%Y-%m-%d"),
+            last.format("%Y-%m-%d")
+        ),
+        _ => "Seen in Hangouts".to_owned(),
+    };
+    note.push_str(&format!(
+        " in {} conversation{}: {}",
+        contact.conversations.len(),
+        if contact.conversations.len() == 1 {
+            ""
+        } else {
+            "s"
+        },
+        contact.conversations.join("; ")
+    ));
+    write_line(writer, &format!("NOTE:{}", escape_text(&note)))?;
+    write_line(writer, "END:VCARD")?;
+    Ok(())
+}
@@ -0,0 +1,252 @@
+//! XMPP message archive export.
+//!
+//! One-to-one conversations are written as the personal message archive (XEP-0313) of the
+//! account owner inside a XEP-0227 user data document, which can be imported by servers such
+//! as Prosody and ejabberd. Group conversations are written as separate XEP-0227 documents
+//! in which the room is an account of the MUC service host, the layout Prosody uses to store
+//! MUC archives. XEP-0227 itself defines no place for room archives, so other servers may
+//! need the room archive moved to where they keep MUC history.
+//!
+//! Every chat message becomes a `<message>` stanza wrapped in a XEP-0297 `<forwarded>`
+//! element with a XEP-0203 `<delay>` stamp holding the original time of the message. Photo,
+//! place and thing attachments are added to the body and as XEP-0066 out-of-band URLs.
+
+use crate::export::{escape_xml, ExportError};
+use crate::{Conversation, Event, Hangouts, ParticipantId};
+
+use std::collections::HashMap;
+use std::io::Write;
+
+use chrono::SecondsFormat;
+
+/// Options for XMPP export.
+#[derive(Debug, Clone, PartialEq)]
+pub struct XmppOptions {
+    /// JIDs of participants.
+    pub jids: HashMap<ParticipantId, String>,
+    /// Domain of the JIDs generated for participants missing from [`Self::jids`].
+    pub domain: String,
+    /// JIDs of the rooms for group conversations, by conversation ID.
+    pub rooms: HashMap<String, String>,
+    /// MUC service of the room JIDs generated for conversations missing from [`Self::rooms`].
+    pub muc_service: String,
+}
+
+impl Default for XmppOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            jids: HashMap::new(),
+            domain: "localhost".to_owned(),
+            rooms: HashMap::new(),
+            muc_service: "conference.localhost".to_owned(),
+        }
+    }
+}
+
+impl XmppOptions {
+    /// Get the bare JID for a participant.
+    ///
+    /// Generated JIDs use the Gaia ID as localpart, escaped like room localparts.
+    #[inline]
+    pub fn jid(&self, id: &ParticipantId) -> String {
+        match self.jids.get(id) {
+            Some(jid) => jid.clone(),
+            None => format!("{}@{}", escape_localpart(&id.gaia_id), self.domain),
+        }
+    }
+
+    /// Get the room JID for a group conversation.
+    ///
+    /// Generated room JIDs use the conversation ID as localpart, with every character other
+    /// than lowercase ASCII letters, digits, `-`, `.` and `_` written as XEP-0106 `\xx`
+    /// escapes of its UTF-8 bytes. Capitals are escaped as well, since servers fold the case
+    /// of localparts while conversation IDs are case-sensitive.
+    #[inline]
+    pub fn room_jid(&self, conversation: &Conversation) -> String {
+        match self.rooms.get(&conversation.conversation_id) {
+            Some(jid) => jid.clone(),
+            None => format!(
+                "{}@{}",
+                escape_localpart(&conversation.conversation_id),
+                self.muc_service
+            ),
+        }
+    }
+}
+
+fn escape_localpart(id: &str) -> String {
+    let mut out = String::with_capacity(id.len());
+    for b in id.bytes() {
+        match b {
+            b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' => out.push(b as char),
+            b => out.push_str(&format!("\\{:02x}", b)),
+        }
+    }
+    out
+}
+
+/// Write a XEP-0227 document holding the message archive of `owner` with all one-to-one
+/// conversations in `hangouts`.
+pub fn write_user_data<W: Write>(
+    mut writer: W,
+    hangouts: &Hangouts,
+    owner: &ParticipantId,
+    options: &XmppOptions,
+) -> Result<(), ExportError> {
+    let owner_jid = options.jid(owner);
+    write_archive_start(&mut writer, &owner_jid)?;
+
+    for conversation in hangouts.conversations.iter().filter(|c| !c.is_group()) {
+        for event in chat_events(conversation) {
+            let from = options.jid(&event.sender);
+            let to = conversation
+                .participants
+                .keys()
+                .find(|id| **id != event.sender)
+                .map(|id| options.jid(id))
+                .unwrap_or_else(|| owner_jid.clone());
+            write_result(&mut writer, event, &from, &to, "chat", None)?;
+        }
+    }
+
+    write_archive_end(writer)
+}
+
+/// Write a XEP-0227 document holding the MUC archive of a group conversation, as the
+/// archive of the room's account on the MUC service host.
+///
+/// Messages are sent from the occupant JID `room@service/nick`, using participant names as
+/// nicknames, and carry the real JID of the sender in a `muc#user` item.
+pub fn write_muc_archive<W: Write>(
+    mut writer: W,
+    conversation: &Conversation,
+    options: &XmppOptions,
+) -> Result<(), ExportError> {
+    let room = options.room_jid(conversation);
+
+    write_archive_start(&mut writer, &room)?;
+
+    for event in chat_events(conversation) {
+        let nick = conversation
+            .participant_name(&event.sender)
+            .map(String::as_str)
+            .unwrap_or(&event.sender.gaia_id);
+        let from = format!("{}/{}", room, nick);
+        let real_jid = options.jid(&event.sender);
+        write_result(
+            &mut writer,
+            event,
+            &from,
+            &room,
+            "groupchat",
+            Some(&real_jid),
+        )?;
+    }
+
+    write_archive_end(writer)
+}
+
+/// Open the XEP-0227 document and the archive of the account `jid`.
+fn write_archive_start<W: Write>(writer: &mut W, jid: &str) -> Result<(), ExportError> {
+    let (user, host) = match jid.split_once('@') {
+        Some((user, host)) => (user, host),
+        None => ("", jid),
+    };
+
+    writeln!(writer, "<?xml version='1.0' encoding='UTF-8'?>")?;
+    writeln!(writer, "<server-data xmlns='urn:xmpp:pie:0'>")?;
+    writeln!(writer, "  <host jid='{}'>", escape_xml(host))?;
+    writeln!(writer, "    <user name='{}'>", escape_xml(user))?;
+    writeln!(writer, "      <archive xmlns='urn:xmpp:pie:0#mam'>")?;
+    Ok(())
+}
+
+fn write_archive_end<W: Write>(mut writer: W) -> Result<(), ExportError> {
+    writeln!(writer, "      </archive>")?;
+    writeln!(writer, "    </user>")?;
+    writeln!(writer, "  </host>")?;
+    writeln!(writer, "</server-data>")?;
+    writer.flush()?;
+    Ok(())
+}
+
+fn chat_events(conversation: &Conversation) -> Vec<&Event> {
+    let mut events: Vec<_> = conversation
+        .events
+        .iter()
+        .filter(|event| event.data.is_chat_message())
+        .collect();
+    events.sort_by_key(|event| event.timestamp);
+    events
+}
+
+fn write_result<W: Write>(
+    writer: &mut W,
+    event: &Event,
+    from: &str,
+    to: &str,
+    typ: &str,
+    real_jid: Option<&str>,
+) -> Result<(), ExportError> {
+    let message = match event.data.as_chat_message() {
+        Some(message) => message,
+        None => return Ok(()),
+    };
+
+    let mut body = message.contents_as_str();
+    let mut urls = Vec::new();
+    for attachment in &message.attachments {
+        let url = match attachment.item.url() {
+            Some(url) => url,
+            None => continue,
+        };
+        if !body.is_empty() {
+            body.push('\n');
+        }
+        body.push_str(url);
+        urls.push(url);
+    }
+
+    let stamp = event.timestamp.to_rfc3339_opts(SecondsFormat::Micros, true);
+    let id = escape_xml(&event.id);
+
+    writeln!(
+        writer,
+        "        <result xmlns='urn:xmpp:mam:2' id='{}'>",
+        id
+    )?;
+    writeln!(writer, "          <forwarded xmlns='urn:xmpp:forward:0'>")?;
+    writeln!(
+        writer,
+        "            <delay xmlns='urn:xmpp:delay' stamp='{}'/>",
+        stamp
+    )?;
+    writeln!(
+        writer,
+        "            <message xmlns='jabber:client' from='{}' to='{}' type='{}' id='{}'>",
+        escape_xml(from),
+        escape_xml(to),
+        typ,
+        id
+    )?;
+    writeln!(writer, "              <body>{}</body>", escape_xml(&body))?;
+    for url in urls {
+        writeln!(
+            writer,
+            "              <x xmlns='jabber:x:oob'><url>{}</url></x>",
+            escape_xml(url)
+        )?;
+    }
+    if let Some(jid) = real_jid {
+        writeln!(
+            writer,
+            "              <x xmlns='http://jabber.org/protocol/muc#user'><item jid='{}'/></x>",
+            escape_xml(jid)
+        )?;
+    }
+    writeln!(writer, "            </message>")?;
+    writeln!(writer, "          </forwarded>")?;
+    writeln!(writer, "        </result>")?;
+    Ok(())
+}
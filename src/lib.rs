@@ -2,15 +2,46 @@
 #[cfg(feature = "raw")]
 pub mod raw;
 
+/// Builders of conversations and events.
+pub mod builder;
+/// Structural differences between two exports.
+pub mod diff;
+/// Exporters to other formats.
+pub mod export;
+/// Local media files shipped in a Takeout.
+pub mod media;
+/// Merging of overlapping exports.
+pub mod merge;
+/// Detection and redaction of personal data in messages.
+#[cfg(feature = "pii")]
+pub mod pii;
+/// Statistics over conversations.
+pub mod stats;
+/// Integrity checks of conversations.
+pub mod validate;
+/// Local web viewer.
+#[cfg(feature = "web")]
+pub mod web;
+
 mod event;
+mod util;
 
 use std::collections::HashMap;
+use std::convert::TryFrom;
 
 pub use crate::event::*;
+pub use crate::util::format_duration;
 pub use chrono;
 
 use chrono::{DateTime, Utc};
 
+/// Largest difference in seconds between the time from the start to the end event of a call
+/// and its reported duration for the two events to belong to the same call.
+///
+/// The reported duration leaves out the time spent ringing, and the events are timestamped
+/// by different clients.
+pub const CALL_TOLERANCE_SECS: i64 = 120;
+
 /// Top-level struct for Hangouts data.
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
@@ -55,10 +86,113 @@ impl Conversation {
             .collect()
     }
 
+    /// Get the name of a participant in the conversation, if present.
+    #[inline]
+    pub fn participant_name(&self, id: &ParticipantId) -> Option<&String> {
+        self.participants.get(id).and_then(Participant::name)
+    }
+
+    /// Returns `true` if the conversation is a group conversation rather than a one-on-one.
+    ///
+    /// Conversations are considered groups if they have a name or more than two participants.
+    #[inline]
+    pub fn is_group(&self) -> bool {
+        self.name.is_some() || self.participants.len() > 2
+    }
+
+    /// Get a human-readable name for the conversation.
+    ///
+    /// This is the conversation name if set, or otherwise the sorted names of its
+    /// participants. Falls back to the conversation ID if no participant has a name.
+    pub fn display_name(&self) -> String {
+        if let Some(name) = &self.name {
+            return name.clone();
+        }
+
+        let mut names: Vec<_> = self
+            .participants
+            .values()
+            .filter_map(Participant::name)
+            .map(String::as_str)
+            .collect();
+        if names.is_empty() {
+            return self.conversation_id.clone();
+        }
+
+        names.sort_unstable();
+        names.join(", ")
+    }
+
+    /// Get the hangout calls in the conversation, from oldest to newest.
+    ///
+    /// Every end event is paired with the latest preceding start event that has not been
+    /// paired yet and lies the duration of the call before it, give or take
+    /// [`CALL_TOLERANCE_SECS`]. End events without such a start event make calls of their own,
+    /// starting the duration of the call before the end, or at the end if the duration is out
+    /// of range. Start events without an end event are ignored.
+    pub fn calls(&self) -> Vec<Call<'_>> {
+        let mut events: Vec<_> = self
+            .events
+            .iter()
+            .filter_map(|event| event.data.as_hangout_event().map(|data| (event, data)))
+            .collect();
+        events.sort_by_key(|(event, _)| event.timestamp);
+
+        let mut open = Vec::new();
+        let mut calls = Vec::new();
+        for (event, data) in events {
+            let duration = match data.typ {
+                HangoutEventType::Start => {
+                    open.push((event, data));
+                    continue;
+                }
+                HangoutEventType::End { duration } => duration,
+            };
+            // Durations come from the input and may be absurdly large.
+            let secs = i64::try_from(duration).ok();
+
+            let start = open
+                .iter()
+                .rposition(|(start, _)| {
+                    let elapsed = (event.timestamp - start.timestamp).num_seconds();
+                    secs.and_then(|secs| elapsed.checked_sub(secs))
+                        .is_some_and(|diff| diff.unsigned_abs() <= CALL_TOLERANCE_SECS as u64)
+                })
+                .map(|i| open.remove(i));
+            let mut participants: Vec<_> = start
+                .iter()
+                .flat_map(|(_, start)| &start.participants)
+                .chain(&data.participants)
+                .collect();
+            participants.sort();
+            participants.dedup();
+
+            calls.push(Call {
+                start_event: start.map(|(event, _)| event),
+                end_event: event,
+                start: match start {
+                    Some((start, _)) => start.timestamp,
+                    None => secs
+                        .and_then(chrono::Duration::try_seconds)
+                        .and_then(|secs| event.timestamp.checked_sub_signed(secs))
+                        .unwrap_or(event.timestamp),
+                },
+                end: event.timestamp,
+                duration,
+                media_type: data
+                    .media_type
+                    .as_ref()
+                    .or_else(|| start.and_then(|(_, start)| start.media_type.as_ref())),
+                participants,
+            });
+        }
+        calls
+    }
+
     /// Sort the events by timestamp, from oldest to newest.
     #[inline]
     pub fn sort_events_by_time(&mut self) {
-        self.events.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+        self.events.sort_by_key(|event| event.timestamp);
     }
 }
 
@@ -70,6 +204,8 @@ pub struct Participant {
     pub fallback_name: Option<String>,
     /// Type of the participant.
     pub typ: Option<ParticipantType>,
+    /// Phone number of the participant, typically present for off-network phone participants.
+    pub phone_number: Option<PhoneNumber>,
 
     /// Invitation status for the participant.
     pub invitation_status: Option<InvitationStatus>,
@@ -87,6 +223,20 @@ impl Participant {
     }
 }
 
+/// Phone number of a participant.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct PhoneNumber {
+    /// Number in E.164 format.
+    pub e164: Option<String>,
+    /// Number formatted for international dialing.
+    pub international: Option<String>,
+    /// Number formatted for national dialing.
+    pub national: Option<String>,
+    /// Region code of the number.
+    pub region_code: Option<String>,
+}
+
 /// Composite ID for a participant user.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
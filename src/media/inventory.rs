@@ -0,0 +1,218 @@
+use crate::media::{MediaRef, MediaResolver};
+use crate::{Hangouts, MediaType, ParticipantId, Thumbnail};
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+use chrono::{DateTime, Utc};
+
+/// Listing of every photo and video attachment in an export.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct MediaInventory {
+    /// All photo attachments, in conversation and event order.
+    pub items: Vec<MediaItem>,
+    /// Groups of duplicate items, as indices into [`Self::items`].
+    ///
+    /// Items are duplicates if they share a `photo_id` or if their local files have the same
+    /// contents. Only groups with more than one item are listed.
+    pub duplicates: Vec<Vec<usize>>,
+    /// Totals by conversation ID.
+    pub by_conversation: HashMap<String, MediaTotals>,
+    /// Totals by sender.
+    pub by_participant: HashMap<ParticipantId, MediaTotals>,
+}
+
+/// A photo or video attachment.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct MediaItem {
+    /// ID of the conversation the attachment was sent in.
+    pub conversation_id: String,
+    /// ID of the event containing the attachment.
+    pub event_id: String,
+    /// ID of the attachment.
+    pub attachment_id: String,
+    /// Sender of the attachment.
+    pub sender: ParticipantId,
+    /// Time the attachment was sent.
+    pub timestamp: DateTime<Utc>,
+    /// The type of media.
+    pub media_type: MediaType,
+    /// ID the media has been assigned.
+    pub photo_id: String,
+    /// Thumbnail data, including its dimensions.
+    pub thumbnail: Thumbnail,
+    /// Local copy of the media, if resolved.
+    pub local: Option<MediaRef>,
+    /// Hash of the contents of the local file, if resolved.
+    pub content_hash: Option<u64>,
+}
+
+/// Number and total local file size of attachments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct MediaTotals {
+    /// Number of attachments.
+    pub count: usize,
+    /// Number of attachments with a local file.
+    pub local_count: usize,
+    /// Total size in bytes of the local files.
+    pub size: u64,
+}
+
+impl MediaInventory {
+    /// List the photo attachments in `hangouts`, resolving local files with `resolver` if
+    /// given.
+    ///
+    /// Local files are read in full to hash their contents.
+    pub fn new(hangouts: &Hangouts, resolver: Option<&MediaResolver>) -> io::Result<Self> {
+        let mut items = Vec::new();
+        let mut hashes: HashMap<&Path, u64> = HashMap::new();
+        let mut local_files = Vec::new();
+
+        for conversation in &hangouts.conversations {
+            for event in &conversation.events {
+                let message = match event.data.as_chat_message() {
+                    Some(message) => message,
+                    None => continue,
+                };
+                for attachment in &message.attachments {
+                    let photo = match &attachment.item.photo {
+                        Some(photo) => photo,
+                        None => continue,
+                    };
+                    let local = resolver.and_then(|r| r.resolve_photo(photo));
+                    local_files.push(local);
+                    items.push(MediaItem {
+                        conversation_id: conversation.conversation_id.clone(),
+                        event_id: event.id.clone(),
+                        attachment_id: attachment.id.clone(),
+                        sender: event.sender.clone(),
+                        timestamp: event.timestamp,
+                        media_type: photo.media_type.clone(),
+                        photo_id: photo.photo_id.clone(),
+                        thumbnail: photo.thumbnail.clone(),
+                        local: local.cloned(),
+                        content_hash: None,
+                    });
+                }
+            }
+        }
+
+        // Hash every distinct local file once.
+        for (item, local) in items.iter_mut().zip(local_files) {
+            if let Some(local) = local {
+                let hash = match hashes.get(local.path.as_path()) {
+                    Some(hash) => *hash,
+                    None => {
+                        let hash = hash_file(File::open(&local.path)?)?;
+                        hashes.insert(&local.path, hash);
+                        hash
+                    }
+                };
+                item.content_hash = Some(hash);
+            }
+        }
+
+        let duplicates = duplicates(&items);
+
+        let mut by_conversation: HashMap<String, MediaTotals> = HashMap::new();
+        let mut by_participant: HashMap<ParticipantId, MediaTotals> = HashMap::new();
+        for item in &items {
+            let totals = [
+                by_conversation
+                    .entry(item.conversation_id.clone())
+                    .or_default(),
+                by_participant.entry(item.sender.clone()).or_default(),
+            ];
+            for totals in totals {
+                totals.count += 1;
+                if let Some(local) = &item.local {
+                    totals.local_count += 1;
+                    totals.size += local.size;
+                }
+            }
+        }
+
+        Ok(Self {
+            items,
+            duplicates,
+            by_conversation,
+            by_participant,
+        })
+    }
+
+    /// Total number and size of all attachments.
+    #[inline]
+    pub fn totals(&self) -> MediaTotals {
+        self.by_conversation
+            .values()
+            .fold(MediaTotals::default(), |acc, totals| MediaTotals {
+                count: acc.count + totals.count,
+                local_count: acc.local_count + totals.local_count,
+                size: acc.size + totals.size,
+            })
+    }
+}
+
+/// Group items sharing a photo ID or local file contents.
+fn duplicates(items: &[MediaItem]) -> Vec<Vec<usize>> {
+    // Union-find over item indices.
+    let mut parents: Vec<usize> = (0..items.len()).collect();
+    fn root(parents: &mut [usize], mut i: usize) -> usize {
+        while parents[i] != i {
+            parents[i] = parents[parents[i]];
+            i = parents[i];
+        }
+        i
+    }
+
+    let mut by_photo_id: HashMap<&str, usize> = HashMap::new();
+    let mut by_contents: HashMap<(u64, u64), usize> = HashMap::new();
+    for (i, item) in items.iter().enumerate() {
+        let mut keys = vec![*by_photo_id.entry(&item.photo_id).or_insert(i)];
+        if let (Some(local), Some(hash)) = (&item.local, item.content_hash) {
+            keys.push(*by_contents.entry((local.size, hash)).or_insert(i));
+        }
+        for j in keys {
+            let (a, b) = (root(&mut parents, i), root(&mut parents, j));
+            parents[a] = b;
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..items.len() {
+        let r = root(&mut parents, i);
+        groups.entry(r).or_default().push(i);
+    }
+
+    let mut groups: Vec<_> = groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    groups.sort();
+    groups
+}
+
+/// 64-bit FNV-1a hash of the contents of `reader`.
+fn hash_file<R: Read>(mut reader: R) -> io::Result<u64> {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    let mut hash = OFFSET;
+    let mut buf = [0; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        for &b in &buf[..n] {
+            hash ^= u64::from(b);
+            hash = hash.wrapping_mul(PRIME);
+        }
+    }
+    Ok(hash)
+}
@@ -0,0 +1,60 @@
+//! Local media files shipped in a Takeout.
+//!
+//! Takeouts ship downloaded photos and videos alongside Hangouts.json, while [`Photo`] only
+//! references them by remote URLs that mostly no longer resolve. [`MediaResolver`] matches
+//! attachments to their local copies, and [`MediaInventory`] lists all photo attachments
+//! with their duplicates and sizes. [`organize_media`] copies them into a folder tree named
+//! by conversation, time and sender.
+//!
+//! [`Photo`]: crate::Photo
+
+mod inventory;
+mod organize;
+mod resolve;
+
+pub use self::inventory::*;
+pub use self::organize::*;
+pub use self::resolve::*;
+
+use std::path::{Path, PathBuf};
+
+/// A local media file.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct MediaRef {
+    /// Path to the file.
+    pub path: PathBuf,
+    /// Size of the file in bytes.
+    pub size: u64,
+    /// MIME type of the file, guessed from its extension.
+    pub mime_type: String,
+}
+
+/// Guess the MIME type of a media file from its extension.
+///
+/// Returns [`None`] if the extension does not belong to a known image, video or audio format.
+pub fn mime_type<P: AsRef<Path>>(path: P) -> Option<&'static str> {
+    let ext = path.as_ref().extension()?.to_str()?.to_lowercase();
+    let mime_type = match ext.as_str() {
+        "jpg" | "jpeg" | "jpe" => "image/jpeg",
+        "png" => "image/png",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "bmp" => "image/bmp",
+        "tif" | "tiff" => "image/tiff",
+        "heic" => "image/heic",
+        "heif" => "image/heif",
+        "mp4" | "m4v" => "video/mp4",
+        "mov" => "video/quicktime",
+        "3gp" => "video/3gpp",
+        "webm" => "video/webm",
+        "mkv" => "video/x-matroska",
+        "avi" => "video/x-msvideo",
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "ogg" => "audio/ogg",
+        "amr" => "audio/amr",
+        _ => return None,
+    };
+    Some(mime_type)
+}
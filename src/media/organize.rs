@@ -0,0 +1,197 @@
+use crate::export::sanitize_file_name;
+use crate::media::{MediaResolver, UnmatchedAttachment};
+use crate::{Conversation, Hangouts, ParticipantId};
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// Options for organizing media files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OrganizeOptions {
+    /// Plan the copies and build the manifest without touching the file system.
+    pub dry_run: bool,
+    /// Overwrite files already present at target paths instead of choosing new names.
+    pub overwrite: bool,
+}
+
+/// Mapping of attachments to their organized media files.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct MediaManifest {
+    /// Paths of the copied files relative to the output directory, by attachment ID.
+    pub files: BTreeMap<String, PathBuf>,
+    /// Photo attachments without a local file, which were not copied.
+    pub missing: Vec<UnmatchedAttachment>,
+}
+
+impl MediaManifest {
+    /// Get the full paths of the copied files below `dir` by attachment ID, as used by the
+    /// exporters.
+    #[inline]
+    pub fn paths<P: AsRef<Path>>(&self, dir: P) -> HashMap<String, PathBuf> {
+        self.files
+            .iter()
+            .map(|(id, path)| (id.clone(), dir.as_ref().join(path)))
+            .collect()
+    }
+}
+
+/// Copy the local files of all photo attachments into `dir`.
+///
+/// Files are placed at `media/<conversation>/<YYYY-MM-DD_HHMMSS>_<sender>_<photo_id>.<ext>`
+/// with times in UTC, and their modification times are set to the times of their events.
+/// Attachments sharing a local file and target name are copied once; names that are taken by
+/// other files get a numeric suffix. Files already in place with the same contents are kept,
+/// so organizing into the same directory again copies nothing new.
+pub fn organize_media<P: AsRef<Path>>(
+    dir: P,
+    hangouts: &Hangouts,
+    resolver: &MediaResolver,
+    options: &OrganizeOptions,
+) -> io::Result<MediaManifest> {
+    let dir = dir.as_ref();
+    let mut manifest = MediaManifest::default();
+    let mut folders = HashSet::new();
+    // Sources of the planned targets.
+    let mut targets: HashMap<PathBuf, PathBuf> = HashMap::new();
+
+    for conversation in &hangouts.conversations {
+        let folder = unique_folder(&mut folders, &conversation.display_name());
+        let folder = Path::new("media").join(folder);
+
+        for event in &conversation.events {
+            let message = match event.data.as_chat_message() {
+                Some(message) => message,
+                None => continue,
+            };
+            for attachment in &message.attachments {
+                let photo = match &attachment.item.photo {
+                    Some(photo) => photo,
+                    None => continue,
+                };
+                let local = match resolver.resolve_photo(photo) {
+                    Some(local) => local,
+                    None => {
+                        manifest.missing.push(UnmatchedAttachment {
+                            conversation_id: conversation.conversation_id.clone(),
+                            event_id: event.id.clone(),
+                            attachment_id: attachment.id.clone(),
+                            photo_id: photo.photo_id.clone(),
+                        });
+                        continue;
+                    }
+                };
+
+                let stem = format!(
+                    "{}_{}_{}",
+                    event.timestamp.format("%Y-%m-%d_%H%M%S"),
+                    sender_name(conversation, &event.sender),
+                    sanitize_file_name(&photo.photo_id),
+                );
+                let ext = local
+                    .path
+                    .extension()
+                    .map(|ext| format!(".{}", ext.to_string_lossy().to_lowercase()))
+                    .unwrap_or_default();
+
+                let mut target = folder.join(format!("{}{}", stem, ext));
+                let mut n = 2;
+                let copy = loop {
+                    match targets.get(&target) {
+                        Some(source) if *source == local.path => break false,
+                        Some(_) => {}
+                        None => {
+                            let full = dir.join(&target);
+                            if options.overwrite || !full.exists() {
+                                break true;
+                            }
+                            // Keep copies made by an earlier run.
+                            if same_contents(&local.path, &full)? {
+                                targets.insert(target.clone(), local.path.clone());
+                                break false;
+                            }
+                        }
+                    }
+                    target = folder.join(format!("{}_{}{}", stem, n, ext));
+                    n += 1;
+                };
+
+                if copy {
+                    targets.insert(target.clone(), local.path.clone());
+                    if !options.dry_run {
+                        let full = dir.join(&target);
+                        fs::create_dir_all(full.parent().unwrap_or(dir))?;
+                        fs::copy(&local.path, &full)?;
+                        let mtime = SystemTime::from(event.timestamp);
+                        File::options()
+                            .write(true)
+                            .open(&full)?
+                            .set_modified(mtime)?;
+                    }
+                }
+                manifest.files.insert(attachment.id.clone(), target);
+            }
+        }
+    }
+
+    Ok(manifest)
+}
+
+/// Returns `true` if the files at `a` and `b` have the same contents.
+fn same_contents(a: &Path, b: &Path) -> io::Result<bool> {
+    if fs::metadata(a)?.len() != fs::metadata(b)?.len() {
+        return Ok(false);
+    }
+
+    let (mut a, mut b) = (File::open(a)?, File::open(b)?);
+    let (mut buf_a, mut buf_b) = ([0; 8192], [0; 8192]);
+    loop {
+        let n = read_full(&mut a, &mut buf_a)?;
+        if n != read_full(&mut b, &mut buf_b)? || buf_a[..n] != buf_b[..n] {
+            return Ok(false);
+        }
+        if n < buf_a.len() {
+            return Ok(true);
+        }
+    }
+}
+
+/// Read into `buf` until it is full or the end of `reader` is reached.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..])? {
+            0 => break,
+            read => n += read,
+        }
+    }
+    Ok(n)
+}
+
+/// Make a folder name for `name` that is unique among those in `used`.
+fn unique_folder(used: &mut HashSet<String>, name: &str) -> String {
+    let base = sanitize_file_name(name);
+    let mut candidate = base.clone();
+    let mut n = 2;
+    // Compare case-insensitively for case-insensitive file systems.
+    while !used.insert(candidate.to_lowercase()) {
+        candidate = format!("{} ({})", base, n);
+        n += 1;
+    }
+    candidate
+}
+
+/// Name of the sender for use in file names, without underscores or whitespace.
+fn sender_name(conversation: &Conversation, sender: &ParticipantId) -> String {
+    let name = conversation
+        .participant_name(sender)
+        .unwrap_or(&sender.gaia_id);
+    sanitize_file_name(name)
+        .split(|c: char| c.is_whitespace() || c == '_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
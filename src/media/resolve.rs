@@ -0,0 +1,256 @@
+use crate::media::{mime_type, MediaRef};
+use crate::util::percent_decode;
+use crate::{AttachmentSegment, Hangouts, Photo};
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Index of the media files in a Takeout directory, used to find the local copies of
+/// attachments.
+///
+/// Photos are matched to files by their `photo_id`, then by the file name in their URLs.
+/// When several files match, one in a folder named after the photo's album is preferred.
+#[derive(Debug, Clone)]
+pub struct MediaResolver {
+    files: Vec<LocalFile>,
+    /// File indices by lowercased file name.
+    by_name: HashMap<String, Vec<usize>>,
+    /// File indices by lowercased alphanumeric tokens of the file stem.
+    by_token: HashMap<String, Vec<usize>>,
+}
+
+#[derive(Debug, Clone)]
+struct LocalFile {
+    media: MediaRef,
+    /// Lowercased names of the directories between the Takeout directory and the file.
+    folders: Vec<String>,
+}
+
+impl MediaResolver {
+    /// Index all media files below `dir`.
+    ///
+    /// Files are recognized as media by their extension (see [`mime_type`]).
+    pub fn new<P: AsRef<Path>>(dir: P) -> io::Result<Self> {
+        let dir = dir.as_ref();
+        let mut paths = Vec::new();
+        walk(dir, &mut paths)?;
+        paths.sort();
+
+        let mut resolver = Self {
+            files: Vec::new(),
+            by_name: HashMap::new(),
+            by_token: HashMap::new(),
+        };
+        for path in paths {
+            let mime_type = match mime_type(&path) {
+                Some(mime_type) => mime_type,
+                None => continue,
+            };
+            let size = fs::metadata(&path)?.len();
+
+            let i = resolver.files.len();
+            let name = file_name(&path);
+            let stem = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            for token in stem.split(|c: char| !c.is_alphanumeric()) {
+                if !token.is_empty() {
+                    resolver
+                        .by_token
+                        .entry(token.to_owned())
+                        .or_default()
+                        .push(i);
+                }
+            }
+            resolver.by_name.entry(name).or_default().push(i);
+
+            let folders = path
+                .parent()
+                .and_then(|parent| parent.strip_prefix(dir).ok())
+                .map(|parent| {
+                    parent
+                        .iter()
+                        .map(|folder| folder.to_string_lossy().to_lowercase())
+                        .collect()
+                })
+                .unwrap_or_default();
+            resolver.files.push(LocalFile {
+                media: MediaRef {
+                    path,
+                    size,
+                    mime_type: mime_type.to_owned(),
+                },
+                folders,
+            });
+        }
+
+        Ok(resolver)
+    }
+
+    /// All indexed media files.
+    #[inline]
+    pub fn files(&self) -> impl Iterator<Item = &MediaRef> {
+        self.files.iter().map(|file| &file.media)
+    }
+
+    /// Find the local file of a photo.
+    #[inline]
+    pub fn resolve_photo(&self, photo: &Photo) -> Option<&MediaRef> {
+        self.find(photo).map(|i| &self.files[i].media)
+    }
+
+    /// Find the local file of an attachment, if it is a photo.
+    #[inline]
+    pub fn resolve(&self, attachment: &AttachmentSegment) -> Option<&MediaRef> {
+        attachment
+            .item
+            .photo
+            .as_ref()
+            .and_then(|photo| self.resolve_photo(photo))
+    }
+
+    /// Resolve every photo attachment in `hangouts`, reporting attachments without a local
+    /// file and local files that belong to no attachment.
+    pub fn resolve_all(&self, hangouts: &Hangouts) -> MediaReport {
+        let mut resolved = HashMap::new();
+        let mut unmatched = Vec::new();
+        let mut used = HashSet::new();
+
+        for conversation in &hangouts.conversations {
+            for event in &conversation.events {
+                let message = match event.data.as_chat_message() {
+                    Some(message) => message,
+                    None => continue,
+                };
+                for attachment in &message.attachments {
+                    let photo = match &attachment.item.photo {
+                        Some(photo) => photo,
+                        None => continue,
+                    };
+                    match self.find(photo) {
+                        Some(i) => {
+                            used.insert(i);
+                            resolved.insert(attachment.id.clone(), self.files[i].media.clone());
+                        }
+                        None => unmatched.push(UnmatchedAttachment {
+                            conversation_id: conversation.conversation_id.clone(),
+                            event_id: event.id.clone(),
+                            attachment_id: attachment.id.clone(),
+                            photo_id: photo.photo_id.clone(),
+                        }),
+                    }
+                }
+            }
+        }
+
+        let orphaned = (0..self.files.len())
+            .filter(|i| !used.contains(i))
+            .map(|i| self.files[i].media.clone())
+            .collect();
+
+        MediaReport {
+            resolved,
+            unmatched,
+            orphaned,
+        }
+    }
+
+    fn find(&self, photo: &Photo) -> Option<usize> {
+        let album = photo.album_id.to_lowercase();
+
+        let photo_id = photo.photo_id.to_lowercase();
+        if let Some(candidates) = self.by_token.get(&photo_id) {
+            return Some(self.prefer_album(candidates, &album));
+        }
+
+        let urls = [
+            Some(&photo.original_url),
+            photo.download_url.as_ref(),
+            Some(&photo.url),
+        ];
+        for url in urls.iter().flatten() {
+            let candidates = url_file_name(url).and_then(|name| self.by_name.get(&name));
+            if let Some(candidates) = candidates {
+                return Some(self.prefer_album(candidates, &album));
+            }
+        }
+
+        None
+    }
+
+    fn prefer_album(&self, candidates: &[usize], album: &str) -> usize {
+        candidates
+            .iter()
+            .copied()
+            .find(|&i| self.files[i].folders.iter().any(|folder| folder == album))
+            .unwrap_or(candidates[0])
+    }
+}
+
+/// Result of resolving all attachments of an export.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct MediaReport {
+    /// Local files of resolved attachments, by attachment ID.
+    pub resolved: HashMap<String, MediaRef>,
+    /// Photo attachments without a local file.
+    pub unmatched: Vec<UnmatchedAttachment>,
+    /// Local media files that belong to no attachment.
+    pub orphaned: Vec<MediaRef>,
+}
+
+impl MediaReport {
+    /// Get the paths of the resolved files by attachment ID, as used by the exporters.
+    #[inline]
+    pub fn paths(&self) -> HashMap<String, PathBuf> {
+        self.resolved
+            .iter()
+            .map(|(id, media)| (id.clone(), media.path.clone()))
+            .collect()
+    }
+}
+
+/// A photo attachment without a local file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct UnmatchedAttachment {
+    /// ID of the conversation containing the attachment.
+    pub conversation_id: String,
+    /// ID of the event containing the attachment.
+    pub event_id: String,
+    /// ID of the attachment.
+    pub attachment_id: String,
+    /// ID of the photo.
+    pub photo_id: String,
+}
+
+fn walk(dir: &Path, paths: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            walk(&entry.path(), paths)?;
+        } else if file_type.is_file() {
+            paths.push(entry.path());
+        }
+    }
+    Ok(())
+}
+
+#[inline]
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Get the lowercased, percent-decoded last path segment of `url`.
+fn url_file_name(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next()?;
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    let segment = path.rsplit('/').find(|segment| !segment.is_empty())?;
+    Some(percent_decode(segment).to_lowercase())
+}
@@ -0,0 +1,349 @@
+//! Merging of overlapping exports.
+//!
+//! Takeouts made at different times, or by different accounts taking part in the same
+//! conversations, cover overlapping history. [`Hangouts::merge`] unifies them:
+//!
+//! - Conversations are matched by [`Conversation::conversation_id`]. The name, current
+//!   participants, self state and link sharing status are taken from the copy with the later
+//!   sort timestamp.
+//! - Events are matched by [`Event::id`], keeping the copy with the higher
+//!   [`Event::version`]. Merged events are sorted by time.
+//! - Participants are united. Names and other missing data are filled in from either copy,
+//!   preferring the newer conversation, and read states keep the later time.
+//!
+//! Differences that had to be settled this way are listed as [`Conflict`]s in the
+//! [`MergeReport`].
+
+use crate::{Conversation, Event, Hangouts, Participant, ParticipantId};
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+/// Summary of a merge.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct MergeReport {
+    /// Number of conversations only present in the merged export.
+    pub conversations_added: usize,
+    /// Number of conversations present in both exports.
+    pub conversations_merged: usize,
+    /// Number of events only present in the merged export.
+    pub events_added: usize,
+    /// Number of events replaced by a newer version from the merged export.
+    pub events_replaced: usize,
+    /// Number of events of the merged export already present in the same or a newer version.
+    pub events_duplicate: usize,
+    /// Differences settled while merging.
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MergeReport {
+    /// Add the counts and conflicts of `other` to this report.
+    pub fn extend(&mut self, other: MergeReport) {
+        self.conversations_added += other.conversations_added;
+        self.conversations_merged += other.conversations_merged;
+        self.events_added += other.events_added;
+        self.events_replaced += other.events_replaced;
+        self.events_duplicate += other.events_duplicate;
+        self.conflicts.extend(other.conflicts);
+    }
+}
+
+impl fmt::Display for MergeReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(
+            f,
+            "{} conversations added, {} merged; {} events added, {} replaced, {} duplicate; {} conflicts",
+            self.conversations_added,
+            self.conversations_merged,
+            self.events_added,
+            self.events_replaced,
+            self.events_duplicate,
+            self.conflicts.len()
+        )
+    }
+}
+
+/// A difference between two copies of a conversation, and how it was settled.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde-impl", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Conflict {
+    /// The conversation has different names.
+    ConversationName {
+        conversation_id: String,
+        kept: Option<String>,
+        dropped: Option<String>,
+    },
+    /// A participant has different names.
+    ParticipantName {
+        conversation_id: String,
+        participant: ParticipantId,
+        kept: String,
+        dropped: String,
+    },
+    /// A participant has read the conversation up to different times.
+    ReadState {
+        conversation_id: String,
+        participant: ParticipantId,
+        kept: DateTime<Utc>,
+        dropped: DateTime<Utc>,
+    },
+    /// An event has the same ID and version but different contents. The copy already
+    /// present is kept.
+    Event {
+        conversation_id: String,
+        event_id: String,
+        version: u64,
+    },
+}
+
+impl Conflict {
+    /// Get the ID of the conversation of the conflict.
+    #[inline]
+    pub fn conversation_id(&self) -> &str {
+        match self {
+            Self::ConversationName {
+                conversation_id, ..
+            }
+            | Self::ParticipantName {
+                conversation_id, ..
+            }
+            | Self::ReadState {
+                conversation_id, ..
+            }
+            | Self::Event {
+                conversation_id, ..
+            } => conversation_id,
+        }
+    }
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        let name = |name: &Option<String>| match name {
+            Some(name) => format!("{:?}", name),
+            None => "no name".to_owned(),
+        };
+        match self {
+            Self::ConversationName {
+                conversation_id,
+                kept,
+                dropped,
+            } => write!(
+                f,
+                "{}: name {} kept over {}",
+                conversation_id,
+                name(kept),
+                name(dropped)
+            ),
+            Self::ParticipantName {
+                conversation_id,
+                participant,
+                kept,
+                dropped,
+            } => write!(
+                f,
+                "{}: participant {} named {:?} kept over {:?}",
+                conversation_id, participant.gaia_id, kept, dropped
+            ),
+            Self::ReadState {
+                conversation_id,
+                participant,
+                kept,
+                dropped,
+            } => write!(
+                f,
+                "{}: participant {} read up to {} kept over {}",
+                conversation_id,
+                participant.gaia_id,
+                kept.to_rfc3339(),
+                dropped.to_rfc3339()
+            ),
+            Self::Event {
+                conversation_id,
+                event_id,
+                version,
+            } => write!(
+                f,
+                "{}: event {} differs in version {}",
+                conversation_id, event_id, version
+            ),
+        }
+    }
+}
+
+impl Hangouts {
+    /// Merge the conversations of `other` into this export.
+    ///
+    /// To merge more than two exports, merge them one after the other and
+    /// [`extend`](MergeReport::extend) the reports.
+    pub fn merge(&mut self, other: Hangouts) -> MergeReport {
+        let mut report = MergeReport::default();
+        let mut index: HashMap<String, usize> = self
+            .conversations
+            .iter()
+            .enumerate()
+            .map(|(i, conversation)| (conversation.conversation_id.clone(), i))
+            .collect();
+
+        for conversation in other.conversations {
+            match index.get(&conversation.conversation_id) {
+                Some(&i) => {
+                    merge_conversation(&mut self.conversations[i], conversation, &mut report)
+                }
+                None => {
+                    report.conversations_added += 1;
+                    report.events_added += conversation.events.len();
+                    index.insert(
+                        conversation.conversation_id.clone(),
+                        self.conversations.len(),
+                    );
+                    self.conversations.push(conversation);
+                }
+            }
+        }
+        report
+    }
+}
+
+fn merge_conversation(ours: &mut Conversation, theirs: Conversation, report: &mut MergeReport) {
+    report.conversations_merged += 1;
+    let conversation_id = ours.conversation_id.clone();
+    let newer = theirs.sort_timestamp > ours.sort_timestamp;
+
+    if ours.name != theirs.name {
+        let (kept, dropped) = if newer {
+            (theirs.name.clone(), ours.name.clone())
+        } else {
+            (ours.name.clone(), theirs.name.clone())
+        };
+        report.conflicts.push(Conflict::ConversationName {
+            conversation_id: conversation_id.clone(),
+            kept,
+            dropped,
+        });
+    }
+    if newer {
+        ours.name = theirs.name;
+        ours.current_participants = theirs.current_participants;
+        ours.self_state = theirs.self_state;
+        ours.sort_timestamp = theirs.sort_timestamp;
+        ours.group_link_sharing_status = theirs.group_link_sharing_status;
+    }
+
+    // Sorted so that conflicts are reported in a stable order.
+    let mut participants: Vec<_> = theirs.participants.into_iter().collect();
+    participants.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (id, participant) in participants {
+        match ours.participants.entry(id) {
+            Entry::Vacant(entry) => {
+                entry.insert(participant);
+            }
+            Entry::Occupied(mut entry) => {
+                let id = entry.key().clone();
+                merge_participant(
+                    &conversation_id,
+                    &id,
+                    entry.get_mut(),
+                    participant,
+                    newer,
+                    report,
+                );
+            }
+        }
+    }
+
+    let mut positions: HashMap<String, usize> = ours
+        .events
+        .iter()
+        .enumerate()
+        .map(|(i, event)| (event.id.clone(), i))
+        .collect();
+    let mut changed = false;
+    for event in theirs.events {
+        match positions.get(&event.id) {
+            None => {
+                positions.insert(event.id.clone(), ours.events.len());
+                ours.events.push(event);
+                report.events_added += 1;
+                changed = true;
+            }
+            Some(&i) => {
+                let known: &mut Event = &mut ours.events[i];
+                if event.version > known.version {
+                    *known = event;
+                    report.events_replaced += 1;
+                    changed = true;
+                } else {
+                    if event.version == known.version && event != *known {
+                        report.conflicts.push(Conflict::Event {
+                            conversation_id: conversation_id.clone(),
+                            event_id: event.id,
+                            version: event.version,
+                        });
+                    }
+                    report.events_duplicate += 1;
+                }
+            }
+        }
+    }
+    if changed {
+        ours.sort_events_by_time();
+    }
+}
+
+fn merge_participant(
+    conversation_id: &str,
+    id: &ParticipantId,
+    ours: &mut Participant,
+    theirs: Participant,
+    newer: bool,
+    report: &mut MergeReport,
+) {
+    match (&mut ours.fallback_name, theirs.fallback_name) {
+        (Some(kept), Some(name)) if *kept != name => {
+            let dropped = if newer {
+                std::mem::replace(kept, name)
+            } else {
+                name
+            };
+            report.conflicts.push(Conflict::ParticipantName {
+                conversation_id: conversation_id.to_owned(),
+                participant: id.clone(),
+                kept: kept.clone(),
+                dropped,
+            });
+        }
+        (kept @ None, name) => *kept = name,
+        _ => {}
+    }
+
+    if ours.typ.is_none() || (newer && theirs.typ.is_some()) {
+        ours.typ = theirs.typ;
+    }
+    if ours.phone_number.is_none() || (newer && theirs.phone_number.is_some()) {
+        ours.phone_number = theirs.phone_number;
+    }
+    if ours.invitation_status.is_none() || (newer && theirs.invitation_status.is_some()) {
+        ours.invitation_status = theirs.invitation_status;
+    }
+    if ours.new_invitation_status.is_none() || (newer && theirs.new_invitation_status.is_some()) {
+        ours.new_invitation_status = theirs.new_invitation_status;
+    }
+
+    let (kept, dropped) = (ours.read_state.timestamp, theirs.read_state.timestamp);
+    if kept != dropped {
+        let (kept, dropped) = (kept.max(dropped), kept.min(dropped));
+        ours.read_state.timestamp = kept;
+        report.conflicts.push(Conflict::ReadState {
+            conversation_id: conversation_id.to_owned(),
+            participant: id.clone(),
+            kept,
+            dropped,
+        });
+    }
+}
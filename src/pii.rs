@@ -0,0 +1,497 @@
+//! Detection and redaction of personal data in message text.
+//!
+//! A [`Scanner`] looks for emails, phone numbers, payment card numbers, IBANs, street
+//! addresses, API keys and passwords in the text of chat message segments. Each
+//! [`Finding`] names the conversation, event and segment it was found in, with character
+//! offsets into the segment text, and can be passed to [`redact`] or [`redact_raw`] to
+//! replace the found text with a placeholder such as `[EMAIL]`.
+//!
+//! Detection is pattern-based and errs on the side of reporting: card numbers and IBANs are
+//! checked with their checksums, but phone numbers and addresses may include false
+//! positives, and data split across segments is not found. Addresses are recognized in
+//! English (`221B Baker Street`) and German (`Hauptstraße 5`) forms.
+
+#[cfg(feature = "raw")]
+use crate::raw;
+use crate::{ChatSegment, Conversation, EventData, Hangouts};
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use regex::Regex;
+
+/// Kind of personal data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde-impl",
+    derive(serde::Deserialize, serde::Serialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum PiiKind {
+    Email,
+    Phone,
+    CreditCard,
+    Iban,
+    Address,
+    ApiKey,
+    Password,
+}
+
+impl PiiKind {
+    /// Every kind, in the order in which overlapping findings are preferred.
+    pub const ALL: &'static [PiiKind] = &[
+        Self::ApiKey,
+        Self::Password,
+        Self::Email,
+        Self::Iban,
+        Self::CreditCard,
+        Self::Phone,
+        Self::Address,
+    ];
+
+    /// Get the placeholder replacing data of this kind in redacted text.
+    #[inline]
+    pub fn placeholder(&self) -> &'static str {
+        match self {
+            Self::Email => "[EMAIL]",
+            Self::Phone => "[PHONE]",
+            Self::CreditCard => "[CREDIT CARD]",
+            Self::Iban => "[IBAN]",
+            Self::Address => "[ADDRESS]",
+            Self::ApiKey => "[API KEY]",
+            Self::Password => "[PASSWORD]",
+        }
+    }
+
+    /// Patterns of the kind. A `value` group, if present, is the part that is reported.
+    fn patterns(&self) -> &'static [&'static str] {
+        match self {
+            Self::Email => &[r"[A-Za-z0-9._%+-]+@[A-Za-z0-9-]+(?:\.[A-Za-z0-9-]+)*\.[A-Za-z]{2,}"],
+            Self::Phone => {
+                &[r"(?:\+\d{1,3}[ .-]?)?(?:\(\d{1,4}\)[ .-]?)?\d{2,4}(?:[ .-]?\d{2,4}){1,4}"]
+            }
+            Self::CreditCard => &[r"\d(?:[ -]?\d){12,18}"],
+            Self::Iban => &[r"[A-Z]{2}\d{2}(?: ?[A-Z0-9]{4}){2,7}(?: ?[A-Z0-9]{1,4})?"],
+            Self::Address => &[
+                r"\b\d{1,5}[A-Za-z]?\s+(?:[A-Z][A-Za-z'.-]*\s+){1,4}(?i:street|st|avenue|ave|road|rd|boulevard|blvd|lane|ln|drive|dr|court|ct|way|place|pl|terrace|square|sq|parkway|pkwy|highway|hwy)\b\.?",
+                r"\b[A-ZÄÖÜ][a-zäöüß]+(?:straße|strasse|weg|gasse|platz|allee)\s+\d{1,4}[a-z]?\b",
+            ],
+            Self::ApiKey => &[
+                r"\bAKIA[0-9A-Z]{16}\b",
+                r"\bAIza[0-9A-Za-z_-]{35}",
+                r"\bgh[pousr]_[A-Za-z0-9]{36,255}\b",
+                r"\bgithub_pat_[A-Za-z0-9_]{22,255}\b",
+                r"\bxox[abposr]-[A-Za-z0-9-]{10,}",
+                r"\b[rs]k_live_[0-9A-Za-z]{24,}\b",
+                r"\bsk-[A-Za-z0-9_-]{20,}",
+                r"-----BEGIN (?:[A-Z]+ )*PRIVATE KEY-----",
+                r"(?i)\b(?:api[_ -]?key|access[_ -]?token|secret[_ -]?key|client[_ -]?secret)\b\s*(?:is\s+|[:=]\s*)(?P<value>\S{8,})",
+            ],
+            Self::Password => &[
+                r"(?i)\b(?:password|passwd|pwd|passcode|passphrase|passwort|mot de passe|contraseña)\b\s*(?:is\s+|[:=]\s*)(?P<value>\S{4,})",
+            ],
+        }
+    }
+
+    /// Check a match of the kind's patterns beyond what the pattern expresses.
+    fn accepts(&self, found: &str) -> bool {
+        // Keep already redacted text, such as `password: [PASSWORD]`, from being found again.
+        if Self::ALL.iter().any(|kind| kind.placeholder() == found) {
+            return false;
+        }
+        let digits: Vec<u32> = found.chars().filter_map(|c| c.to_digit(10)).collect();
+        match self {
+            Self::Phone => {
+                let formatted = found.contains(['+', '(', ' ', '.', '-']);
+                (7..=15).contains(&digits.len())
+                    && (formatted || digits.len() >= 10)
+                    && !is_date_or_ip(found)
+            }
+            Self::CreditCard => (13..=19).contains(&digits.len()) && luhn(&digits),
+            Self::Iban => is_iban(found),
+            _ => true,
+        }
+    }
+
+    /// Whether matches must not be directly preceded or followed by a letter or digit.
+    #[inline]
+    fn needs_boundaries(&self) -> bool {
+        matches!(self, Self::Phone | Self::CreditCard | Self::Iban)
+    }
+}
+
+/// Options of a [`Scanner`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PiiOptions {
+    /// Kinds of data looked for.
+    pub kinds: Vec<PiiKind>,
+}
+
+impl Default for PiiOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            kinds: PiiKind::ALL.to_vec(),
+        }
+    }
+}
+
+/// Personal data found in a text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match {
+    pub kind: PiiKind,
+    /// Character offsets of the data in the text.
+    pub range: Range<usize>,
+}
+
+/// Personal data found in a chat message segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct Finding {
+    pub conversation_id: String,
+    pub event_id: String,
+    /// Index of the segment in the message contents.
+    pub segment: usize,
+    pub kind: PiiKind,
+    /// Character offset of the start of the data in the segment text.
+    pub start: usize,
+    /// Character offset of the end of the data in the segment text.
+    pub end: usize,
+    /// The found data.
+    pub text: String,
+}
+
+/// Compiled patterns looking for personal data.
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    patterns: Vec<(PiiKind, Regex)>,
+}
+
+impl Scanner {
+    /// Compile the patterns of the kinds in `options`.
+    pub fn new(options: &PiiOptions) -> Self {
+        let patterns = PiiKind::ALL
+            .iter()
+            .filter(|kind| options.kinds.contains(kind))
+            .flat_map(|&kind| {
+                kind.patterns()
+                    .iter()
+                    .map(move |pattern| (kind, Regex::new(pattern).expect("valid pattern")))
+            })
+            .collect();
+        Self { patterns }
+    }
+
+    /// Find personal data in `text`, in order of position.
+    ///
+    /// Overlapping matches are resolved in favor of the kind listed first in
+    /// [`PiiKind::ALL`].
+    pub fn find(&self, text: &str) -> Vec<Match> {
+        let mut found: Vec<(PiiKind, Range<usize>)> = Vec::new();
+        for (kind, regex) in &self.patterns {
+            for captures in regex.captures_iter(text) {
+                let m = captures
+                    .name("value")
+                    .or_else(|| captures.get(0))
+                    .expect("match");
+                let range = m.range();
+                if !kind.accepts(m.as_str())
+                    || (kind.needs_boundaries() && !is_bounded(text, &range))
+                    || found
+                        .iter()
+                        .any(|(_, other)| range.start < other.end && other.start < range.end)
+                {
+                    continue;
+                }
+                found.push((*kind, range));
+            }
+        }
+        found.sort_by_key(|(_, range)| range.start);
+
+        found
+            .into_iter()
+            .map(|(kind, range)| {
+                let start = text[..range.start].chars().count();
+                let len = text[range].chars().count();
+                Match {
+                    kind,
+                    range: start..start + len,
+                }
+            })
+            .collect()
+    }
+
+    /// Find personal data in the chat messages of every conversation.
+    #[inline]
+    pub fn scan(&self, hangouts: &Hangouts) -> Vec<Finding> {
+        hangouts
+            .conversations
+            .iter()
+            .flat_map(|conversation| self.scan_conversation(conversation))
+            .collect()
+    }
+
+    /// Find personal data in the chat messages of a conversation.
+    pub fn scan_conversation(&self, conversation: &Conversation) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for event in &conversation.events {
+            if let Some(message) = event.data.as_chat_message() {
+                let texts = message.contents.iter().map(scanned_text);
+                self.scan_segments(
+                    &conversation.conversation_id,
+                    &event.id,
+                    texts,
+                    &mut findings,
+                );
+            }
+        }
+        findings
+    }
+
+    /// Find personal data in the chat messages of a raw conversation.
+    #[cfg(feature = "raw")]
+    pub fn scan_raw_conversation(&self, conversation: &raw::Conversation) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for event in &conversation.events {
+            if let raw::EventData::ChatMessage {
+                message_content, ..
+            } = &event.data
+            {
+                let texts = message_content.segments.iter().map(raw_scanned_text);
+                self.scan_segments(
+                    &conversation.header.conversation_id.id,
+                    &event.header.event_id,
+                    texts,
+                    &mut findings,
+                );
+            }
+        }
+        findings
+    }
+
+    fn scan_segments<'a, I>(
+        &self,
+        conversation_id: &str,
+        event_id: &str,
+        texts: I,
+        findings: &mut Vec<Finding>,
+    ) where
+        I: Iterator<Item = Option<&'a str>>,
+    {
+        for (segment, text) in texts.enumerate() {
+            let text = match text {
+                Some(text) => text,
+                None => continue,
+            };
+            for m in self.find(text) {
+                findings.push(Finding {
+                    conversation_id: conversation_id.to_owned(),
+                    event_id: event_id.to_owned(),
+                    segment,
+                    kind: m.kind,
+                    start: m.range.start,
+                    end: m.range.end,
+                    text: text
+                        .chars()
+                        .skip(m.range.start)
+                        .take(m.range.len())
+                        .collect(),
+                });
+            }
+        }
+    }
+}
+
+/// Replace the data of `findings` in the chat messages of `hangouts` with placeholders.
+///
+/// Findings whose text no longer matches the message are skipped. When the text of a link
+/// is redacted, the data is also removed from its target.
+pub fn redact(hangouts: &mut Hangouts, findings: &[Finding]) {
+    let by_event = group(findings);
+    for conversation in &mut hangouts.conversations {
+        for event in &mut conversation.events {
+            let key = (conversation.conversation_id.as_str(), event.id.as_str());
+            let (findings, message) = match (by_event.get(&key), &mut event.data) {
+                (Some(findings), EventData::ChatMessage(message)) => (findings, message),
+                _ => continue,
+            };
+            for finding in findings {
+                match message.contents.get_mut(finding.segment) {
+                    Some(ChatSegment::Text { text, .. }) => {
+                        redact_text(text, finding);
+                    }
+                    Some(ChatSegment::Link {
+                        text,
+                        target,
+                        display_url,
+                        ..
+                    }) => redact_link(text, target, display_url.as_mut(), finding),
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Replace the data of `findings` in the chat messages of a raw conversation with
+/// placeholders, like [`redact`].
+#[cfg(feature = "raw")]
+pub fn redact_raw(conversation: &mut raw::Conversation, findings: &[Finding]) {
+    let by_event = group(findings);
+    let conversation_id = conversation.header.conversation_id.id.as_str();
+    for event in &mut conversation.events {
+        let key = (conversation_id, event.header.event_id.as_str());
+        let (findings, segments) = match (by_event.get(&key), &mut event.data) {
+            (
+                Some(findings),
+                raw::EventData::ChatMessage {
+                    message_content, ..
+                },
+            ) => (findings, &mut message_content.segments),
+            _ => continue,
+        };
+        for finding in findings {
+            match segments.get_mut(finding.segment) {
+                Some(raw::ChatSegment::Text { text, .. }) => {
+                    redact_text(text, finding);
+                }
+                Some(raw::ChatSegment::Link {
+                    text, link_data, ..
+                }) => redact_link(
+                    text,
+                    &mut link_data.link_target,
+                    link_data.display_url.as_mut(),
+                    finding,
+                ),
+                _ => {}
+            }
+        }
+    }
+}
+
+/// Group findings by conversation and event ID, latest in each segment first so that
+/// redacting one does not shift the offsets of the others.
+fn group(findings: &[Finding]) -> HashMap<(&str, &str), Vec<&Finding>> {
+    let mut by_event: HashMap<_, Vec<_>> = HashMap::new();
+    for finding in findings {
+        by_event
+            .entry((finding.conversation_id.as_str(), finding.event_id.as_str()))
+            .or_default()
+            .push(finding);
+    }
+    for findings in by_event.values_mut() {
+        findings.sort_by_key(|finding| std::cmp::Reverse((finding.segment, finding.start)));
+    }
+    by_event
+}
+
+/// Replace the data of `finding` in `text`, returning whether it was found.
+fn redact_text(text: &mut String, finding: &Finding) -> bool {
+    let byte = |offset: usize| {
+        text.char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(text.len()))
+            .nth(offset)
+    };
+    match (byte(finding.start), byte(finding.end)) {
+        (Some(start), Some(end)) if start <= end && text[start..end] == finding.text => {
+            text.replace_range(start..end, finding.kind.placeholder());
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Replace the data of `finding` in the text of a link, and everywhere in its target.
+fn redact_link(
+    text: &mut String,
+    target: &mut String,
+    display_url: Option<&mut String>,
+    finding: &Finding,
+) {
+    if redact_text(text, finding) && !finding.text.is_empty() {
+        for url in std::iter::once(target).chain(display_url) {
+            *url = url.replace(&finding.text, finding.kind.placeholder());
+        }
+    }
+}
+
+/// Text of a segment that is scanned, excluding line breaks.
+#[inline]
+fn scanned_text(segment: &ChatSegment) -> Option<&str> {
+    match segment {
+        ChatSegment::Text { text, .. } | ChatSegment::Link { text, .. } => Some(text),
+        ChatSegment::LinkBreak { .. } => None,
+    }
+}
+
+#[cfg(feature = "raw")]
+#[inline]
+fn raw_scanned_text(segment: &raw::ChatSegment) -> Option<&str> {
+    match segment {
+        raw::ChatSegment::Text { text, .. } | raw::ChatSegment::Link { text, .. } => Some(text),
+        raw::ChatSegment::LineBreak { .. } => None,
+    }
+}
+
+/// Whether the byte `range` of `text` is not adjacent to letters or digits.
+fn is_bounded(text: &str, range: &Range<usize>) -> bool {
+    let before = text[..range.start].chars().next_back();
+    let after = text[range.end..].chars().next();
+    !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+}
+
+/// Whether a phone number candidate is rather a date such as `2016-04-12` or an IPv4
+/// address.
+fn is_date_or_ip(s: &str) -> bool {
+    let groups: Vec<&str> = s.split(['-', '.', '/']).collect();
+    let numeric = groups
+        .iter()
+        .all(|group| !group.is_empty() && group.bytes().all(|b| b.is_ascii_digit()));
+    numeric
+        && match groups.as_slice() {
+            [a, b, c] => {
+                (a.len() == 4 && b.len() <= 2 && c.len() <= 2)
+                    || (a.len() <= 2 && b.len() <= 2 && (c.len() == 2 || c.len() == 4))
+            }
+            [_, _, _, _] => groups.iter().all(|group| group.len() <= 3),
+            _ => false,
+        }
+}
+
+/// Luhn checksum of payment card numbers.
+fn luhn(digits: &[u32]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, &d)| match (i % 2, d * 2) {
+            (0, _) => d,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// ISO 13616 check of an IBAN, ignoring spaces.
+fn is_iban(s: &str) -> bool {
+    let compact: Vec<char> = s.chars().filter(|c| *c != ' ').collect();
+    if !(15..=34).contains(&compact.len()) {
+        return false;
+    }
+    // Move the country code and check digits to the end, then read letters as 10 to 35.
+    let mut remainder = 0;
+    for c in compact[4..].iter().chain(&compact[..4]) {
+        let value = match c.to_digit(36) {
+            Some(value) => value,
+            None => return false,
+        };
+        remainder = if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        };
+    }
+    remainder == 1
+}
@@ -0,0 +1,331 @@
+//! Pseudonymization of raw Hangouts data.
+//!
+//! An [`Anonymizer`] rewrites raw conversations in place so that they can be shared as bug
+//! reproductions or datasets. The result keeps the shape of the original and still
+//! serializes to valid Hangouts.json:
+//!
+//! - Gaia and chat IDs, conversation IDs and photo IDs are replaced with pseudonyms of the
+//!   same length and character classes. The same ID always gets the same pseudonym.
+//! - Fallback names of participants become `Person 1`, `Person 2`, … in order of first
+//!   appearance.
+//! - Message text is rewritten character by character (see [`TextMode`]), keeping its
+//!   length, whitespace, punctuation and emoji, and the segments, formatting and line breaks
+//!   of messages.
+//! - Link targets, photo and place URLs are replaced with [`REDACTED_URL`], place
+//!   coordinates are zeroed, and postal addresses, phone numbers and domain IDs are removed.
+//!
+//! Timestamps, event types and the number and order of events are kept. Output is
+//! deterministic for a given seed. The seed only makes runs reproducible and is not a
+//! cryptographic key: pseudonyms and hashed words come from a non-cryptographic hash, so
+//! with a known or guessable seed they can be matched against guessed IDs and words. Use
+//! [`TextMode::Scramble`] when words must not be recoverable from the output.
+
+use crate::raw::{
+    ChatSegment, Conversation, EventData, Hangouts, ParticipantId, PlaceV2, PlusPhoto,
+    RepresentativeImage,
+};
+use crate::util::{mix, Rng};
+
+use std::collections::{HashMap, HashSet};
+
+/// URL replacing every link target, photo URL and place URL.
+pub const REDACTED_URL: &str = "https://example.com/";
+
+/// Options of an [`Anonymizer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AnonymizeOptions {
+    /// Seed of the pseudonyms and rewritten text.
+    pub seed: u64,
+    /// How message text is rewritten.
+    pub text: TextMode,
+}
+
+impl Default for AnonymizeOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            text: TextMode::Scramble,
+        }
+    }
+}
+
+/// How message text is rewritten.
+///
+/// Either way, ASCII letters and digits are replaced with random letters and digits of the
+/// same case, other letters with ASCII letters, and everything else is kept.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextMode {
+    /// Replace every character independently, so that equal words differ between messages.
+    Scramble,
+    /// Replace every word with a pseudo-word derived from a seeded hash of the word, so that
+    /// repeated words stay equal and word frequencies are preserved. Frequent words can be
+    /// recovered by matching frequencies or guesses.
+    Hash,
+}
+
+/// Rewriter of raw conversations, keeping its pseudonyms consistent across calls.
+#[derive(Debug, Clone)]
+pub struct Anonymizer {
+    options: AnonymizeOptions,
+    /// Pseudonyms by original ID.
+    ids: HashMap<String, String>,
+    /// Pseudonyms already given out.
+    taken: HashSet<String>,
+    /// Pseudonymous names by original Gaia ID.
+    names: HashMap<String, String>,
+}
+
+impl Anonymizer {
+    #[inline]
+    pub fn new(options: AnonymizeOptions) -> Self {
+        Self {
+            options,
+            ids: HashMap::new(),
+            taken: HashSet::new(),
+            names: HashMap::new(),
+        }
+    }
+
+    /// Anonymize every conversation of `hangouts`.
+    #[inline]
+    pub fn anonymize(&mut self, hangouts: &mut Hangouts) {
+        for conversation in &mut hangouts.conversations {
+            self.anonymize_conversation(conversation);
+        }
+    }
+
+    /// Anonymize a single conversation, such as one read by
+    /// [`for_each_conversation`](crate::raw::stream::for_each_conversation).
+    pub fn anonymize_conversation(&mut self, conversation: &mut Conversation) {
+        let header = &mut conversation.header;
+        self.id(&mut header.conversation_id.id);
+
+        let details = &mut header.details;
+        self.id(&mut details.id.id);
+        if let Some(name) = &mut details.name {
+            *name = self.hash_text(name);
+        }
+
+        let state = &mut details.self_conversation_state;
+        self.participant(&mut state.self_read_state.participant_id);
+        self.participant(&mut state.inviter_id);
+        for read_state in &mut details.read_state {
+            self.participant(&mut read_state.participant_id);
+        }
+        for id in &mut details.current_participant {
+            self.participant(id);
+        }
+        for data in &mut details.participant_data {
+            if data.fallback_name.is_some() {
+                data.fallback_name = Some(self.name(&data.id.gaia_id));
+            }
+            self.participant(&mut data.id);
+            data.domain_id = None;
+            data.phone_number = None;
+        }
+
+        for event in &mut conversation.events {
+            let mut rng = Rng(hash(self.options.seed, b"event", &event.header.event_id));
+            let header = &mut event.header;
+            self.id(&mut header.conversation_id.id);
+            self.participant(&mut header.sender_id);
+            self.participant(&mut header.self_event_state.user_id);
+
+            match &mut event.data {
+                EventData::ChatMessage {
+                    message_content,
+                    annotation,
+                } => {
+                    for segment in &mut message_content.segments {
+                        match segment {
+                            ChatSegment::Text { text, .. } => *text = self.text(text, &mut rng),
+                            ChatSegment::Link {
+                                text, link_data, ..
+                            } => {
+                                *text = self.text(text, &mut rng);
+                                link_data.link_target = REDACTED_URL.to_owned();
+                                if link_data.display_url.is_some() {
+                                    link_data.display_url = Some(REDACTED_URL.to_owned());
+                                }
+                            }
+                            ChatSegment::LineBreak { .. } => {}
+                        }
+                    }
+                    for attachment in &mut message_content.attachments {
+                        let item = &mut attachment.embed_item;
+                        self.optional_id(&mut item.id);
+                        if let Some(photo) = &mut item.plus_photo {
+                            self.photo(photo);
+                        }
+                        if let Some(place) = &mut item.place_v2 {
+                            self.place(place);
+                        }
+                        if let Some(thing) = &mut item.thing_v2 {
+                            thing.url = REDACTED_URL.to_owned();
+                            if let Some(name) = &mut thing.name {
+                                *name = self.hash_text(name);
+                            }
+                            self.image(&mut thing.representative_image);
+                        }
+                    }
+                    for annotation in annotation.iter_mut().flatten() {
+                        annotation.value = self.text(&annotation.value, &mut rng);
+                    }
+                }
+                EventData::HangoutEvent { participant_id, .. }
+                | EventData::MembershipChange { participant_id, .. } => {
+                    for id in participant_id {
+                        self.participant(id);
+                    }
+                }
+                EventData::ConversationRename { new_name, old_name } => {
+                    *new_name = self.hash_text(new_name);
+                    *old_name = self.hash_text(old_name);
+                }
+            }
+        }
+    }
+
+    fn participant(&mut self, id: &mut ParticipantId) {
+        self.id(&mut id.gaia_id);
+        self.id(&mut id.chat_id);
+    }
+
+    /// Replace `id` with its pseudonym.
+    fn id(&mut self, id: &mut String) {
+        if let Some(pseudonym) = self.ids.get(id.as_str()) {
+            *id = pseudonym.clone();
+            return;
+        }
+
+        let mut rng = Rng(hash(self.options.seed, b"id", id));
+        let pseudonym = loop {
+            let candidate = rewrite(id, &mut rng);
+            // Only IDs without letters or digits can run out of pseudonyms.
+            if self.taken.insert(candidate.clone()) || candidate == *id {
+                break candidate;
+            }
+        };
+        self.ids.insert(id.clone(), pseudonym.clone());
+        *id = pseudonym;
+    }
+
+    #[inline]
+    fn optional_id(&mut self, id: &mut Option<String>) {
+        if let Some(id) = id {
+            self.id(id);
+        }
+    }
+
+    fn name(&mut self, gaia_id: &str) -> String {
+        let next = self.names.len() + 1;
+        self.names
+            .entry(gaia_id.to_owned())
+            .or_insert_with(|| format!("Person {}", next))
+            .clone()
+    }
+
+    fn photo(&mut self, photo: &mut PlusPhoto) {
+        self.id(&mut photo.album_id);
+        self.id(&mut photo.photo_id);
+        self.id(&mut photo.owner_obfuscated_id);
+        for id in &mut photo.stream_id {
+            self.id(id);
+        }
+        photo.original_content_url = REDACTED_URL.to_owned();
+        photo.url = REDACTED_URL.to_owned();
+        photo.thumbnail.image_url = REDACTED_URL.to_owned();
+        if photo.thumbnail.url.is_some() {
+            photo.thumbnail.url = Some(REDACTED_URL.to_owned());
+        }
+        if photo.download_url.is_some() {
+            photo.download_url = Some(REDACTED_URL.to_owned());
+        }
+    }
+
+    fn place(&mut self, place: &mut PlaceV2) {
+        place.url = REDACTED_URL.to_owned();
+        if let Some(name) = &mut place.name {
+            *name = self.hash_text(name);
+        }
+        let address = &mut place.address.postal_address_v2;
+        address.name = None;
+        address.street_address = None;
+        address.address_locality = None;
+        address.address_region = None;
+        address.address_country = None;
+        address.postal_code = None;
+        place.geo.geo_coordinates_v2.latitude = 0.0;
+        place.geo.geo_coordinates_v2.longitude = 0.0;
+        self.optional_id(&mut place.place_id);
+        self.optional_id(&mut place.cluster_id);
+        self.optional_id(&mut place.reference_id);
+        self.image(&mut place.representative_image);
+    }
+
+    fn image(&mut self, image: &mut RepresentativeImage) {
+        self.id(&mut image.id);
+        image.image_object_v2.url = REDACTED_URL.to_owned();
+    }
+
+    /// Rewrite message text according to the text mode.
+    fn text(&self, text: &str, rng: &mut Rng) -> String {
+        match self.options.text {
+            TextMode::Scramble => rewrite(text, rng),
+            TextMode::Hash => self.hash_text(text),
+        }
+    }
+
+    /// Rewrite text word by word, so that equal text is always rewritten the same way.
+    ///
+    /// Used for names regardless of the text mode, so that renames stay consistent with the
+    /// conversation name.
+    fn hash_text(&self, text: &str) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(char::is_alphanumeric) {
+            out.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest
+                .find(|c: char| !c.is_alphanumeric())
+                .unwrap_or(rest.len());
+            let word = &rest[..end];
+            let mut rng = Rng(hash(self.options.seed, b"word", &word.to_lowercase()));
+            out.push_str(&rewrite(word, &mut rng));
+            rest = &rest[end..];
+        }
+        out.push_str(rest);
+        out
+    }
+}
+
+/// Replace the letters and digits of `s` with random ones of the same class.
+fn rewrite(s: &str, rng: &mut Rng) -> String {
+    s.chars()
+        .map(|c| {
+            if c.is_ascii_digit() || (c.is_numeric() && !c.is_ascii()) {
+                rng.pick(b'0', 10)
+            } else if c.is_uppercase() {
+                rng.pick(b'A', 26)
+            } else if c.is_alphabetic() {
+                rng.pick(b'a', 26)
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+/// Seeded 64-bit hash: FNV-1a over the domain and input, finished with the SplitMix64 mixer.
+///
+/// Unlike the standard library hashers, its output is stable across Rust versions. It is
+/// not a keyed PRF and only serves to derive pseudonyms reproducibly.
+fn hash(seed: u64, domain: &[u8], s: &str) -> u64 {
+    let mut h = 0xcbf2_9ce4_8422_2325 ^ mix(seed);
+    for &b in domain.iter().chain(&[0]).chain(s.as_bytes()) {
+        h ^= u64::from(b);
+        h = h.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    mix(h)
+}
@@ -3,8 +3,8 @@ use crate::{
     ConversationStatus, EmbedItem, Event, EventData, Formatting, Geo, HangoutEvent,
     HangoutEventType, Hangouts, InvitationAffinity, InvitationData, InvitationStatus,
     LinkSharingStatus, MediaType, MembershipChange, MembershipChangeType, NotificationLevel,
-    Participant, ParticipantId, ParticipantType, Photo, PlaceV2, ReadState, RepresentativeImage,
-    SelfEventState, SelfState, ThingV2, Thumbnail, View,
+    Participant, ParticipantId, ParticipantType, PhoneNumber, Photo, PlaceV2, ReadState,
+    RepresentativeImage, SelfEventState, SelfState, ThingV2, Thumbnail, View,
 };
 
 use std::collections::HashMap;
@@ -12,7 +12,7 @@ use std::convert::{TryFrom, TryInto};
 use std::fmt;
 use std::num::ParseIntError;
 
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, Utc};
 
 #[derive(Debug, Clone, thiserror::Error)]
 pub enum ConversionError {
@@ -113,6 +113,7 @@ impl TryFrom<raw::Conversation> for Conversation {
                     pd.id.clone().into(),
                     Participant {
                         typ: pd.participant_type.map(From::from),
+                        phone_number: pd.phone_number.map(From::from),
                         fallback_name: pd.fallback_name,
                         invitation_status: pd.invitation_status.map(From::from),
                         new_invitation_status: pd.new_invitation_status.map(From::from),
@@ -221,6 +222,19 @@ impl From<raw::ParticipantId> for ParticipantId {
     }
 }
 
+impl From<raw::PhoneNumber> for PhoneNumber {
+    #[inline]
+    fn from(val: raw::PhoneNumber) -> Self {
+        let i18n = val.i18n_data;
+        Self {
+            e164: val.e164,
+            international: i18n.as_ref().and_then(|d| d.international_number.clone()),
+            national: i18n.as_ref().and_then(|d| d.national_number.clone()),
+            region_code: i18n.and_then(|d| d.region_code),
+        }
+    }
+}
+
 impl From<raw::ParticipantType> for ParticipantType {
     #[inline]
     fn from(val: raw::ParticipantType) -> Self {
@@ -492,6 +506,5 @@ fn from_timestamp(millisecs: i64) -> DateTime<Utc> {
     const MILLI: i64 = 10i64.pow(6);
     let secs = millisecs / MILLI;
     let milli = millisecs % MILLI;
-    let naive = NaiveDateTime::from_timestamp(secs, (milli * 1000) as u32);
-    DateTime::from_utc(naive, Utc)
+    DateTime::from_timestamp(secs, (milli * 1000) as u32).unwrap()
 }
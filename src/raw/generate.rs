@@ -0,0 +1,818 @@
+//! Deterministic generator of synthetic Hangouts data.
+//!
+//! [`generate`] builds a realistic raw [`Hangouts`] from a seed, for tests and benchmarks
+//! that cannot ship real exports. Conversations are one-to-one chats (some of them off the
+//! record), named and unnamed groups, and SMS threads with off-network phone contacts. Their
+//! events mix chat messages with formatting, line breaks, links and emoji, photo, place and
+//! thing attachments, audio and video calls, membership changes and renames.
+//!
+//! The same options always produce the same data, which converts to [`crate::Hangouts`] and,
+//! with the `serde-impl` feature, serializes to valid Hangouts.json. All names, numbers,
+//! places and URLs are made up.
+
+use crate::raw::{
+    Address, AddressType, AttachmentSegment, ChatSegment, ChatSegments, Conversation,
+    ConversationDetails, ConversationHeader, ConversationId, ConversationStatus, ConversationType,
+    EmbedItem, EmbedItemType, Event, EventData, EventHeader, EventOtr, EventType, Formatting, Geo,
+    GeoCoordinatesV2, GeoType, HangoutEvent, Hangouts, ImageObjectV2, InvitationAffinity,
+    InvitationStatus, LinkData, LinkSharingStatus, MediaType, MembershipChangeType,
+    NotificationLevel, ParticipantData, ParticipantId, ParticipantType, PhoneNumber,
+    PhoneNumberI18nData, PlaceV2, PlusPhoto, PostalAddressV2, ReadState, RepresentativeImage,
+    RepresentativeImageType, SelfConversationState, SelfEventState, ThingV2, Thumbnail, View,
+};
+use crate::util::Rng;
+
+use chrono::{DateTime, TimeZone, Utc};
+
+const FIRST_NAMES: &[&str] = &[
+    "Alice", "Bruno", "Chloé", "Dmitri", "Emma", "Farid", "Greta", "Hiroshi", "Ines", "Jonas",
+    "Kavya", "Liam", "Maya", "Nikolai", "Olivia", "Pedro", "Quinn", "Rosa", "Sven", "Tariq", "Uma",
+    "Victor", "Wen", "Yara", "Zoë",
+];
+
+const LAST_NAMES: &[&str] = &[
+    "Andersen", "Becker", "Costa", "Dubois", "Eriksson", "Fischer", "García", "Hoffmann",
+    "Ivanova", "Jensen", "Kowalski", "Lee", "Moreau", "Nakamura", "Okafor", "Patel",
+];
+
+const GROUP_NAMES: &[&str] = &[
+    "Weekend plans",
+    "Book club",
+    "Climbing crew",
+    "Family 🏡",
+    "Project Falcon",
+    "Roommates",
+    "Trip to Lisbon",
+    "Board games night",
+    "Marathon training",
+    "Band practice 🎸",
+];
+
+const WORDS: &[&str] = &[
+    "the", "a", "to", "and", "I", "you", "it", "is", "that", "we", "on", "for", "at", "this",
+    "what", "are", "just", "so", "be", "do", "have", "was", "not", "can", "will", "know", "think",
+    "going", "tomorrow", "tonight", "later", "today", "yeah", "okay", "sure", "maybe", "great",
+    "good", "really", "time", "dinner", "lunch", "coffee", "movie", "train", "meeting", "weekend",
+    "home", "work", "beach", "pizza", "idea", "photos", "sounds", "fun", "see", "call", "when",
+    "where", "haha", "thanks", "sorry", "now", "soon", "minutes", "late", "ready", "leaving",
+    "there", "here", "new", "party", "game", "music", "book", "rain",
+];
+
+const EMOJI: &[&str] = &["😀", "😂", "👍", "❤️", "🎉", "😅", "🙏", "☕", "🍕", "😴"];
+
+const DOMAINS: &[&str] = &[
+    "example.com",
+    "news.example.org",
+    "blog.example.net",
+    "maps.example.com",
+    "video.example.org",
+];
+
+/// A made-up place.
+struct Place {
+    name: &'static str,
+    street: &'static str,
+    locality: &'static str,
+    region: &'static str,
+    country: &'static str,
+    postal_code: &'static str,
+    latitude: f64,
+    longitude: f64,
+}
+
+const PLACES: &[Place] = &[
+    Place {
+        name: "Blue Door Café",
+        street: "12 Harbor Lane",
+        locality: "Portsmouth",
+        region: "NH",
+        country: "US",
+        postal_code: "03801",
+        latitude: 43.0766,
+        longitude: -70.7579,
+    },
+    Place {
+        name: "Lindenpark",
+        street: "Parkstraße 8",
+        locality: "Leipzig",
+        region: "Sachsen",
+        country: "DE",
+        postal_code: "04109",
+        latitude: 51.3397,
+        longitude: 12.3731,
+    },
+    Place {
+        name: "Miradouro da Graça",
+        street: "Largo da Graça 1",
+        locality: "Lisboa",
+        region: "Lisboa",
+        country: "PT",
+        postal_code: "1170-165",
+        latitude: 38.7163,
+        longitude: -9.1315,
+    },
+];
+
+const THINGS: &[&str] = &[
+    "Ten minute pasta recipe",
+    "Live session — The Example Band",
+    "How to fix a bike chain",
+];
+
+/// Microseconds per second.
+const SECOND: i64 = 1_000_000;
+
+/// Options of [`generate`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratorOptions {
+    /// Seed of the random choices.
+    pub seed: u64,
+    /// Number of conversations.
+    pub conversations: usize,
+    /// Number of people conversations are drawn from, including the account owner. At least
+    /// 2 are generated.
+    pub participants: usize,
+    /// Number of events of every conversation.
+    pub events: usize,
+    /// Earliest time of the first event of a conversation.
+    pub start: DateTime<Utc>,
+}
+
+impl Default for GeneratorOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            conversations: 10,
+            participants: 12,
+            events: 200,
+            start: Utc.with_ymd_and_hms(2015, 1, 1, 0, 0, 0).unwrap(),
+        }
+    }
+}
+
+/// Generate synthetic Hangouts data.
+///
+/// The first participant is the account owner, and takes part in every conversation. Every
+/// fourth other participant is an off-network phone contact. One conversation in three is a
+/// group and one in six an SMS thread, given enough participants; one one-to-one conversation
+/// in four is off the record.
+pub fn generate(options: &GeneratorOptions) -> Hangouts {
+    let mut generator = Generator {
+        rng: Rng(options.seed),
+        people: Vec::new(),
+        events: options.events,
+        start: options.start.timestamp() * SECOND,
+    };
+    for i in 0..options.participants.max(2) {
+        let person = generator.person(i);
+        generator.people.push(person);
+    }
+    let conversations = (0..options.conversations)
+        .map(|i| generator.conversation(i))
+        .collect();
+    Hangouts { conversations }
+}
+
+struct Person {
+    id: ParticipantId,
+    name: String,
+    phone: Option<PhoneNumber>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    OneToOne { off_the_record: bool },
+    Group,
+    Sms,
+}
+
+/// State of the conversation being generated.
+struct State {
+    id: ConversationId,
+    kind: Kind,
+    /// Current members, as indices of people.
+    members: Vec<usize>,
+    /// Everyone who was ever a member, in order of joining.
+    everyone: Vec<usize>,
+    name: Option<String>,
+    /// Time of the last event, in microseconds.
+    time: i64,
+    events: Vec<Event>,
+}
+
+struct Generator {
+    rng: Rng,
+    people: Vec<Person>,
+    events: usize,
+    start: i64,
+}
+
+impl Generator {
+    #[inline]
+    fn below(&mut self, n: usize) -> usize {
+        (self.rng.next() % n.max(1) as u64) as usize
+    }
+
+    /// Whether an event with the given chance in percent happens.
+    #[inline]
+    fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    #[inline]
+    fn choose<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    fn digits(&mut self, len: usize) -> String {
+        (0..len).map(|_| self.rng.pick(b'0', 10)).collect()
+    }
+
+    fn alphanumeric(&mut self, len: usize) -> String {
+        (0..len)
+            .map(|_| match self.below(62) {
+                n @ 0..=9 => (b'0' + n as u8) as char,
+                n @ 10..=35 => (b'a' + (n - 10) as u8) as char,
+                n => (b'A' + (n - 36) as u8) as char,
+            })
+            .collect()
+    }
+
+    fn person(&mut self, i: usize) -> Person {
+        // The index keeps IDs unique.
+        let gaia_id = format!("1{:04}{}", i, self.digits(16));
+        let id = ParticipantId {
+            chat_id: gaia_id.clone(),
+            gaia_id,
+        };
+
+        if i % 4 == 3 {
+            let number = format!("555{}", self.digits(7));
+            let international = format!("+1 {}-{}-{}", &number[..3], &number[3..6], &number[6..]);
+            let phone = PhoneNumber {
+                e164: Some(format!("+1{}", number)),
+                i18n_data: Some(PhoneNumberI18nData {
+                    national_number: Some(format!(
+                        "({}) {}-{}",
+                        &number[..3],
+                        &number[3..6],
+                        &number[6..]
+                    )),
+                    international_number: Some(international.clone()),
+                    country_code: Some(1),
+                    region_code: Some("US".to_owned()),
+                    is_valid: Some(true),
+                    validation_result: Some("IS_POSSIBLE".to_owned()),
+                }),
+            };
+            return Person {
+                id,
+                name: international,
+                phone: Some(phone),
+            };
+        }
+
+        let first = FIRST_NAMES[i % FIRST_NAMES.len()];
+        let last = *self.choose(LAST_NAMES);
+        Person {
+            id,
+            name: format!("{} {}", first, last),
+            phone: None,
+        }
+    }
+
+    fn conversation(&mut self, i: usize) -> Conversation {
+        let contacts: Vec<usize> = (1..self.people.len())
+            .filter(|&p| self.people[p].phone.is_none())
+            .collect();
+        let phones: Vec<usize> = (1..self.people.len())
+            .filter(|&p| self.people[p].phone.is_some())
+            .collect();
+
+        let kind = if i % 6 == 5 && !phones.is_empty() {
+            Kind::Sms
+        } else if i.is_multiple_of(3) && contacts.len() >= 2 {
+            Kind::Group
+        } else {
+            Kind::OneToOne {
+                off_the_record: i % 4 == 1,
+            }
+        };
+        let members = match kind {
+            Kind::Sms => vec![0, *self.choose(&phones)],
+            Kind::OneToOne { .. } if contacts.is_empty() => vec![0, *self.choose(&phones)],
+            Kind::OneToOne { .. } => vec![0, *self.choose(&contacts)],
+            Kind::Group => {
+                let mut members = vec![0];
+                let size = 2 + self.below(contacts.len().min(5) - 1);
+                while members.len() <= size {
+                    let p = *self.choose(&contacts);
+                    if !members.contains(&p) {
+                        members.push(p);
+                    }
+                }
+                members
+            }
+        };
+        let name = match kind {
+            Kind::Group if self.chance(70) => Some(self.choose(GROUP_NAMES).to_string()),
+            _ => None,
+        };
+
+        let mut state = State {
+            id: ConversationId {
+                id: format!("Ug{}4AaABAQ", self.alphanumeric(20)),
+            },
+            kind,
+            everyone: members.clone(),
+            members,
+            name,
+            time: self.start + self.below(365 * 24 * 3600) as i64 * SECOND,
+            events: Vec::with_capacity(self.events),
+        };
+        while state.events.len() < self.events {
+            self.event(&mut state);
+        }
+        state.events.truncate(self.events);
+
+        self.finish(state)
+    }
+
+    fn finish(&mut self, state: State) -> Conversation {
+        let owner = self.people[0].id.clone();
+        let first = state.events.first().map_or(state.time, |event| {
+            event.header.timestamp.parse().expect("generated timestamp")
+        });
+        let (group, sms) = match state.kind {
+            Kind::Group => (true, false),
+            Kind::Sms => (false, true),
+            Kind::OneToOne { .. } => (false, false),
+        };
+        let off_the_record = state.kind
+            == Kind::OneToOne {
+                off_the_record: true,
+            };
+
+        let last = state.time;
+        let read_state = |id: &ParticipantId| ReadState {
+            participant_id: id.clone(),
+            latest_read_timestamp: last.to_string(),
+        };
+        let participant_data = state
+            .everyone
+            .iter()
+            .map(|&p| {
+                let person = &self.people[p];
+                ParticipantData {
+                    id: person.id.clone(),
+                    fallback_name: Some(person.name.clone()),
+                    invitation_status: Some(InvitationStatus::Accepted),
+                    participant_type: Some(if person.phone.is_some() {
+                        ParticipantType::OffNetworkPhone
+                    } else {
+                        ParticipantType::Gaia
+                    }),
+                    new_invitation_status: Some(InvitationStatus::Accepted),
+                    in_different_customer_as_requester: Some(false),
+                    domain_id: None,
+                    phone_number: person.phone.clone(),
+                }
+            })
+            .collect();
+
+        let details = ConversationDetails {
+            id: state.id.clone(),
+            typ: if group {
+                ConversationType::Group
+            } else {
+                ConversationType::OneToOne
+            },
+            name: state.name,
+            self_conversation_state: SelfConversationState {
+                self_read_state: read_state(&owner),
+                status: ConversationStatus::Active,
+                notification_level: NotificationLevel::Ring,
+                view: vec![if self.chance(10) {
+                    View::Archived
+                } else {
+                    View::Inbox
+                }],
+                inviter_id: self.people[state.everyone[0]].id.clone(),
+                invite_timestamp: first.to_string(),
+                invitation_display_type: None,
+                invite_affinity: Some(InvitationAffinity::High),
+                sort_timestamp: state.time.to_string(),
+                active_timestamp: Some(state.time.to_string()),
+                is_guest: None,
+            },
+            read_state: state
+                .everyone
+                .iter()
+                .map(|&p| read_state(&self.people[p].id))
+                .collect(),
+            has_active_hangout: false,
+            otr_status: if off_the_record {
+                "OFF_THE_RECORD"
+            } else {
+                "ON_THE_RECORD"
+            }
+            .to_owned(),
+            otr_toggle: if group || sms { "DISABLED" } else { "ENABLED" }.to_owned(),
+            current_participant: state
+                .members
+                .iter()
+                .map(|&p| self.people[p].id.clone())
+                .collect(),
+            participant_data,
+            fork_on_external_invite: false,
+            network_type: vec![if sms { "PHONE" } else { "BABEL" }.to_owned()],
+            force_history_state: "NO_FORCE".to_owned(),
+            group_link_sharing_status: LinkSharingStatus::Off,
+        };
+
+        Conversation {
+            header: ConversationHeader {
+                conversation_id: state.id,
+                details,
+            },
+            events: state.events,
+        }
+    }
+
+    /// Add one event to the conversation, or two for a call.
+    fn event(&mut self, state: &mut State) {
+        let gap = match self.below(100) {
+            0..=69 => 5 + self.below(300),
+            70..=94 => 300 + self.below(3 * 3600),
+            _ => 3 * 3600 + self.below(5 * 24 * 3600),
+        };
+        state.time += gap as i64 * SECOND + self.below(1_000_000) as i64;
+        let sender = *self.choose(&state.members);
+
+        let roll = self.below(100);
+        let (typ, data) = match state.kind {
+            Kind::Sms => (EventType::Sms, self.message(false)),
+            Kind::Group if roll < 3 => return self.membership(state),
+            Kind::Group if roll < 5 => {
+                let new_name = self.choose(GROUP_NAMES).to_string();
+                let old_name = state.name.replace(new_name.clone()).unwrap_or_default();
+                (
+                    EventType::RenameConversation,
+                    EventData::ConversationRename { new_name, old_name },
+                )
+            }
+            _ if roll < 10 => return self.call(state, sender),
+            _ => (EventType::RegularChatMessage, self.message(true)),
+        };
+        self.push(state, sender, typ, data);
+    }
+
+    fn push(&mut self, state: &mut State, sender: usize, typ: EventType, data: EventData) {
+        let own = sender == 0;
+        let client_generated_id = if own { Some(self.digits(19)) } else { None };
+        let event_otr = if state.kind
+            == (Kind::OneToOne {
+                off_the_record: true,
+            }) {
+            EventOtr::OffTheRecord
+        } else {
+            EventOtr::OnTheRecord
+        };
+        state.events.push(Event {
+            header: EventHeader {
+                conversation_id: state.id.clone(),
+                sender_id: self.people[sender].id.clone(),
+                timestamp: state.time.to_string(),
+                self_event_state: SelfEventState {
+                    user_id: self.people[0].id.clone(),
+                    client_generated_id,
+                    notification_level: if own {
+                        None
+                    } else {
+                        Some(NotificationLevel::Ring)
+                    },
+                },
+                event_id: format!("{}{}", self.alphanumeric(11), self.alphanumeric(11)),
+                advances_sort_timestamp: true,
+                event_otr,
+                event_version: state.time.to_string(),
+            },
+            data,
+            event_type: typ,
+        });
+    }
+
+    fn call(&mut self, state: &mut State, sender: usize) {
+        let media_type = if self.chance(30) {
+            MediaType::AudioOnly
+        } else {
+            MediaType::AudioVideo
+        };
+        self.push(
+            state,
+            sender,
+            EventType::HangoutEvent,
+            EventData::HangoutEvent {
+                data: HangoutEvent::StartHangout,
+                media_type: Some(media_type.clone()),
+                participant_id: Vec::new(),
+            },
+        );
+
+        let duration = 10 + self.below(2 * 3600);
+        state.time += duration as i64 * SECOND;
+        let mut participant_id = Vec::new();
+        for &p in &state.members {
+            if p == sender || !self.rng.next().is_multiple_of(3) {
+                participant_id.push(self.people[p].id.clone());
+            }
+        }
+        self.push(
+            state,
+            sender,
+            EventType::HangoutEvent,
+            EventData::HangoutEvent {
+                data: HangoutEvent::EndHangout {
+                    hangout_duration_secs: duration.to_string(),
+                },
+                media_type: Some(media_type),
+                participant_id,
+            },
+        );
+    }
+
+    fn membership(&mut self, state: &mut State) {
+        let outsiders: Vec<usize> = (1..self.people.len())
+            .filter(|p| self.people[*p].phone.is_none() && !state.members.contains(p))
+            .collect();
+
+        if state.members.len() > 3 && (outsiders.is_empty() || self.chance(50)) {
+            // Someone leaves, or is removed by another member.
+            let leaving = state.members[1 + self.below(state.members.len() - 1)];
+            let sender = if self.chance(60) {
+                leaving
+            } else {
+                *self.choose(&state.members)
+            };
+            state.members.retain(|&p| p != leaving);
+            let data = EventData::MembershipChange {
+                typ: MembershipChangeType::Leave,
+                participant_id: vec![self.people[leaving].id.clone()],
+            };
+            self.push(state, sender, EventType::RemoveUser, data);
+        } else if !outsiders.is_empty() {
+            let sender = *self.choose(&state.members);
+            let joining = *self.choose(&outsiders);
+            state.members.push(joining);
+            if !state.everyone.contains(&joining) {
+                state.everyone.push(joining);
+            }
+            let data = EventData::MembershipChange {
+                typ: MembershipChangeType::Join,
+                participant_id: vec![self.people[joining].id.clone()],
+            };
+            self.push(state, sender, EventType::AddUser, data);
+        } else {
+            let sender = *self.choose(&state.members);
+            let data = self.message(true);
+            self.push(state, sender, EventType::RegularChatMessage, data);
+        }
+    }
+
+    /// Generate a chat message, with attachments if `rich`.
+    fn message(&mut self, rich: bool) -> EventData {
+        let mut segments = Vec::new();
+        let mut attachments = Vec::new();
+
+        match self.below(100) {
+            0..=3 if rich => attachments.push(self.photo()),
+            4 if rich => attachments.push(self.place()),
+            5 if rich => attachments.push(self.thing()),
+            _ => {
+                let words = 1 + self.below(14);
+                let mut text = String::new();
+                let mut format = Formatting::default();
+                for i in 0..words {
+                    if i > 0 {
+                        text.push(' ');
+                    }
+                    // Formatting changes, or a line break, start a new segment.
+                    if i > 0 && self.chance(4) {
+                        let next = self.formatting();
+                        if next != format {
+                            segments.push(ChatSegment::Text {
+                                text: std::mem::take(&mut text),
+                                formatting: std::mem::replace(&mut format, next),
+                            });
+                        }
+                    } else if i > 0 && self.chance(2) {
+                        segments.push(ChatSegment::Text {
+                            text: std::mem::take(&mut text).trim_end().to_owned(),
+                            formatting: format.clone(),
+                        });
+                        segments.push(ChatSegment::LineBreak {
+                            text: Some("\n".to_owned()),
+                            formatting: Formatting::default(),
+                        });
+                    }
+                    let word = if self.chance(6) {
+                        self.choose(EMOJI)
+                    } else {
+                        self.choose(WORDS)
+                    };
+                    text.push_str(word);
+                }
+                segments.push(ChatSegment::Text {
+                    text,
+                    formatting: format,
+                });
+
+                if self.chance(8) {
+                    segments.push(ChatSegment::Text {
+                        text: " ".to_owned(),
+                        formatting: Formatting::default(),
+                    });
+                    segments.push(self.link());
+                }
+            }
+        }
+
+        EventData::ChatMessage {
+            message_content: ChatSegments {
+                segments,
+                attachments,
+            },
+            annotation: None,
+        }
+    }
+
+    fn formatting(&mut self) -> Formatting {
+        let bits = self.below(16);
+        Formatting {
+            bold: bits & 1 != 0,
+            italics: bits & 2 != 0,
+            strikethrough: bits & 4 != 0,
+            underline: bits & 8 != 0,
+        }
+    }
+
+    fn link(&mut self) -> ChatSegment {
+        let domain = *self.choose(DOMAINS);
+        let path = format!("{}/{}", self.choose(WORDS), self.alphanumeric(6));
+        let url = format!("https://{}/{}", domain, path);
+        ChatSegment::Link {
+            link_data: LinkData {
+                // Hangouts wraps links in Google redirects.
+                link_target: format!(
+                    "https://www.google.com/url?q=https%3A%2F%2F{}%2F{}&sa=D&source=hangouts",
+                    domain,
+                    path.replace('/', "%2F")
+                ),
+                display_url: None,
+            },
+            text: url,
+            formatting: Formatting::default(),
+        }
+    }
+
+    fn photo(&mut self) -> AttachmentSegment {
+        let photo_id = self.digits(19);
+        let (media_type, extension) = match self.below(10) {
+            0 => (MediaType::AnimatedPhoto, "gif"),
+            1 => (MediaType::Video, "mp4"),
+            _ => (MediaType::Photo, "jpg"),
+        };
+        let file = format!("IMG_{}.{}", self.digits(4), extension);
+        let base = format!(
+            "https://lh3.googleusercontent.com/-{}/{}",
+            self.alphanumeric(11),
+            self.alphanumeric(11)
+        );
+        let (width, height) = *self.choose(&[(4032, 3024), (3024, 4032), (1920, 1080)]);
+
+        AttachmentSegment {
+            embed_item: EmbedItem {
+                id: Some(format!("{}/{}", self.digits(21), photo_id)),
+                plus_photo: Some(PlusPhoto {
+                    album_id: self.digits(19),
+                    media_type,
+                    original_content_url: format!("{}/s0/{}", base, file),
+                    owner_obfuscated_id: self.digits(21),
+                    photo_id: photo_id.clone(),
+                    stream_id: vec!["photos_from_hangouts".to_owned()],
+                    thumbnail: Thumbnail {
+                        height_px: height / 8,
+                        width_px: width / 8,
+                        image_url: format!("{}/w{}-h{}/{}", base, width / 8, height / 8, file),
+                        url: Some(format!(
+                            "https://plus.google.com/photos/albums/p{}?pid={}",
+                            self.alphanumeric(20),
+                            photo_id
+                        )),
+                    },
+                    url: format!(
+                        "https://plus.google.com/photos/albums/p{}?pid={}",
+                        self.alphanumeric(20),
+                        photo_id
+                    ),
+                    download_url: None,
+                }),
+                place_v2: None,
+                thing_v2: None,
+                types: vec![EmbedItemType::PlusPhoto],
+            },
+            id: self.alphanumeric(24),
+        }
+    }
+
+    fn image(&mut self, typ: RepresentativeImageType) -> RepresentativeImage {
+        RepresentativeImage {
+            types: vec![typ],
+            id: self.alphanumeric(16),
+            image_object_v2: ImageObjectV2 {
+                url: format!(
+                    "https://lh3.googleusercontent.com/{}=w200-h200",
+                    self.alphanumeric(24)
+                ),
+                width: Some("200".to_owned()),
+                height: Some("200".to_owned()),
+            },
+        }
+    }
+
+    fn place(&mut self) -> AttachmentSegment {
+        let &Place {
+            name,
+            street,
+            locality,
+            region,
+            country,
+            postal_code,
+            latitude,
+            longitude,
+        } = self.choose(PLACES);
+        let place = PlaceV2 {
+            url: format!("https://maps.google.com/maps?q={},{}", latitude, longitude),
+            name: Some(name.to_owned()),
+            address: Address {
+                types: vec![AddressType::PostalAddressV2],
+                postal_address_v2: PostalAddressV2 {
+                    name: Some(name.to_owned()),
+                    street_address: Some(street.to_owned()),
+                    address_locality: Some(locality.to_owned()),
+                    address_region: Some(region.to_owned()),
+                    address_country: Some(country.to_owned()),
+                    postal_code: Some(postal_code.to_owned()),
+                },
+            },
+            geo: Geo {
+                types: vec![GeoType::GeoCoordinatesV2],
+                geo_coordinates_v2: GeoCoordinatesV2 {
+                    latitude,
+                    longitude,
+                },
+            },
+            representative_image: self.image(RepresentativeImageType::ImageObjectV2),
+            place_id: Some(format!("ChIJ{}", self.alphanumeric(23))),
+            cluster_id: Some(format!("0x{}", self.digits(16))),
+            reference_id: None,
+        };
+
+        AttachmentSegment {
+            embed_item: EmbedItem {
+                id: None,
+                plus_photo: None,
+                place_v2: Some(place),
+                thing_v2: None,
+                types: vec![
+                    EmbedItemType::PlaceV2,
+                    EmbedItemType::ThingV2,
+                    EmbedItemType::Thing,
+                ],
+            },
+            id: self.alphanumeric(24),
+        }
+    }
+
+    fn thing(&mut self) -> AttachmentSegment {
+        let thing = ThingV2 {
+            url: format!(
+                "https://video.example.org/watch?v={}",
+                self.alphanumeric(11)
+            ),
+            name: Some(self.choose(THINGS).to_string()),
+            representative_image: self.image(RepresentativeImageType::ThingV2),
+        };
+        AttachmentSegment {
+            embed_item: EmbedItem {
+                id: None,
+                plus_photo: None,
+                place_v2: None,
+                thing_v2: Some(thing),
+                types: vec![EmbedItemType::ThingV2, EmbedItemType::Thing],
+            },
+            id: self.alphanumeric(24),
+        }
+    }
+}
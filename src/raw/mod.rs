@@ -1,4 +1,8 @@
+pub mod anonymize;
 pub mod convert;
+pub mod generate;
+#[cfg(feature = "json")]
+pub mod stream;
 
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(
@@ -184,8 +188,25 @@ pub struct ParticipantData {
     pub new_invitation_status: Option<InvitationStatus>,
     pub in_different_customer_as_requester: Option<bool>,
     pub domain_id: Option<String>,
-    // TODO:
-    // pub phone_number: Option<serde_json::Value>,
+    pub phone_number: Option<PhoneNumber>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct PhoneNumber {
+    pub e164: Option<String>,
+    pub i18n_data: Option<PhoneNumberI18nData>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct PhoneNumberI18nData {
+    pub national_number: Option<String>,
+    pub international_number: Option<String>,
+    pub country_code: Option<u64>,
+    pub region_code: Option<String>,
+    pub is_valid: Option<bool>,
+    pub validation_result: Option<String>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -261,6 +282,8 @@ pub struct EventHeader {
 pub enum EventOtr {
     #[cfg_attr(feature = "serde-impl", serde(rename = "ON_THE_RECORD"))]
     OnTheRecord,
+    #[cfg_attr(feature = "serde-impl", serde(rename = "OFF_THE_RECORD"))]
+    OffTheRecord,
 }
 
 #[derive(Debug, Clone, PartialEq)]
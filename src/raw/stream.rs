@@ -0,0 +1,144 @@
+//! Incremental parsing of Hangouts.json.
+//!
+//! [`for_each_conversation`] reads the top-level `conversations` array one element at a time
+//! and hands each [`Conversation`] to a callback, so only a single conversation is held in
+//! memory at once.
+
+use crate::raw::Conversation;
+
+use std::fmt;
+use std::io::Read;
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+
+/// Error produced while streaming conversations.
+#[derive(Debug, thiserror::Error)]
+pub enum StreamError<E> {
+    /// The input is not valid Hangouts.json.
+    Json(#[from] serde_json::Error),
+    /// The callback returned an error and parsing was stopped.
+    Callback(E),
+}
+
+impl<E: fmt::Display> fmt::Display for StreamError<E> {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        match self {
+            Self::Json(err) => write!(f, "json: {}", err),
+            Self::Callback(err) => write!(f, "callback: {}", err),
+        }
+    }
+}
+
+/// Parse Hangouts.json from `reader`, calling `f` with each conversation as soon as it has
+/// been read.
+///
+/// Parsing stops at the first error returned by `f`.
+pub fn for_each_conversation<R, F, E>(reader: R, f: F) -> Result<(), StreamError<E>>
+where
+    R: Read,
+    F: FnMut(Conversation) -> Result<(), E>,
+{
+    let mut state = State { f, error: None };
+
+    let mut de = serde_json::Deserializer::from_reader(reader);
+    let res = HangoutsSeed(&mut state)
+        .deserialize(&mut de)
+        .and_then(|_| de.end());
+
+    match (res, state.error) {
+        (_, Some(err)) => Err(StreamError::Callback(err)),
+        (Err(err), None) => Err(StreamError::Json(err)),
+        (Ok(()), None) => Ok(()),
+    }
+}
+
+struct State<F, E> {
+    f: F,
+    // Error returned by the callback, kept aside while the deserializer unwinds.
+    error: Option<E>,
+}
+
+struct HangoutsSeed<'a, F, E>(&'a mut State<F, E>);
+
+impl<'de, 'a, F, E> DeserializeSeed<'de> for HangoutsSeed<'a, F, E>
+where
+    F: FnMut(Conversation) -> Result<(), E>,
+{
+    type Value = ();
+
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'a, F, E> Visitor<'de> for HangoutsSeed<'a, F, E>
+where
+    F: FnMut(Conversation) -> Result<(), E>,
+{
+    type Value = ();
+
+    #[inline]
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a Hangouts.json object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        const FIELDS: &[&str] = &["conversations"];
+
+        let mut seen = false;
+        while let Some(key) = map.next_key::<String>()? {
+            if key != "conversations" {
+                return Err(de::Error::unknown_field(&key, FIELDS));
+            }
+            if seen {
+                return Err(de::Error::duplicate_field("conversations"));
+            }
+            map.next_value_seed(ConversationsSeed(&mut *self.0))?;
+            seen = true;
+        }
+
+        if seen {
+            Ok(())
+        } else {
+            Err(de::Error::missing_field("conversations"))
+        }
+    }
+}
+
+struct ConversationsSeed<'a, F, E>(&'a mut State<F, E>);
+
+impl<'de, 'a, F, E> DeserializeSeed<'de> for ConversationsSeed<'a, F, E>
+where
+    F: FnMut(Conversation) -> Result<(), E>,
+{
+    type Value = ();
+
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a, F, E> Visitor<'de> for ConversationsSeed<'a, F, E>
+where
+    F: FnMut(Conversation) -> Result<(), E>,
+{
+    type Value = ();
+
+    #[inline]
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a list of conversations")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(conversation) = seq.next_element::<Conversation>()? {
+            if let Err(err) = (self.0.f)(conversation) {
+                self.0.error = Some(err);
+                return Err(de::Error::custom("stopped by callback"));
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{Conversation, Event, EventData, Hangouts, ParticipantId};
+
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, NaiveDate, TimeZone, Timelike};
+
+/// The kind of an event, for filtering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub enum EventKind {
+    /// Chat message.
+    ChatMessage,
+    /// Hangout call event.
+    HangoutEvent,
+    /// Conversation membership change.
+    MembershipChange,
+    /// Conversation name change.
+    ConversationRename,
+}
+
+impl EventKind {
+    /// Get the kind of `data`.
+    #[inline]
+    pub fn of(data: &EventData) -> Self {
+        match data {
+            EventData::ChatMessage(_) => Self::ChatMessage,
+            EventData::HangoutEvent(_) => Self::HangoutEvent,
+            EventData::MembershipChange(_) => Self::MembershipChange,
+            EventData::ConversationRename(_) => Self::ConversationRename,
+        }
+    }
+}
+
+/// Selection of the events counted in an [`Activity`].
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ActivityFilter {
+    /// Senders of the counted events. All senders are counted if empty.
+    pub senders: Vec<ParticipantId>,
+    /// Kinds of the counted events. All kinds are counted if empty.
+    pub kinds: Vec<EventKind>,
+}
+
+impl ActivityFilter {
+    /// Filter counting only the chat messages of all senders.
+    #[inline]
+    pub fn chat_messages() -> Self {
+        Self {
+            senders: Vec::new(),
+            kinds: vec![EventKind::ChatMessage],
+        }
+    }
+
+    /// Returns `true` if `event` is selected by the filter.
+    #[inline]
+    pub fn matches(&self, event: &Event) -> bool {
+        (self.senders.is_empty() || self.senders.contains(&event.sender))
+            && (self.kinds.is_empty() || self.kinds.contains(&EventKind::of(&event.data)))
+    }
+}
+
+/// Event counts bucketed by local time.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct Activity {
+    /// Counts by hour of the day, from midnight.
+    pub by_hour: [usize; 24],
+    /// Counts by day of the week, from Monday.
+    pub by_weekday: [usize; 7],
+    /// Counts by day. Days without events are absent.
+    pub by_day: BTreeMap<NaiveDate, usize>,
+    /// Counts by month, keyed by the first day of the month. Months without events are absent.
+    pub by_month: BTreeMap<NaiveDate, usize>,
+    /// Counts by year. Years without events are absent.
+    pub by_year: BTreeMap<i32, usize>,
+}
+
+impl Activity {
+    /// Bucket the events of a conversation selected by `filter` by their time in `tz`.
+    #[inline]
+    pub fn for_conversation<Tz: TimeZone>(
+        conversation: &Conversation,
+        tz: &Tz,
+        filter: &ActivityFilter,
+    ) -> Self {
+        let mut activity = Self::default();
+        activity.add_all(&conversation.events, tz, filter);
+        activity
+    }
+
+    /// Bucket the events of all conversations selected by `filter` by their time in `tz`.
+    #[inline]
+    pub fn for_hangouts<Tz: TimeZone>(
+        hangouts: &Hangouts,
+        tz: &Tz,
+        filter: &ActivityFilter,
+    ) -> Self {
+        let mut activity = Self::default();
+        for conversation in &hangouts.conversations {
+            activity.add_all(&conversation.events, tz, filter);
+        }
+        activity
+    }
+
+    /// Add the events selected by `filter`, bucketed by their time in `tz`.
+    pub fn add_all<'a, I, Tz>(&mut self, events: I, tz: &Tz, filter: &ActivityFilter)
+    where
+        I: IntoIterator<Item = &'a Event>,
+        Tz: TimeZone,
+    {
+        for event in events.into_iter().filter(|event| filter.matches(event)) {
+            self.add(event, tz);
+        }
+    }
+
+    /// Add a single event, bucketed by its time in `tz`.
+    pub fn add<Tz: TimeZone>(&mut self, event: &Event, tz: &Tz) {
+        let time = event.timestamp.with_timezone(tz);
+        let date = time.date_naive();
+
+        self.by_hour[time.hour() as usize] += 1;
+        self.by_weekday[time.weekday().num_days_from_monday() as usize] += 1;
+        *self.by_day.entry(date).or_default() += 1;
+        *self.by_month.entry(date.with_day(1).unwrap()).or_default() += 1;
+        *self.by_year.entry(date.year()).or_default() += 1;
+    }
+
+    /// Total number of counted events.
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.by_hour.iter().sum()
+    }
+}
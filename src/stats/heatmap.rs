@@ -0,0 +1,161 @@
+use crate::export::escape_xml;
+
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+use chrono::{Datelike, Duration, NaiveDate};
+
+/// Width of the weekday labels on the left.
+const LABEL_WIDTH: u32 = 28;
+/// Height of the month labels on top.
+const LABEL_HEIGHT: u32 = 16;
+
+/// Options for rendering a calendar heatmap.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeatmapOptions {
+    /// Width and height of a day cell in pixels.
+    pub cell_size: u32,
+    /// Space between cells in pixels.
+    pub gap: u32,
+    /// Fill colors from no activity to the most activity, as SVG paint values. Must not be
+    /// empty.
+    pub colors: Vec<String>,
+    /// First day shown, defaulting to the first day with activity.
+    pub start: Option<NaiveDate>,
+    /// Last day shown, defaulting to the last day with activity.
+    pub end: Option<NaiveDate>,
+}
+
+impl Default for HeatmapOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            cell_size: 11,
+            gap: 2,
+            colors: ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"]
+                .iter()
+                .map(|color| (*color).to_owned())
+                .collect(),
+            start: None,
+            end: None,
+        }
+    }
+}
+
+/// Render daily counts, such as [`Activity::by_day`], as a calendar heatmap in SVG.
+///
+/// Weeks are laid out as columns and weekdays as rows from Monday, with a cell for every day
+/// colored by its count relative to the highest count. The document is self-contained and
+/// has a tooltip with the count for every day.
+///
+/// Fails with [`io::ErrorKind::InvalidInput`] if [`HeatmapOptions::colors`] is empty.
+///
+/// [`Activity::by_day`]: crate::stats::Activity::by_day
+pub fn write_heatmap<W: Write>(
+    mut writer: W,
+    days: &BTreeMap<NaiveDate, usize>,
+    options: &HeatmapOptions,
+) -> io::Result<()> {
+    if options.colors.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "heatmap needs at least one color",
+        ));
+    }
+    let colors: Vec<String> = options.colors.iter().map(|c| escape_xml(c)).collect();
+
+    let start = options.start.or_else(|| days.keys().next().copied());
+    let end = options.end.or_else(|| days.keys().next_back().copied());
+    let (start, end) = match (start, end) {
+        (Some(start), Some(end)) if start <= end => (start, end),
+        _ => {
+            writeln!(
+                writer,
+                r#"<svg xmlns="http://www.w3.org/2000/svg" width="0" height="0"></svg>"#
+            )?;
+            return Ok(());
+        }
+    };
+
+    let step = options.cell_size + options.gap;
+    // Align the grid to the Monday of the first week.
+    let grid_start = start - Duration::days(start.weekday().num_days_from_monday() as i64);
+    let weeks = ((end - grid_start).num_days() / 7 + 1) as u32;
+    let width = LABEL_WIDTH + weeks * step;
+    let height = LABEL_HEIGHT + 7 * step;
+    let max = days
+        .range(start..=end)
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0);
+
+    writeln!(
+        writer,
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}" font-family="sans-serif" font-size="9" fill="#767676">"##,
+        width, height
+    )?;
+
+    for (row, label) in [(0, "Mon"), (2, "Wed"), (4, "Fri")].iter() {
+        writeln!(
+            writer,
+            r#"  <text x="0" y="{}">{}</text>"#,
+            LABEL_HEIGHT + row * step + options.cell_size - 2,
+            label
+        )?;
+    }
+
+    let mut date = start;
+    let mut labeled = None;
+    while date <= end {
+        let offset = (date - grid_start).num_days();
+        let (week, row) = ((offset / 7) as u32, (offset % 7) as u32);
+        let (x, y) = (LABEL_WIDTH + week * step, LABEL_HEIGHT + row * step);
+
+        // Label each month above the first week starting in it, and the first week shown.
+        let month = (date.year(), date.month());
+        if labeled != Some(month) && (row == 0 || date == start) {
+            let format = if labeled.is_none() || date.month() == 1 {
+                "%b %Y"
+            } else {
+                "%b"
+            };
+            writeln!(
+                writer,
+                r#"  <text x="{}" y="{}">{}</text>"#,
+                x,
+                LABEL_HEIGHT - 5,
+                date.format(format)
+            )?;
+            labeled = Some(month);
+        }
+
+        let count = days.get(&date).copied().unwrap_or(0);
+        writeln!(
+            writer,
+            r#"  <rect x="{x}" y="{y}" width="{size}" height="{size}" rx="2" fill="{fill}"><title>{date}: {count}</title></rect>"#,
+            x = x,
+            y = y,
+            size = options.cell_size,
+            fill = color(count, max, &colors),
+            date = date.format("%Y-%m-%d"),
+            count = count,
+        )?;
+
+        date = date.succ_opt().unwrap();
+    }
+
+    writeln!(writer, "</svg>")?;
+    Ok(())
+}
+
+/// Pick the color for `count`, reserving the first color for days without activity.
+///
+/// `colors` must not be empty.
+fn color(count: usize, max: usize, colors: &[String]) -> &str {
+    let levels = colors.len() - 1;
+    if count == 0 || levels == 0 {
+        return &colors[0];
+    }
+    let level = (count * levels).div_ceil(max).clamp(1, levels);
+    &colors[level]
+}
@@ -0,0 +1,23 @@
+//! Statistics over conversations.
+//!
+//! [`Stats`] holds message, word, attachment and call counts per participant and in total,
+//! for a single conversation or for all of them. [`Activity`] buckets events by local time
+//! for charts, such as the calendar heatmap rendered by [`write_heatmap`]. [`Responsiveness`]
+//! measures reply latencies over the [`sessions`] and turns of conversations. With the
+//! `text-stats` feature, `Vocabulary` counts words, emoji and link domains.
+
+mod activity;
+#[cfg(feature = "text-stats")]
+mod emoji;
+mod heatmap;
+mod summary;
+mod turns;
+#[cfg(feature = "text-stats")]
+mod words;
+
+pub use self::activity::*;
+pub use self::heatmap::*;
+pub use self::summary::*;
+pub use self::turns::*;
+#[cfg(feature = "text-stats")]
+pub use self::words::*;
@@ -0,0 +1,248 @@
+use crate::{
+    ChatMessage, ChatSegment, Conversation, Event, EventData, HangoutEventType, Hangouts,
+    Participant, ParticipantId,
+};
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, NaiveDate, Utc};
+
+/// Statistics of all conversations in an export.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct HangoutsStats {
+    /// Statistics over all conversations together.
+    pub overall: Stats,
+    /// Statistics of every conversation, in the order of the export.
+    pub conversations: Vec<Stats>,
+}
+
+impl HangoutsStats {
+    /// Compute the statistics of `hangouts`.
+    pub fn new(hangouts: &Hangouts) -> Self {
+        Self {
+            overall: Stats::for_hangouts(hangouts),
+            conversations: hangouts
+                .conversations
+                .iter()
+                .map(Stats::for_conversation)
+                .collect(),
+        }
+    }
+}
+
+/// Statistics of one or more conversations.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct Stats {
+    /// ID of the conversation, or [`None`] if computed over several conversations.
+    pub conversation_id: Option<String>,
+    /// Statistics of every participant that sent a message, by descending message count.
+    pub participants: Vec<ParticipantStats>,
+    /// Total counts of all chat messages.
+    pub totals: MessageCounts,
+    /// Number of finished hangout calls.
+    pub calls: usize,
+    /// Total duration of all hangout calls in seconds.
+    pub call_duration: u64,
+    /// The first chat message.
+    pub first_message: Option<MessageRef>,
+    /// The last chat message.
+    pub last_message: Option<MessageRef>,
+    /// The day (in UTC) with the most chat messages, the earliest one if tied.
+    pub busiest_day: Option<DayCount>,
+    /// The longest time without chat messages between the first and last message.
+    pub longest_silence: Option<Silence>,
+}
+
+/// Statistics of a single participant.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct ParticipantStats {
+    /// ID of the participant.
+    pub id: ParticipantId,
+    /// Name of the participant, if known.
+    pub name: Option<String>,
+    /// Counts of the chat messages sent by the participant.
+    pub counts: MessageCounts,
+}
+
+/// Counts of chat messages and their contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct MessageCounts {
+    /// Number of chat messages.
+    pub messages: usize,
+    /// Number of whitespace-separated words in the message text.
+    pub words: usize,
+    /// Number of characters in the message text.
+    pub characters: usize,
+    /// Number of attachments of any kind.
+    pub attachments: usize,
+    /// Number of link segments.
+    pub links: usize,
+    /// Number of place attachments.
+    pub places: usize,
+}
+
+impl MessageCounts {
+    /// Add the counts of `message`.
+    pub fn add(&mut self, message: &ChatMessage) {
+        let text = message.contents_as_str();
+        self.messages += 1;
+        self.words += text.split_whitespace().count();
+        self.characters += text.chars().count();
+        self.attachments += message.attachments.len();
+        self.links += message
+            .contents
+            .iter()
+            .filter(|segment| matches!(segment, ChatSegment::Link { .. }))
+            .count();
+        self.places += message
+            .attachments
+            .iter()
+            .filter(|attachment| attachment.item.place.is_some())
+            .count();
+    }
+}
+
+/// Reference to a chat message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct MessageRef {
+    /// ID of the conversation containing the message.
+    pub conversation_id: String,
+    /// ID of the message event.
+    pub event_id: String,
+    /// Sender of the message.
+    pub sender: ParticipantId,
+    /// Time the message was sent.
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Number of chat messages on a day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct DayCount {
+    /// The day, in UTC.
+    pub date: NaiveDate,
+    /// Number of chat messages.
+    pub messages: usize,
+}
+
+/// A period without chat messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct Silence {
+    /// Time of the last message before the silence.
+    pub start: DateTime<Utc>,
+    /// Time of the first message after the silence.
+    pub end: DateTime<Utc>,
+    /// Length of the silence in seconds.
+    pub secs: i64,
+}
+
+impl Stats {
+    /// Compute the statistics of a single conversation.
+    #[inline]
+    pub fn for_conversation(conversation: &Conversation) -> Self {
+        let mut stats = Self::compute(std::iter::once(conversation));
+        stats.conversation_id = Some(conversation.conversation_id.clone());
+        stats
+    }
+
+    /// Compute the statistics over all conversations of `hangouts` together.
+    #[inline]
+    pub fn for_hangouts(hangouts: &Hangouts) -> Self {
+        Self::compute(hangouts.conversations.iter())
+    }
+
+    /// Get the statistics of a participant, if they sent any message.
+    #[inline]
+    pub fn participant(&self, id: &ParticipantId) -> Option<&ParticipantStats> {
+        self.participants.iter().find(|stats| &stats.id == id)
+    }
+
+    fn compute<'a, I: Iterator<Item = &'a Conversation>>(conversations: I) -> Self {
+        let mut stats = Self::default();
+        let mut participants: HashMap<&ParticipantId, ParticipantStats> = HashMap::new();
+        let mut days: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+        let mut messages: Vec<(&Conversation, &Event)> = Vec::new();
+
+        for conversation in conversations {
+            for event in &conversation.events {
+                match &event.data {
+                    EventData::ChatMessage(message) => {
+                        stats.totals.add(message);
+                        participants
+                            .entry(&event.sender)
+                            .or_insert_with(|| ParticipantStats {
+                                id: event.sender.clone(),
+                                name: None,
+                                counts: MessageCounts::default(),
+                            })
+                            .counts
+                            .add(message);
+                        *days.entry(event.timestamp.date_naive()).or_default() += 1;
+                        messages.push((conversation, event));
+                    }
+                    EventData::HangoutEvent(hangout) => {
+                        if let HangoutEventType::End { duration } = hangout.typ {
+                            stats.calls += 1;
+                            stats.call_duration = stats.call_duration.saturating_add(duration);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            for (id, stats) in participants.iter_mut() {
+                if stats.name.is_none() {
+                    stats.name = conversation
+                        .participants
+                        .get(id)
+                        .and_then(Participant::name)
+                        .cloned();
+                }
+            }
+        }
+
+        stats.participants = participants.into_values().collect();
+        stats.participants.sort_by(|a, b| {
+            b.counts
+                .messages
+                .cmp(&a.counts.messages)
+                .then_with(|| a.id.cmp(&b.id))
+        });
+
+        messages.sort_by_key(|(_, event)| event.timestamp);
+        stats.first_message = messages.first().map(message_ref);
+        stats.last_message = messages.last().map(message_ref);
+        stats.longest_silence = messages
+            .windows(2)
+            .map(|pair| (pair[0].1.timestamp, pair[1].1.timestamp))
+            .max_by(|a, b| (a.1 - a.0).cmp(&(b.1 - b.0)).then(b.0.cmp(&a.0)))
+            .map(|(start, end)| Silence {
+                start,
+                end,
+                secs: (end - start).num_seconds(),
+            });
+
+        stats.busiest_day = days
+            .into_iter()
+            .max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(&a.0)))
+            .map(|(date, messages)| DayCount { date, messages });
+
+        stats
+    }
+}
+
+#[inline]
+fn message_ref((conversation, event): &(&Conversation, &Event)) -> MessageRef {
+    MessageRef {
+        conversation_id: conversation.conversation_id.clone(),
+        event_id: event.id.clone(),
+        sender: event.sender.clone(),
+        timestamp: event.timestamp,
+    }
+}
@@ -0,0 +1,225 @@
+use crate::{Conversation, Hangouts, ParticipantId};
+
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Utc};
+
+/// Options for segmenting conversations into sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SessionOptions {
+    /// Longest time between two chat messages of the same session.
+    pub gap: Duration,
+}
+
+impl Default for SessionOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            gap: Duration::hours(1),
+        }
+    }
+}
+
+/// A run of chat messages without a longer pause than the session gap.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct Session {
+    /// The turns of the session, in order.
+    pub turns: Vec<Turn>,
+}
+
+impl Session {
+    /// Participant that sent the first message of the session.
+    #[inline]
+    pub fn starter(&self) -> &ParticipantId {
+        &self.turns[0].sender
+    }
+
+    /// Time of the first message.
+    #[inline]
+    pub fn start(&self) -> DateTime<Utc> {
+        self.turns[0].start
+    }
+
+    /// Time of the last message.
+    #[inline]
+    pub fn end(&self) -> DateTime<Utc> {
+        self.turns[self.turns.len() - 1].end
+    }
+
+    /// Number of chat messages.
+    #[inline]
+    pub fn messages(&self) -> usize {
+        self.turns.iter().map(|turn| turn.messages).sum()
+    }
+}
+
+/// Consecutive chat messages of the same sender within a session.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct Turn {
+    /// Sender of the messages.
+    pub sender: ParticipantId,
+    /// Time of the first message.
+    pub start: DateTime<Utc>,
+    /// Time of the last message.
+    pub end: DateTime<Utc>,
+    /// Number of chat messages.
+    pub messages: usize,
+}
+
+/// Segment the chat messages of `conversation` into sessions and turns.
+pub fn sessions(conversation: &Conversation, options: &SessionOptions) -> Vec<Session> {
+    let mut messages: Vec<_> = conversation
+        .events
+        .iter()
+        .filter(|event| event.data.is_chat_message())
+        .collect();
+    messages.sort_by_key(|event| event.timestamp);
+
+    let mut sessions: Vec<Session> = Vec::new();
+    let mut last: Option<DateTime<Utc>> = None;
+    for event in messages {
+        let continues = last.is_some_and(|last| event.timestamp - last <= options.gap);
+        last = Some(event.timestamp);
+
+        if !continues {
+            sessions.push(Session { turns: Vec::new() });
+        }
+        let turns = &mut sessions.last_mut().unwrap().turns;
+        match turns.last_mut() {
+            Some(turn) if turn.sender == event.sender => {
+                turn.end = event.timestamp;
+                turn.messages += 1;
+            }
+            _ => turns.push(Turn {
+                sender: event.sender.clone(),
+                start: event.timestamp,
+                end: event.timestamp,
+                messages: 1,
+            }),
+        }
+    }
+    sessions
+}
+
+/// Reply latencies and session statistics of one or more conversations.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct Responsiveness {
+    /// ID of the conversation, or [`None`] if computed over several conversations.
+    pub conversation_id: Option<String>,
+    /// Statistics of every participant that sent a message, sorted by ID.
+    pub participants: Vec<ResponseStats>,
+    /// Number of sessions.
+    pub sessions: usize,
+    /// Average time from the first to the last message of a session, in seconds.
+    pub average_session_secs: f64,
+    /// Average number of chat messages in a session.
+    pub average_session_messages: f64,
+}
+
+/// Reply latencies and turn counts of a participant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct ResponseStats {
+    /// ID of the participant.
+    pub id: ParticipantId,
+    /// Number of turns.
+    pub turns: usize,
+    /// Number of sessions started.
+    pub sessions_started: usize,
+    /// Number of turns replying to another participant within a session.
+    pub replies: usize,
+    /// Median reply latency in seconds.
+    pub median_reply_secs: Option<i64>,
+    /// 90th percentile reply latency in seconds.
+    pub p90_reply_secs: Option<i64>,
+}
+
+impl Responsiveness {
+    /// Compute the responsiveness in a single conversation.
+    #[inline]
+    pub fn for_conversation(conversation: &Conversation, options: &SessionOptions) -> Self {
+        let mut responsiveness = Self::compute(std::iter::once(conversation), options);
+        responsiveness.conversation_id = Some(conversation.conversation_id.clone());
+        responsiveness
+    }
+
+    /// Compute the responsiveness over all conversations of `hangouts` together.
+    #[inline]
+    pub fn for_hangouts(hangouts: &Hangouts, options: &SessionOptions) -> Self {
+        Self::compute(hangouts.conversations.iter(), options)
+    }
+
+    /// Get the statistics of a participant, if they sent any message.
+    #[inline]
+    pub fn participant(&self, id: &ParticipantId) -> Option<&ResponseStats> {
+        self.participants.iter().find(|stats| &stats.id == id)
+    }
+
+    fn compute<'a, I>(conversations: I, options: &SessionOptions) -> Self
+    where
+        I: Iterator<Item = &'a Conversation>,
+    {
+        // Turn and session counts with the reply latencies of every participant.
+        let mut participants: BTreeMap<ParticipantId, (usize, usize, Vec<i64>)> = BTreeMap::new();
+        let (mut count, mut secs, mut messages) = (0, 0, 0);
+
+        for conversation in conversations {
+            for session in sessions(conversation, options) {
+                count += 1;
+                secs += (session.end() - session.start()).num_seconds();
+                messages += session.messages();
+                participants.entry(session.starter().clone()).or_default().1 += 1;
+
+                let mut previous: Option<&Turn> = None;
+                for turn in &session.turns {
+                    let entry = participants.entry(turn.sender.clone()).or_default();
+                    entry.0 += 1;
+                    if let Some(previous) = previous {
+                        entry.2.push((turn.start - previous.end).num_seconds());
+                    }
+                    previous = Some(turn);
+                }
+            }
+        }
+
+        let average = |total: f64| {
+            if count == 0 {
+                0.0
+            } else {
+                total / count as f64
+            }
+        };
+        Self {
+            conversation_id: None,
+            participants: participants
+                .into_iter()
+                .map(|(id, (turns, sessions_started, mut latencies))| {
+                    latencies.sort_unstable();
+                    ResponseStats {
+                        id,
+                        turns,
+                        sessions_started,
+                        replies: latencies.len(),
+                        median_reply_secs: percentile(&latencies, 50),
+                        p90_reply_secs: percentile(&latencies, 90),
+                    }
+                })
+                .collect(),
+            sessions: count,
+            average_session_secs: average(secs as f64),
+            average_session_messages: average(messages as f64),
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted `values`.
+fn percentile(values: &[i64], p: usize) -> Option<i64> {
+    if values.is_empty() {
+        return None;
+    }
+    let rank = (p * values.len()).div_ceil(100).max(1);
+    Some(values[rank - 1])
+}
@@ -0,0 +1,278 @@
+use super::emoji::is_emoji;
+use crate::util::percent_decode;
+use crate::{ChatSegment, Conversation, Event, Hangouts, ParticipantId};
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::Datelike;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Language of a built-in stopword list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub enum Language {
+    English,
+    French,
+    German,
+    Spanish,
+}
+
+impl Language {
+    /// Get the lowercase stopwords of the language.
+    pub fn stopwords(&self) -> &'static [&'static str] {
+        match self {
+            Self::English => &[
+                "a", "about", "after", "all", "also", "am", "an", "and", "any", "are", "as", "at",
+                "be", "because", "been", "but", "by", "can", "could", "did", "do", "does", "for",
+                "from", "had", "has", "have", "he", "her", "him", "his", "how", "i", "if", "in",
+                "into", "is", "it", "its", "just", "me", "my", "no", "not", "of", "on", "or",
+                "our", "out", "she", "so", "than", "that", "the", "their", "them", "then", "there",
+                "they", "this", "to", "too", "up", "us", "was", "we", "were", "what", "when",
+                "which", "who", "will", "with", "would", "you", "your",
+            ],
+            Self::French => &[
+                "a", "au", "aux", "avec", "ce", "ces", "dans", "de", "des", "du", "elle", "en",
+                "est", "et", "eux", "il", "ils", "je", "la", "le", "les", "leur", "lui", "ma",
+                "mais", "me", "mes", "moi", "mon", "ne", "nos", "notre", "nous", "on", "ou", "par",
+                "pas", "pour", "qu", "que", "qui", "sa", "se", "ses", "son", "sur", "ta", "te",
+                "tes", "toi", "ton", "tu", "un", "une", "vos", "votre", "vous", "y",
+            ],
+            Self::German => &[
+                "aber", "als", "am", "an", "auch", "auf", "aus", "bei", "bin", "bis", "da", "das",
+                "dass", "dem", "den", "der", "des", "die", "du", "ein", "eine", "einen", "er",
+                "es", "für", "hat", "ich", "ihr", "im", "in", "ist", "ja", "mit", "nach", "nicht",
+                "noch", "nur", "oder", "sich", "sie", "sind", "so", "um", "und", "uns", "von",
+                "vor", "war", "was", "wie", "wir", "zu", "zum", "zur",
+            ],
+            Self::Spanish => &[
+                "a", "al", "algo", "como", "con", "de", "del", "el", "ella", "en", "es", "esa",
+                "ese", "esta", "este", "está", "fue", "ha", "la", "las", "le", "les", "lo", "los",
+                "me", "mi", "muy", "más", "no", "nos", "o", "para", "pero", "por", "que", "se",
+                "si", "sin", "su", "sus", "te", "tu", "un", "una", "y", "ya", "yo",
+            ],
+        }
+    }
+}
+
+/// Options for text statistics.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextOptions {
+    /// Languages whose stopwords are excluded from word counts.
+    pub stopwords: Vec<Language>,
+    /// Number of words in the counted n-grams. N-grams are not counted if less than 2.
+    pub ngram_len: usize,
+}
+
+impl Default for TextOptions {
+    #[inline]
+    fn default() -> Self {
+        Self {
+            stopwords: Vec::new(),
+            ngram_len: 2,
+        }
+    }
+}
+
+impl TextOptions {
+    #[inline]
+    fn is_stopword(&self, word: &str) -> bool {
+        self.stopwords
+            .iter()
+            .any(|language| language.stopwords().contains(&word))
+    }
+}
+
+/// Occurrence counts of strings.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct Frequencies {
+    /// Counts by string.
+    pub counts: HashMap<String, usize>,
+}
+
+impl Frequencies {
+    /// Count one occurrence of `s`.
+    #[inline]
+    pub fn add(&mut self, s: &str) {
+        match self.counts.get_mut(s) {
+            Some(count) => *count += 1,
+            None => {
+                self.counts.insert(s.to_owned(), 1);
+            }
+        }
+    }
+
+    /// Get the count of `s`.
+    #[inline]
+    pub fn get(&self, s: &str) -> usize {
+        self.counts.get(s).copied().unwrap_or(0)
+    }
+
+    /// Total number of occurrences.
+    #[inline]
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Get the `n` most frequent strings with their counts, ties broken alphabetically.
+    pub fn top(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut top: Vec<_> = self
+            .counts
+            .iter()
+            .map(|(s, count)| (s.as_str(), *count))
+            .collect();
+        top.sort_unstable_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        top.truncate(n);
+        top
+    }
+}
+
+/// Word, n-gram, emoji and link domain counts.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct TextStats {
+    /// Lowercased words, without stopwords.
+    pub words: Frequencies,
+    /// Lowercased n-grams of consecutive words joined by spaces, without those made of
+    /// stopwords only.
+    pub ngrams: Frequencies,
+    /// Emoji, by grapheme cluster.
+    pub emoji: Frequencies,
+    /// Domains of link targets, without a leading `www.`.
+    pub domains: Frequencies,
+}
+
+/// Text statistics of chat messages, overall, per participant and per year.
+#[derive(Debug, Clone, PartialEq, Default)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct Vocabulary {
+    /// Statistics of all messages.
+    pub overall: TextStats,
+    /// Statistics of the messages of every sender.
+    pub by_participant: HashMap<ParticipantId, TextStats>,
+    /// Statistics of the messages of every year (in UTC).
+    pub by_year: BTreeMap<i32, TextStats>,
+}
+
+impl Vocabulary {
+    /// Compute the text statistics of a single conversation.
+    #[inline]
+    pub fn for_conversation(conversation: &Conversation, options: &TextOptions) -> Self {
+        let mut vocabulary = Self::default();
+        for event in &conversation.events {
+            vocabulary.add(event, options);
+        }
+        vocabulary
+    }
+
+    /// Compute the text statistics of all conversations of `hangouts`.
+    #[inline]
+    pub fn for_hangouts(hangouts: &Hangouts, options: &TextOptions) -> Self {
+        let mut vocabulary = Self::default();
+        for event in hangouts.conversations.iter().flat_map(|c| &c.events) {
+            vocabulary.add(event, options);
+        }
+        vocabulary
+    }
+
+    /// Add the contents of `event` if it is a chat message.
+    pub fn add(&mut self, event: &Event, options: &TextOptions) {
+        let message = match event.data.as_chat_message() {
+            Some(message) => message,
+            None => return,
+        };
+
+        let mut stats = TextStats::default();
+        for segment in &message.contents {
+            match segment {
+                ChatSegment::Text { text, .. } => add_text(&mut stats, text, options),
+                ChatSegment::Link { target, .. } => {
+                    if let Some(domain) = link_domain(target) {
+                        stats.domains.add(&domain);
+                    }
+                }
+                ChatSegment::LinkBreak { .. } => {}
+            }
+        }
+
+        let year = event.timestamp.year();
+        for target in [
+            &mut self.overall,
+            self.by_participant.entry(event.sender.clone()).or_default(),
+            self.by_year.entry(year).or_default(),
+        ] {
+            merge(target, &stats);
+        }
+    }
+}
+
+fn add_text(stats: &mut TextStats, text: &str, options: &TextOptions) {
+    // Keycap emoji are words of their own by Unicode rules.
+    let words: Vec<String> = text
+        .unicode_words()
+        .filter(|word| !is_emoji(word))
+        .map(str::to_lowercase)
+        .collect();
+    for word in &words {
+        if !options.is_stopword(word) {
+            stats.words.add(word);
+        }
+    }
+    if options.ngram_len >= 2 {
+        for ngram in words.windows(options.ngram_len) {
+            if !ngram.iter().all(|word| options.is_stopword(word)) {
+                stats.ngrams.add(&ngram.join(" "));
+            }
+        }
+    }
+
+    for grapheme in text.graphemes(true) {
+        if is_emoji(grapheme) {
+            stats.emoji.add(grapheme);
+        }
+    }
+}
+
+fn merge(target: &mut TextStats, stats: &TextStats) {
+    let pairs = [
+        (&mut target.words, &stats.words),
+        (&mut target.ngrams, &stats.ngrams),
+        (&mut target.emoji, &stats.emoji),
+        (&mut target.domains, &stats.domains),
+    ];
+    for (target, source) in pairs {
+        for (s, count) in &source.counts {
+            *target.counts.entry(s.clone()).or_default() += count;
+        }
+    }
+}
+
+/// Get the lowercased domain of a link target.
+///
+/// Google redirect links are unwrapped to the domain of the link they redirect to.
+fn link_domain(target: &str) -> Option<String> {
+    let (scheme, rest) = target.split_once("://")?;
+    if !scheme.eq_ignore_ascii_case("http") && !scheme.eq_ignore_ascii_case("https") {
+        return None;
+    }
+    let (authority, path) = rest.split_at(rest.find(['/', '?', '#']).unwrap_or(rest.len()));
+    let host = authority.rsplit('@').next()?;
+    let host = host.split(':').next()?.to_lowercase();
+    let host = host.strip_prefix("www.").unwrap_or(&host).to_owned();
+
+    if host.starts_with("google.") && path.starts_with("/url?") {
+        let redirect = path[5..]
+            .split('&')
+            .find_map(|param| param.strip_prefix("q="))
+            .map(percent_decode);
+        if let Some(domain) = redirect.as_deref().and_then(link_domain) {
+            return Some(domain);
+        }
+    }
+
+    if host.is_empty() {
+        None
+    } else {
+        Some(host)
+    }
+}
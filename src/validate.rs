@@ -0,0 +1,282 @@
+//! Integrity checks of conversations.
+//!
+//! Exports that parse fine can still be inconsistent, after a merge, a redaction or through
+//! bugs of the export itself. [`Hangouts::validate`] collects such problems in a
+//! [`ValidationReport`] instead of failing:
+//!
+//! - current participants missing from the participants of the conversation,
+//! - events sent by someone who is not a participant,
+//! - events sharing their ID with an earlier event,
+//! - calls ending without having started,
+//! - renames from a name other than the one set by the previous rename,
+//! - read states later than the time of validation,
+//! - and, in raw conversations, events whose header names another conversation.
+//!
+//! The last check needs the raw event headers dropped in conversion, so only
+//! [`raw::Hangouts::validate`] runs all of them. Large exports can be checked one conversation
+//! at a time with [`ValidationReport::check`] and [`ValidationReport::check_raw`].
+
+#[cfg(feature = "raw")]
+use crate::raw::{self, convert::ConversionError};
+use crate::{Conversation, EventData, Hangouts, ParticipantId};
+
+use std::collections::HashSet;
+#[cfg(feature = "raw")]
+use std::convert::TryFrom;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+
+/// Result of the validation of conversations.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct ValidationReport {
+    /// Time read states are compared to.
+    pub now: DateTime<Utc>,
+    /// Number of conversations checked.
+    pub conversations: usize,
+    /// Number of events checked.
+    pub events: usize,
+    /// Problems found, in order of conversation.
+    pub issues: Vec<Issue>,
+}
+
+impl ValidationReport {
+    /// Start an empty report, flagging read states later than `now`.
+    #[inline]
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now,
+            conversations: 0,
+            events: 0,
+            issues: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if no problems were found.
+    #[inline]
+    pub fn is_valid(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// Check a conversation and add its problems to the report.
+    pub fn check(&mut self, conversation: &Conversation) {
+        self.conversations += 1;
+        self.events += conversation.events.len();
+        let now = self.now;
+        let conversation_id = &conversation.conversation_id;
+        let mut issue = |event_id: Option<&String>, kind: IssueKind| {
+            self.issues.push(Issue {
+                conversation_id: conversation_id.clone(),
+                event_id: event_id.cloned(),
+                kind,
+            })
+        };
+
+        for id in &conversation.current_participants {
+            if !conversation.participants.contains_key(id) {
+                issue(
+                    None,
+                    IssueKind::UnknownCurrentParticipant {
+                        participant: id.clone(),
+                    },
+                );
+            }
+        }
+
+        let mut participants: Vec<_> = conversation.participants.iter().collect();
+        participants.sort_by_key(|(id, _)| *id);
+        for (id, participant) in participants {
+            if participant.read_state.timestamp > now {
+                issue(
+                    None,
+                    IssueKind::FutureReadState {
+                        participant: id.clone(),
+                        timestamp: participant.read_state.timestamp,
+                    },
+                );
+            }
+        }
+
+        let mut events: Vec<_> = conversation.events.iter().collect();
+        events.sort_by_key(|event| event.timestamp);
+        let mut seen = HashSet::new();
+        let mut name: Option<&String> = None;
+        for event in events {
+            if !seen.insert(&event.id) {
+                issue(Some(&event.id), IssueKind::DuplicateEvent);
+            }
+            if !conversation.participants.contains_key(&event.sender) {
+                issue(
+                    Some(&event.id),
+                    IssueKind::UnknownSender {
+                        sender: event.sender.clone(),
+                    },
+                );
+            }
+            if let EventData::ConversationRename(rename) = &event.data {
+                if let Some(name) = name.filter(|name| **name != rename.old) {
+                    issue(
+                        Some(&event.id),
+                        IssueKind::RenameMismatch {
+                            expected: name.clone(),
+                            old: rename.old.clone(),
+                        },
+                    );
+                }
+                name = Some(&rename.new);
+            }
+        }
+
+        for call in conversation.calls() {
+            if call.start_event.is_none() {
+                issue(Some(&call.end_event.id), IssueKind::CallEndWithoutStart);
+            }
+        }
+    }
+
+    /// Check the parts of a raw conversation lost in conversion, adding its problems to the
+    /// report.
+    ///
+    /// This does not count the conversation; check its converted form too.
+    #[cfg(feature = "raw")]
+    pub fn check_raw(&mut self, conversation: &raw::Conversation) {
+        let conversation_id = &conversation.header.conversation_id.id;
+        for event in &conversation.events {
+            let header = &event.header.conversation_id.id;
+            if header != conversation_id {
+                self.issues.push(Issue {
+                    conversation_id: conversation_id.clone(),
+                    event_id: Some(event.header.event_id.clone()),
+                    kind: IssueKind::ConversationMismatch {
+                        header: header.clone(),
+                    },
+                });
+            }
+        }
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        writeln!(
+            f,
+            "{} conversations, {} events",
+            self.conversations, self.events
+        )?;
+        for issue in &self.issues {
+            writeln!(f, "{}", issue)?;
+        }
+        Ok(())
+    }
+}
+
+/// A problem found in a conversation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+pub struct Issue {
+    /// ID of the conversation.
+    pub conversation_id: String,
+    /// ID of the event, if the problem concerns one.
+    pub event_id: Option<String>,
+    #[cfg_attr(feature = "serde-impl", serde(flatten))]
+    pub kind: IssueKind,
+}
+
+impl fmt::Display for Issue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
+        write!(f, "{}: ", self.conversation_id)?;
+        let event = self.event_id.as_deref().unwrap_or_default();
+        match &self.kind {
+            IssueKind::UnknownCurrentParticipant { participant } => write!(
+                f,
+                "current participant {} is not a participant",
+                participant.gaia_id
+            ),
+            IssueKind::UnknownSender { sender } => write!(
+                f,
+                "event {} sent by unknown participant {}",
+                event, sender.gaia_id
+            ),
+            IssueKind::DuplicateEvent => write!(f, "duplicate event {}", event),
+            IssueKind::CallEndWithoutStart => {
+                write!(f, "call ended by event {} never started", event)
+            }
+            IssueKind::RenameMismatch { expected, old } => write!(
+                f,
+                "event {} renames from {:?} but the name was {:?}",
+                event, old, expected
+            ),
+            IssueKind::FutureReadState {
+                participant,
+                timestamp,
+            } => write!(
+                f,
+                "participant {} read up to {}, in the future",
+                participant.gaia_id,
+                timestamp.to_rfc3339()
+            ),
+            IssueKind::ConversationMismatch { header } => write!(
+                f,
+                "event {} belongs to conversation {} according to its header",
+                event, header
+            ),
+        }
+    }
+}
+
+/// Kind of an [`Issue`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde-impl", derive(serde::Deserialize, serde::Serialize))]
+#[cfg_attr(feature = "serde-impl", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum IssueKind {
+    /// A current participant is missing from the participants.
+    UnknownCurrentParticipant { participant: ParticipantId },
+    /// The sender of the event is not a participant.
+    UnknownSender { sender: ParticipantId },
+    /// An earlier event has the same ID.
+    DuplicateEvent,
+    /// The event ends a call without a preceding start event.
+    CallEndWithoutStart,
+    /// The event renames the conversation from another name than the one set by the
+    /// previous rename.
+    RenameMismatch { expected: String, old: String },
+    /// A participant has read the conversation up to a time after the validation.
+    FutureReadState {
+        participant: ParticipantId,
+        timestamp: DateTime<Utc>,
+    },
+    /// The header of the event names another conversation.
+    ConversationMismatch { header: String },
+}
+
+impl Hangouts {
+    /// Check every conversation for inconsistencies, flagging read states in the future of
+    /// the current time.
+    ///
+    /// Events filed under the wrong conversation cannot be detected after conversion; use
+    /// [`raw::Hangouts::validate`] to check for them too.
+    pub fn validate(&self) -> ValidationReport {
+        let mut report = ValidationReport::new(Utc::now());
+        for conversation in &self.conversations {
+            report.check(conversation);
+        }
+        report
+    }
+}
+
+#[cfg(feature = "raw")]
+impl raw::Hangouts {
+    /// Check every conversation for inconsistencies, including events filed under the wrong
+    /// conversation, flagging read states in the future of the current time.
+    ///
+    /// Conversations are converted one at a time to be checked, failing if one cannot be.
+    pub fn validate(&self) -> Result<ValidationReport, ConversionError> {
+        let mut report = ValidationReport::new(Utc::now());
+        for conversation in &self.conversations {
+            report.check_raw(conversation);
+            report.check(&Conversation::try_from(conversation.clone())?);
+        }
+        Ok(report)
+    }
+}
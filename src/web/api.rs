@@ -0,0 +1,239 @@
+use crate::export::jsonl::media_type_str;
+use crate::media::MediaRef;
+use crate::util::percent_encode;
+use crate::{
+    AttachmentSegment, ChatSegment, Conversation, Event, EventData, Formatting, HangoutEventType,
+    MembershipChangeType, ParticipantId,
+};
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// A slice of a longer list.
+#[derive(Serialize)]
+pub(super) struct Page<T> {
+    pub total: usize,
+    pub offset: usize,
+    pub items: Vec<T>,
+}
+
+#[derive(Serialize)]
+pub(super) struct ConversationView<'a> {
+    id: &'a str,
+    name: String,
+    group: bool,
+    participants: Vec<ParticipantView<'a>>,
+    messages: usize,
+    events: usize,
+    first: Option<&'a DateTime<Utc>>,
+    last: Option<&'a DateTime<Utc>>,
+    sort_timestamp: &'a DateTime<Utc>,
+}
+
+impl<'a> ConversationView<'a> {
+    pub fn new(conversation: &'a Conversation) -> Self {
+        let mut participants: Vec<_> = conversation
+            .participants
+            .keys()
+            .map(|id| ParticipantView::new(conversation, id))
+            .collect();
+        participants.sort_by(|a, b| a.name.cmp(&b.name).then(a.gaia_id.cmp(b.gaia_id)));
+
+        Self {
+            id: &conversation.conversation_id,
+            name: conversation.display_name(),
+            group: conversation.is_group(),
+            participants,
+            messages: conversation
+                .events
+                .iter()
+                .filter(|event| event.data.is_chat_message())
+                .count(),
+            events: conversation.events.len(),
+            first: conversation.events.first().map(|event| &event.timestamp),
+            last: conversation.events.last().map(|event| &event.timestamp),
+            sort_timestamp: &conversation.sort_timestamp,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ParticipantView<'a> {
+    gaia_id: &'a str,
+    name: Option<&'a str>,
+}
+
+impl<'a> ParticipantView<'a> {
+    fn new(conversation: &'a Conversation, id: &'a ParticipantId) -> Self {
+        Self {
+            gaia_id: &id.gaia_id,
+            name: conversation.participant_name(id).map(String::as_str),
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub(super) struct EventView<'a> {
+    /// Position of the event in its conversation.
+    index: usize,
+    id: &'a str,
+    timestamp: &'a DateTime<Utc>,
+    sender_gaia_id: &'a str,
+    sender_name: Option<&'a str>,
+    #[serde(flatten)]
+    body: Body<'a>,
+}
+
+impl<'a> EventView<'a> {
+    pub fn new(
+        conversation: &'a Conversation,
+        index: usize,
+        media: &HashMap<String, MediaRef>,
+    ) -> Self {
+        let event: &Event = &conversation.events[index];
+        Self {
+            index,
+            id: &event.id,
+            timestamp: &event.timestamp,
+            sender_gaia_id: &event.sender.gaia_id,
+            sender_name: conversation
+                .participant_name(&event.sender)
+                .map(String::as_str),
+            body: Body::new(conversation, &event.data, media),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Body<'a> {
+    ChatMessage {
+        segments: Vec<Segment<'a>>,
+        attachments: Vec<Attachment<'a>>,
+    },
+    HangoutEvent {
+        call: &'static str,
+        duration_secs: Option<u64>,
+        media_type: Option<&'static str>,
+        participants: Vec<ParticipantView<'a>>,
+    },
+    MembershipChange {
+        change: &'static str,
+        participants: Vec<ParticipantView<'a>>,
+    },
+    ConversationRename {
+        old_name: &'a str,
+        new_name: &'a str,
+    },
+}
+
+impl<'a> Body<'a> {
+    fn new(
+        conversation: &'a Conversation,
+        data: &'a EventData,
+        media: &HashMap<String, MediaRef>,
+    ) -> Self {
+        let participants = |ids: &'a [ParticipantId]| {
+            ids.iter()
+                .map(|id| ParticipantView::new(conversation, id))
+                .collect()
+        };
+
+        match data {
+            EventData::ChatMessage(message) => Self::ChatMessage {
+                segments: message.contents.iter().map(Segment::new).collect(),
+                attachments: message
+                    .attachments
+                    .iter()
+                    .map(|attachment| Attachment::new(attachment, media))
+                    .collect(),
+            },
+            EventData::HangoutEvent(hangout) => {
+                let (call, duration_secs) = match hangout.typ {
+                    HangoutEventType::Start => ("start", None),
+                    HangoutEventType::End { duration } => ("end", Some(duration)),
+                };
+                Self::HangoutEvent {
+                    call,
+                    duration_secs,
+                    media_type: hangout.media_type.as_ref().map(media_type_str),
+                    participants: participants(&hangout.participants),
+                }
+            }
+            EventData::MembershipChange(change) => Self::MembershipChange {
+                change: match change.typ {
+                    MembershipChangeType::Join => "join",
+                    MembershipChangeType::Leave => "leave",
+                },
+                participants: participants(&change.participants),
+            },
+            EventData::ConversationRename(rename) => Self::ConversationRename {
+                old_name: &rename.old,
+                new_name: &rename.new,
+            },
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Segment<'a> {
+    text: &'a str,
+    link: Option<&'a str>,
+    #[serde(flatten)]
+    format: &'a Formatting,
+}
+
+impl<'a> Segment<'a> {
+    fn new(segment: &'a ChatSegment) -> Self {
+        let link = match segment {
+            ChatSegment::Link { target, .. } => Some(target.as_str()),
+            _ => None,
+        };
+        Self {
+            text: segment.text(),
+            link,
+            format: segment.formatting(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Attachment<'a> {
+    id: &'a str,
+    #[serde(rename = "type")]
+    typ: &'static str,
+    url: Option<&'a str>,
+    name: Option<&'a str>,
+    /// Path of the local file served by the viewer, if resolved.
+    media: Option<String>,
+    mime_type: Option<String>,
+}
+
+impl<'a> Attachment<'a> {
+    fn new(attachment: &'a AttachmentSegment, media: &HashMap<String, MediaRef>) -> Self {
+        let item = &attachment.item;
+        let (typ, name) = if item.photo.is_some() {
+            ("photo", None)
+        } else if let Some(place) = &item.place {
+            ("place", place.name.as_deref())
+        } else if let Some(thing) = &item.thing {
+            ("thing", thing.name.as_deref())
+        } else {
+            ("unknown", None)
+        };
+        let mime_type = media.get(&attachment.id).map(|file| file.mime_type.clone());
+
+        Self {
+            id: &attachment.id,
+            typ,
+            url: item.url(),
+            name,
+            media: mime_type
+                .as_ref()
+                .map(|_| format!("/media/{}", percent_encode(&attachment.id))),
+            mime_type,
+        }
+    }
+}
@@ -0,0 +1,414 @@
+//! Local web viewer.
+//!
+//! A [`Viewer`] holds a [`Hangouts`] loaded once and answers requests for a bundled
+//! single-page UI, a JSON API and the local media files of the Takeout. Requests are answered
+//! by [`Viewer::handle`] without touching the network, so the API can be exercised by an
+//! in-process client, while [`Server`] serves a viewer over HTTP on localhost only.
+//!
+//! # API
+//!
+//! | Request                                         | Response                               |
+//! |-------------------------------------------------|----------------------------------------|
+//! | `GET /api/conversations`                        | All conversations, most recent first.  |
+//! | `GET /api/conversations/{id}`                   | A single conversation.                 |
+//! | `GET /api/conversations/{id}/events`            | A page of events, in time order.       |
+//! | `GET /api/search?q=...`                         | A page of matching chat messages, most recent first. |
+//! | `GET /media/{attachment id}`                    | The local file of an attachment.       |
+//!
+//! Pages are selected with the `offset` and `limit` query parameters, the limit defaulting
+//! to [`DEFAULT_LIMIT`] and capped at [`MAX_LIMIT`]. Without an offset, the events endpoint
+//! returns the last page. Search is case-insensitive and can be restricted to one
+//! conversation with the `conversation` parameter. Events are described like the records of
+//! [`jsonl`], with the text of chat messages split into formatted `segments`.
+//!
+//! [`jsonl`]: crate::export::jsonl
+
+mod api;
+
+use self::api::{ConversationView, EventView, Page};
+use crate::media::{MediaRef, MediaResolver};
+use crate::util::percent_decode;
+use crate::{Conversation, Hangouts};
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+/// Number of events or search results returned when no limit is given.
+pub const DEFAULT_LIMIT: usize = 100;
+/// Largest number of events or search results returned at once.
+pub const MAX_LIMIT: usize = 1000;
+
+const INDEX_HTML: &str = include_str!("index.html");
+
+/// Response to a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    /// HTTP status code.
+    pub status: u16,
+    /// MIME type of the body.
+    pub content_type: String,
+    pub body: Body,
+}
+
+/// Body of a [`Response`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Body {
+    /// Bytes held in memory.
+    Bytes(Vec<u8>),
+    /// Contents of a local file, to be streamed.
+    File(PathBuf),
+}
+
+impl Response {
+    #[inline]
+    fn json<T: Serialize>(value: &T) -> Self {
+        match serde_json::to_vec(value) {
+            Ok(bytes) => Self {
+                status: 200,
+                content_type: "application/json".to_owned(),
+                body: Body::Bytes(bytes),
+            },
+            Err(err) => Self::error(500, &err.to_string()),
+        }
+    }
+
+    #[inline]
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "application/json".to_owned(),
+            body: Body::Bytes(
+                serde_json::json!({ "error": message })
+                    .to_string()
+                    .into_bytes(),
+            ),
+        }
+    }
+
+    /// Read the body into memory.
+    pub fn bytes(&self) -> io::Result<Vec<u8>> {
+        match &self.body {
+            Body::Bytes(bytes) => Ok(bytes.clone()),
+            Body::File(path) => std::fs::read(path),
+        }
+    }
+}
+
+/// Conversations answering the requests of the web viewer.
+#[derive(Debug, Clone)]
+pub struct Viewer {
+    hangouts: Hangouts,
+    /// Indices of the conversations, most recent first.
+    order: Vec<usize>,
+    /// Indices of the conversations by ID.
+    by_id: HashMap<String, usize>,
+    /// Local files of attachments by attachment ID.
+    media: HashMap<String, MediaRef>,
+}
+
+impl Viewer {
+    /// Create a viewer of `hangouts`, serving the media files matched by `resolver`.
+    ///
+    /// The events of every conversation are sorted by time.
+    pub fn new(mut hangouts: Hangouts, resolver: Option<&MediaResolver>) -> Self {
+        for conversation in &mut hangouts.conversations {
+            conversation.sort_events_by_time();
+        }
+
+        let mut order: Vec<_> = (0..hangouts.conversations.len()).collect();
+        order.sort_by_key(|&i| Reverse(hangouts.conversations[i].sort_timestamp));
+        let by_id = hangouts
+            .conversations
+            .iter()
+            .enumerate()
+            .map(|(i, conversation)| (conversation.conversation_id.clone(), i))
+            .collect();
+        let media = resolver
+            .map(|resolver| resolver.resolve_all(&hangouts).resolved)
+            .unwrap_or_default();
+
+        Self {
+            hangouts,
+            order,
+            by_id,
+            media,
+        }
+    }
+
+    /// Get the viewed conversations.
+    #[inline]
+    pub fn hangouts(&self) -> &Hangouts {
+        &self.hangouts
+    }
+
+    /// Answer a request for `url`, the path and query of the requested URL.
+    pub fn handle(&self, method: &str, url: &str) -> Response {
+        if method != "GET" && method != "HEAD" {
+            return Response::error(405, "method not allowed");
+        }
+
+        let (path, query) = url.split_once('?').unwrap_or((url, ""));
+        let query = Query::parse(query);
+        let segments: Vec<String> = path
+            .trim_start_matches('/')
+            .split('/')
+            .map(percent_decode)
+            .collect();
+        let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+        let result = match segments.as_slice() {
+            [""] | ["index.html"] => Ok(Response {
+                status: 200,
+                content_type: "text/html; charset=utf-8".to_owned(),
+                body: Body::Bytes(INDEX_HTML.as_bytes().to_vec()),
+            }),
+            ["api", "conversations"] => Ok(self.conversations()),
+            ["api", "conversations", id] => self
+                .conversation(id)
+                .map(|conversation| Response::json(&ConversationView::new(conversation))),
+            ["api", "conversations", id, "events"] => self.events(id, &query),
+            ["api", "search"] => self.search(&query),
+            ["media", id] => self.media(id),
+            _ => Err(Response::error(404, "not found")),
+        };
+        result.unwrap_or_else(|response| response)
+    }
+
+    fn conversation(&self, id: &str) -> Result<&Conversation, Response> {
+        self.by_id
+            .get(id)
+            .map(|&i| &self.hangouts.conversations[i])
+            .ok_or_else(|| Response::error(404, "no such conversation"))
+    }
+
+    fn conversations(&self) -> Response {
+        let conversations: Vec<_> = self
+            .order
+            .iter()
+            .map(|&i| ConversationView::new(&self.hangouts.conversations[i]))
+            .collect();
+        Response::json(&conversations)
+    }
+
+    fn events(&self, id: &str, query: &Query) -> Result<Response, Response> {
+        let conversation = self.conversation(id)?;
+        let total = conversation.events.len();
+        let limit = query.limit()?;
+        let offset = query
+            .usize("offset")?
+            .unwrap_or_else(|| total.saturating_sub(limit))
+            .min(total);
+
+        let items = (offset..(offset + limit).min(total))
+            .map(|i| EventView::new(conversation, i, &self.media))
+            .collect();
+        Ok(Response::json(&Page {
+            total,
+            offset,
+            items,
+        }))
+    }
+
+    fn search(&self, query: &Query) -> Result<Response, Response> {
+        let needle = match query.get("q") {
+            Some(q) if !q.trim().is_empty() => q.to_lowercase(),
+            _ => return Err(Response::error(400, "missing search text")),
+        };
+        let conversations = match query.get("conversation") {
+            Some(id) => vec![self.conversation(id)?],
+            None => self.hangouts.conversations.iter().collect(),
+        };
+        let limit = query.limit()?;
+        let offset = query.usize("offset")?.unwrap_or(0);
+
+        let mut hits: Vec<(&Conversation, usize)> = Vec::new();
+        for conversation in conversations {
+            for (i, event) in conversation.events.iter().enumerate() {
+                let matches = event.data.as_chat_message().is_some_and(|message| {
+                    message.contents_as_str().to_lowercase().contains(&needle)
+                });
+                if matches {
+                    hits.push((conversation, i));
+                }
+            }
+        }
+        hits.sort_by_key(|(conversation, i)| Reverse(conversation.events[*i].timestamp));
+
+        #[derive(Serialize)]
+        struct Hit<'a> {
+            conversation_id: &'a str,
+            conversation_name: String,
+            event: EventView<'a>,
+        }
+
+        let items = hits
+            .iter()
+            .skip(offset)
+            .take(limit)
+            .map(|(conversation, i)| Hit {
+                conversation_id: &conversation.conversation_id,
+                conversation_name: conversation.display_name(),
+                event: EventView::new(conversation, *i, &self.media),
+            })
+            .collect();
+        Ok(Response::json(&Page {
+            total: hits.len(),
+            offset,
+            items,
+        }))
+    }
+
+    fn media(&self, id: &str) -> Result<Response, Response> {
+        let file = self
+            .media
+            .get(id)
+            .ok_or_else(|| Response::error(404, "no local file for attachment"))?;
+        Ok(Response {
+            status: 200,
+            content_type: file.mime_type.clone(),
+            body: Body::File(file.path.clone()),
+        })
+    }
+}
+
+/// Decoded query string parameters.
+struct Query(HashMap<String, String>);
+
+impl Query {
+    fn parse(query: &str) -> Self {
+        let decode = |s: &str| percent_decode(&s.replace('+', " "));
+        Self(
+            query
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                .map(|pair| {
+                    let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                    (decode(key), decode(value))
+                })
+                .collect(),
+        )
+    }
+
+    #[inline]
+    fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    fn usize(&self, key: &str) -> Result<Option<usize>, Response> {
+        match self.get(key) {
+            Some(value) => value.parse().map(Some).map_err(|_| {
+                Response::error(400, &format!("{} must be a non-negative integer", key))
+            }),
+            None => Ok(None),
+        }
+    }
+
+    #[inline]
+    fn limit(&self) -> Result<usize, Response> {
+        Ok(self
+            .usize("limit")?
+            .unwrap_or(DEFAULT_LIMIT)
+            .clamp(1, MAX_LIMIT))
+    }
+}
+
+/// HTTP server of a [`Viewer`], bound to the loopback interface.
+pub struct Server {
+    server: tiny_http::Server,
+}
+
+impl Server {
+    /// Listen on `port` of 127.0.0.1, or on a free port if `port` is 0.
+    pub fn bind(port: u16) -> io::Result<Self> {
+        let server = tiny_http::Server::http(SocketAddr::from((Ipv4Addr::LOCALHOST, port)))
+            .map_err(io::Error::other)?;
+        Ok(Self { server })
+    }
+
+    /// Get the address the server listens on.
+    #[inline]
+    pub fn addr(&self) -> SocketAddr {
+        self.server
+            .server_addr()
+            .to_ip()
+            .expect("server listens on an IP address")
+    }
+
+    /// Answer requests with `viewer` until the server is shut down from another thread.
+    ///
+    /// Requests naming a host other than localhost in their `Host` header are refused, so
+    /// that other websites cannot read the viewer through DNS rebinding.
+    pub fn run(&self, viewer: &Viewer) {
+        for request in self.server.incoming_requests() {
+            let response = if is_local_host(&request) {
+                viewer.handle(request.method().as_str(), request.url())
+            } else {
+                Response::error(403, "forbidden host")
+            };
+            // Errors only concern the connection of this request.
+            let _ = respond(request, response);
+        }
+    }
+
+    /// Stop [`Server::run`].
+    #[inline]
+    pub fn unblock(&self) {
+        self.server.unblock();
+    }
+}
+
+fn is_local_host(request: &tiny_http::Request) -> bool {
+    let host = match request
+        .headers()
+        .iter()
+        .find(|header| header.field.equiv("Host"))
+    {
+        Some(header) => header.value.as_str(),
+        None => return true,
+    };
+    let name = match host.rsplit_once(':') {
+        Some((name, port)) if !port.contains(']') => name,
+        _ => host,
+    };
+    matches!(name, "localhost" | "127.0.0.1" | "[::1]")
+}
+
+fn respond(request: tiny_http::Request, response: Response) -> io::Result<()> {
+    let header = |field: &str, value: &str| {
+        tiny_http::Header::from_bytes(field.as_bytes(), value.as_bytes()).expect("valid header")
+    };
+    let headers = vec![
+        header("Content-Type", &response.content_type),
+        header("X-Content-Type-Options", "nosniff"),
+        header("Cache-Control", "no-store"),
+    ];
+
+    match response.body {
+        Body::Bytes(bytes) => request.respond(tiny_http::Response::new(
+            response.status.into(),
+            headers,
+            &bytes[..],
+            Some(bytes.len()),
+            None,
+        )),
+        Body::File(path) => match File::open(&path) {
+            Ok(file) => {
+                let len = file.metadata().ok().map(|metadata| metadata.len() as usize);
+                request.respond(tiny_http::Response::new(
+                    response.status.into(),
+                    headers,
+                    file,
+                    len,
+                    None,
+                ))
+            }
+            Err(_) => respond(request, Response::error(404, "media file is missing")),
+        },
+    }
+}
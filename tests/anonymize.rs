@@ -0,0 +1,216 @@
+use std::collections::HashMap;
+
+use hangouts_rs::raw::anonymize::{AnonymizeOptions, Anonymizer, TextMode, REDACTED_URL};
+use hangouts_rs::raw::generate::{generate, GeneratorOptions};
+use hangouts_rs::raw::{self, ChatSegment, EventData};
+
+fn generated() -> raw::Hangouts {
+    let mut hangouts = generate(&GeneratorOptions {
+        seed: 5,
+        conversations: 5,
+        participants: 6,
+        events: 300,
+        ..GeneratorOptions::default()
+    });
+    for conversation in &mut hangouts.conversations {
+        for data in &mut conversation.header.details.participant_data {
+            data.domain_id = Some("example.org".to_owned());
+            data.phone_number = Some(raw::PhoneNumber {
+                e164: Some("+15550100".to_owned()),
+                i18n_data: None,
+            });
+        }
+    }
+    hangouts
+}
+
+fn anonymized(seed: u64, text: TextMode) -> raw::Hangouts {
+    let mut hangouts = generated();
+    Anonymizer::new(AnonymizeOptions { seed, text }).anonymize(&mut hangouts);
+    hangouts
+}
+
+/// Chat messages of `hangouts` in order, with the ID of their conversation.
+fn messages(hangouts: &raw::Hangouts) -> Vec<(&str, &raw::ChatSegments)> {
+    hangouts
+        .conversations
+        .iter()
+        .flat_map(|conversation| {
+            conversation
+                .events
+                .iter()
+                .filter_map(move |event| match &event.data {
+                    EventData::ChatMessage {
+                        message_content, ..
+                    } => Some((
+                        conversation.header.conversation_id.id.as_str(),
+                        message_content,
+                    )),
+                    _ => None,
+                })
+        })
+        .collect()
+}
+
+#[test]
+fn removes_contact_data() {
+    let anonymized = anonymized(1, TextMode::Scramble);
+    for conversation in &anonymized.conversations {
+        for data in &conversation.header.details.participant_data {
+            assert_eq!(data.domain_id, None);
+            assert_eq!(data.phone_number, None);
+            if let Some(name) = &data.fallback_name {
+                assert!(name.starts_with("Person "), "{}", name);
+            }
+        }
+    }
+
+    let (mut photos, mut places, mut things) = (0, 0, 0);
+    for (_, message) in messages(&anonymized) {
+        for attachment in &message.attachments {
+            let item = &attachment.embed_item;
+            if let Some(photo) = &item.plus_photo {
+                photos += 1;
+                assert_eq!(photo.url, REDACTED_URL);
+                assert_eq!(photo.original_content_url, REDACTED_URL);
+                assert_eq!(photo.thumbnail.image_url, REDACTED_URL);
+            }
+            if let Some(place) = &item.place_v2 {
+                places += 1;
+                assert_eq!(place.url, REDACTED_URL);
+                assert_eq!(place.geo.geo_coordinates_v2.latitude, 0.0);
+                assert_eq!(place.geo.geo_coordinates_v2.longitude, 0.0);
+                let address = &place.address.postal_address_v2;
+                assert_eq!(
+                    [
+                        &address.name,
+                        &address.street_address,
+                        &address.address_locality,
+                        &address.address_region,
+                        &address.address_country,
+                        &address.postal_code,
+                    ],
+                    [&None; 6]
+                );
+                assert_eq!(place.representative_image.image_object_v2.url, REDACTED_URL);
+            }
+            if let Some(thing) = &item.thing_v2 {
+                things += 1;
+                assert_eq!(thing.url, REDACTED_URL);
+                assert_eq!(thing.representative_image.image_object_v2.url, REDACTED_URL);
+            }
+        }
+    }
+    assert!(photos > 0 && places > 0 && things > 0);
+}
+
+#[test]
+fn keeps_message_structure() {
+    let original = generated();
+    for text in [TextMode::Scramble, TextMode::Hash] {
+        let anonymized = anonymized(1, text);
+        let pairs: Vec<_> = messages(&original)
+            .into_iter()
+            .zip(messages(&anonymized))
+            .collect();
+        assert_eq!(pairs.len(), messages(&original).len());
+
+        let mut changed = 0;
+        for ((_, before), (_, after)) in pairs {
+            assert_eq!(before.segments.len(), after.segments.len());
+            assert_eq!(before.attachments.len(), after.attachments.len());
+            for (before, after) in before.segments.iter().zip(&after.segments) {
+                match (before, after) {
+                    (
+                        ChatSegment::Text {
+                            text: a,
+                            formatting: fa,
+                        },
+                        ChatSegment::Text {
+                            text: b,
+                            formatting: fb,
+                        },
+                    ) => {
+                        assert_eq!(a.chars().count(), b.chars().count());
+                        assert_eq!(fa, fb);
+                        changed += usize::from(a != b);
+                    }
+                    (
+                        ChatSegment::Link { text: a, .. },
+                        ChatSegment::Link {
+                            text: b, link_data, ..
+                        },
+                    ) => {
+                        assert_eq!(a.chars().count(), b.chars().count());
+                        assert_eq!(link_data.link_target, REDACTED_URL);
+                    }
+                    (ChatSegment::LineBreak { .. }, ChatSegment::LineBreak { .. }) => {}
+                    (before, after) => panic!("{:?} became {:?}", before, after),
+                }
+            }
+        }
+        assert!(changed > 0);
+    }
+}
+
+#[test]
+fn gives_ids_consistent_pseudonyms() {
+    let original = generated();
+    let anonymized = anonymized(1, TextMode::Scramble);
+
+    // Participants are shared between conversations.
+    let mut pseudonyms: HashMap<&str, &str> = HashMap::new();
+    let mut shared = 0;
+    for (before, after) in original.conversations.iter().zip(&anonymized.conversations) {
+        assert_ne!(
+            before.header.conversation_id.id,
+            after.header.conversation_id.id
+        );
+        let participants = before
+            .header
+            .details
+            .participant_data
+            .iter()
+            .zip(&after.header.details.participant_data);
+        for (before, after) in participants {
+            assert_ne!(before.id.gaia_id, after.id.gaia_id);
+            assert_eq!(before.id.gaia_id.len(), after.id.gaia_id.len());
+            if let Some(earlier) = pseudonyms.insert(&before.id.gaia_id, &after.id.gaia_id) {
+                assert_eq!(earlier, after.id.gaia_id);
+                shared += 1;
+            }
+        }
+    }
+    assert!(shared > 0);
+    // Different IDs keep different pseudonyms.
+    let mut distinct: Vec<_> = pseudonyms.values().collect();
+    distinct.sort();
+    distinct.dedup();
+    assert_eq!(distinct.len(), pseudonyms.len());
+
+    // Senders use the same pseudonyms as the participant list.
+    for conversation in &anonymized.conversations {
+        let ids: Vec<_> = conversation
+            .header
+            .details
+            .participant_data
+            .iter()
+            .map(|data| &data.id)
+            .collect();
+        for event in &conversation.events {
+            assert!(ids.contains(&&event.header.sender_id));
+        }
+    }
+}
+
+#[test]
+fn depends_on_seed() {
+    for text in [TextMode::Scramble, TextMode::Hash] {
+        assert_eq!(anonymized(1, text), anonymized(1, text));
+        assert_ne!(anonymized(1, text), anonymized(2, text));
+    }
+    assert_ne!(
+        anonymized(1, TextMode::Scramble),
+        anonymized(1, TextMode::Hash)
+    );
+}
@@ -0,0 +1,125 @@
+use hangouts_rs::builder::{
+    default_start, participant_id, ChatMessageBuilder, ConversationBuilder, EventBuilder,
+};
+use hangouts_rs::chrono::Duration;
+use hangouts_rs::{ChatSegment, LinkSharingStatus, MediaType, NotificationLevel, View};
+
+fn group() -> ConversationBuilder {
+    ConversationBuilder::new("group")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .message("1", "Hi")
+        .event(EventBuilder::join("1", &["3"]))
+        .event(EventBuilder::message(
+            "3",
+            ChatMessageBuilder::new()
+                .bold("Thanks")
+                .line_break()
+                .link("example.com", "https://example.com/"),
+        ))
+        .event(EventBuilder::rename("2", "", "Friends"))
+        .event(EventBuilder::leave("2", &["2"]))
+}
+
+#[test]
+fn fills_in_ids_and_times() {
+    let conversation = group().build();
+
+    let ids: Vec<&str> = conversation.events.iter().map(|e| e.id.as_str()).collect();
+    assert_eq!(ids, ["event-1", "event-2", "event-3", "event-4", "event-5"]);
+    for (i, event) in conversation.events.iter().enumerate() {
+        assert_eq!(
+            event.timestamp,
+            default_start() + Duration::minutes(i as i64)
+        );
+        assert_eq!(event.version, event.timestamp.timestamp_micros() as u64);
+    }
+    assert_eq!(
+        conversation.sort_timestamp,
+        conversation.events[4].timestamp
+    );
+    assert_eq!(
+        conversation.self_state.invitation.inviter,
+        participant_id("1")
+    );
+    assert_eq!(
+        conversation.self_state.invitation.timestamp,
+        default_start()
+    );
+}
+
+#[test]
+fn tracks_participants_and_name() {
+    let conversation = group().build();
+
+    assert_eq!(conversation.name.as_deref(), Some("Friends"));
+    assert_eq!(conversation.participants.len(), 3);
+    assert_eq!(
+        conversation.current_participants,
+        [participant_id("1"), participant_id("3")]
+    );
+    assert_eq!(
+        conversation.participant_name(&participant_id("2")).unwrap(),
+        "Bob"
+    );
+    assert_eq!(conversation.participant_name(&participant_id("3")), None);
+    for participant in conversation.participants.values() {
+        assert_eq!(
+            participant.read_state.timestamp,
+            conversation.events[4].timestamp
+        );
+    }
+
+    let message = conversation.events[2].data.as_chat_message().unwrap();
+    assert_eq!(message.contents_as_str(), "Thanks\nexample.com");
+    assert!(message.contents[0].formatting().bold);
+    assert!(matches!(
+        &message.contents[2],
+        ChatSegment::Link { target, .. } if target == "https://example.com/"
+    ));
+}
+
+#[test]
+fn keeps_explicit_values() {
+    let start = default_start() + Duration::days(3);
+    let conversation = ConversationBuilder::new("calls")
+        .participant("1", "Alice")
+        .start(start)
+        .interval(Duration::seconds(10))
+        .notification_level(NotificationLevel::Quiet)
+        .views(vec![View::Archived])
+        .link_sharing(LinkSharingStatus::On)
+        .event(EventBuilder::call_start("1", MediaType::AudioVideo).id("start"))
+        .event(
+            EventBuilder::call_end("1", 600, &["1", "2"])
+                .timestamp(start + Duration::minutes(10))
+                .version(7),
+        )
+        .event(
+            EventBuilder::message(
+                "2",
+                ChatMessageBuilder::new().photo("p1", "https://p/1.jpg"),
+            )
+            .advances_sort_timestamp(false),
+        )
+        .build();
+
+    assert_eq!(conversation.events[0].id, "start");
+    assert_eq!(conversation.events[1].version, 7);
+    assert_eq!(
+        conversation.events[2].timestamp,
+        start + Duration::minutes(10) + Duration::seconds(10)
+    );
+    assert_eq!(conversation.sort_timestamp, start + Duration::minutes(10));
+    assert!(conversation.self_state.notification_level.is_quiet());
+    assert!(conversation.self_state.views[0].is_archived());
+    assert!(conversation.group_link_sharing_status.is_on());
+
+    let calls = conversation.calls();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].duration, 600);
+    assert_eq!(calls[0].participants.len(), 2);
+
+    let message = conversation.events[2].data.as_chat_message().unwrap();
+    assert_eq!(message.attachments[0].item.url(), Some("https://p/1.jpg"));
+}
@@ -0,0 +1,350 @@
+use std::cmp::Reverse;
+use std::convert::TryInto;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+use hangouts_rs::export::{ical, jsonl};
+use hangouts_rs::raw::generate::{generate, GeneratorOptions};
+use hangouts_rs::stats::Stats;
+use hangouts_rs::{raw, Hangouts};
+
+/// Temporary directory removed on drop.
+struct TempDir(PathBuf);
+
+impl TempDir {
+    fn new(name: &str) -> Self {
+        let dir =
+            std::env::temp_dir().join(format!("hangouts-cli-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    fn path(&self, path: &str) -> PathBuf {
+        self.0.join(path)
+    }
+
+    /// Write `raw` as Hangouts.json at `path`.
+    fn write(&self, path: &str, raw: &raw::Hangouts) -> PathBuf {
+        let path = self.path(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, serde_json::to_vec(raw).unwrap()).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn generated() -> raw::Hangouts {
+    generate(&GeneratorOptions {
+        seed: 3,
+        conversations: 4,
+        participants: 5,
+        events: 40,
+        ..GeneratorOptions::default()
+    })
+}
+
+/// The generated conversations as the tool loads them, with events sorted by time.
+fn loaded() -> Hangouts {
+    let mut hangouts: Hangouts = generated().try_into().unwrap();
+    for conversation in &mut hangouts.conversations {
+        conversation.sort_events_by_time();
+    }
+    hangouts
+}
+
+fn run<P: AsRef<Path>>(args: &[&str], input: P) -> Output {
+    let (command, rest) = args.split_first().unwrap();
+    Command::new(env!("CARGO_BIN_EXE_hangouts"))
+        .arg("--utc")
+        .arg(command)
+        .arg(input.as_ref())
+        .args(rest)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: Output) -> String {
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).unwrap()
+}
+
+fn stderr(output: Output) -> String {
+    assert!(!output.status.success());
+    String::from_utf8(output.stderr).unwrap()
+}
+
+#[test]
+fn lists_conversations() {
+    let dir = TempDir::new("list");
+    let json = dir.write("Hangouts.json", &generated());
+    let hangouts = loaded();
+
+    let list = stdout(run(&["list"], &json));
+    let lines: Vec<_> = list.lines().collect();
+    assert_eq!(lines.len(), 3 * hangouts.conversations.len());
+
+    let mut conversations: Vec<_> = hangouts.conversations.iter().collect();
+    conversations.sort_by_key(|c| Reverse(c.sort_timestamp));
+    for (conversation, lines) in conversations.iter().zip(lines.chunks(3)) {
+        assert_eq!(
+            lines[0],
+            format!(
+                "{}  {}",
+                conversation.conversation_id,
+                conversation.display_name()
+            )
+        );
+        let messages = conversation
+            .events
+            .iter()
+            .filter(|event| event.data.is_chat_message())
+            .count();
+        assert!(lines[1].starts_with(&format!("    {} messages, ", messages)));
+        for participant in conversation.participants.values() {
+            assert!(lines[2].contains(participant.fallback_name.as_deref().unwrap()));
+        }
+    }
+
+    // Extracted Takeout directories and zip archives give the same result.
+    let takeout = TempDir::new("list-takeout");
+    takeout.write("Takeout/Hangouts/Hangouts.json", &generated());
+    assert_eq!(stdout(run(&["list"], &takeout.0)), list);
+
+    let archive = dir.path("takeout.zip");
+    let mut zip = zip::ZipWriter::new(File::create(&archive).unwrap());
+    zip.start_file(
+        "Takeout/Hangouts/Hangouts.json",
+        zip::write::SimpleFileOptions::default(),
+    )
+    .unwrap();
+    zip.write_all(&fs::read(&json).unwrap()).unwrap();
+    zip.finish().unwrap();
+    assert_eq!(stdout(run(&["list"], &archive)), list);
+
+    let empty = TempDir::new("list-empty");
+    assert_eq!(
+        stderr(run(&["list"], &empty.0)),
+        format!("error: no Hangouts.json in {}\n", empty.0.display())
+    );
+}
+
+#[test]
+fn shows_and_searches_messages() {
+    let dir = TempDir::new("show");
+    let json = dir.write("Hangouts.json", &generated());
+    let hangouts = loaded();
+    let conversation = &hangouts.conversations[0];
+    let event = conversation
+        .events
+        .iter()
+        .find(|event| event.data.is_chat_message())
+        .unwrap();
+    let text = event.data.as_chat_message().unwrap().contents_as_str();
+    let first_line = text.lines().next().unwrap();
+    let time = event.timestamp.format("%Y-%m-%d %H:%M:%S").to_string();
+
+    let show = stdout(run(&["show", &conversation.conversation_id], &json));
+    assert!(show.starts_with(&format!("# {}\n\n", conversation.display_name())));
+    assert!(show.contains(&format!("{}  ", time)));
+    assert!(show.contains(first_line));
+    assert_eq!(
+        stderr(run(&["show", "no such conversation"], &json)),
+        "error: no conversation matches \"no such conversation\"\n"
+    );
+
+    let search = stdout(run(
+        &[
+            "search",
+            &first_line.to_uppercase(),
+            "--conversation",
+            &conversation.conversation_id,
+        ],
+        &json,
+    ));
+    assert!(search.contains(&format!("{}  [{}]  ", time, conversation.display_name())));
+    for line in search.lines() {
+        assert!(line.to_lowercase().contains(&first_line.to_lowercase()));
+        assert!(line.contains(&format!("[{}]", conversation.display_name())));
+    }
+
+    let sensitive = stdout(run(
+        &["search", &first_line.to_uppercase(), "--case-sensitive"],
+        &json,
+    ));
+    if first_line.to_uppercase() != first_line {
+        assert!(sensitive.is_empty());
+    }
+}
+
+#[test]
+fn prints_stats() {
+    let dir = TempDir::new("stats");
+    let json = dir.write("Hangouts.json", &generated());
+    let hangouts = loaded();
+    let stats = Stats::for_hangouts(&hangouts);
+
+    let text = stdout(run(&["stats"], &json));
+    assert!(text.starts_with(&format!(
+        "All conversations ({})\n  Messages:        {} ({} words, {} characters)\n",
+        hangouts.conversations.len(),
+        stats.totals.messages,
+        stats.totals.words,
+        stats.totals.characters
+    )));
+
+    let value: serde_json::Value =
+        serde_json::from_str(&stdout(run(&["stats", "--json", "--top", "3"], &json))).unwrap();
+    assert_eq!(
+        value["stats"]["totals"]["messages"],
+        serde_json::json!(stats.totals.messages)
+    );
+    assert_eq!(value["stats"]["calls"], serde_json::json!(stats.calls));
+    assert!(value["top_words"].as_array().unwrap().len() <= 3);
+
+    let conversation = &hangouts.conversations[1];
+    let one = stdout(run(&["stats", "-c", &conversation.conversation_id], &json));
+    assert!(one.starts_with(&format!("{}\n", conversation.display_name())));
+}
+
+#[test]
+fn exports_formats() {
+    let dir = TempDir::new("export");
+    let json = dir.write("Hangouts.json", &generated());
+    let hangouts = loaded();
+
+    let mut expected = Vec::new();
+    jsonl::write_hangouts(&mut expected, &hangouts).unwrap();
+    let output = run(&["export", "--format", "jsonl"], &json);
+    assert_eq!(stdout(output), String::from_utf8(expected).unwrap());
+
+    let mut expected = Vec::new();
+    ical::write_calendar(&mut expected, &hangouts, &Default::default()).unwrap();
+    let path = dir.path("calls.ics");
+    stdout(run(
+        &["export", "-f", "ical", "-o", path.to_str().unwrap()],
+        &json,
+    ));
+    assert_eq!(fs::read(&path).unwrap(), expected);
+
+    let rooms = dir.path("matrix");
+    let conversation = &hangouts.conversations[2];
+    stdout(run(
+        &[
+            "export",
+            "-f",
+            "matrix",
+            "-o",
+            rooms.to_str().unwrap(),
+            "-c",
+            &conversation.conversation_id,
+        ],
+        &json,
+    ));
+    let files: Vec<_> = fs::read_dir(&rooms)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name())
+        .collect();
+    assert_eq!(
+        files,
+        [format!("{}.json", conversation.conversation_id).as_str()]
+    );
+
+    assert_eq!(
+        stderr(run(&["export", "-f", "mbox"], &json)),
+        "error: this format needs an output directory (--output)\n"
+    );
+}
+
+#[test]
+fn validates_input() {
+    let dir = TempDir::new("validate");
+    let json = dir.write("Hangouts.json", &generated());
+    assert_eq!(
+        stdout(run(&["validate"], &json)),
+        "4 conversations, 160 events\n"
+    );
+
+    let mut broken = generated();
+    let event = &mut broken.conversations[0].events[0];
+    event.header.sender_id.gaia_id = "stranger".to_owned();
+    event.header.sender_id.chat_id = "stranger".to_owned();
+    let json = dir.write("broken.json", &broken);
+
+    let output = run(&["validate", "--json"], &json);
+    assert_eq!(
+        String::from_utf8_lossy(&output.stderr),
+        "error: 1 problem(s) found\n"
+    );
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["issues"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        report["issues"][0]["event_id"],
+        serde_json::json!(broken.conversations[0].events[0].header.event_id)
+    );
+    assert!(!output.status.success());
+}
+
+#[test]
+fn names_exported_files_safely() {
+    let dir = TempDir::new("export-ids");
+    let mut raw = generated();
+    for (conversation, id) in
+        raw.conversations
+            .iter_mut()
+            .zip(["../../x", "..\\..\\x", "Chat", "chat"])
+    {
+        conversation.header.conversation_id.id = id.to_owned();
+        for event in &mut conversation.events {
+            event.header.conversation_id.id = id.to_owned();
+        }
+    }
+    let json = dir.write("in/Hangouts.json", &raw);
+
+    let files = |path: &Path| {
+        let mut files: Vec<_> = fs::read_dir(path)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        files
+    };
+
+    let rooms = dir.path("out/matrix");
+    stdout(run(
+        &["export", "-f", "matrix", "-o", rooms.to_str().unwrap()],
+        &json,
+    ));
+    assert_eq!(
+        files(&rooms),
+        ["Chat.json", "_.._x-2.json", "_.._x.json", "chat-2.json"]
+    );
+
+    let archive = dir.path("out/xmpp");
+    stdout(run(
+        &[
+            "export",
+            "-f",
+            "xmpp",
+            "-o",
+            archive.to_str().unwrap(),
+            "--owner",
+            "../../owner",
+        ],
+        &json,
+    ));
+    assert!(files(&archive).contains(&"_.._owner.xml".to_owned()));
+    assert_eq!(files(&dir.path("out")), ["matrix", "xmpp"]);
+    assert_eq!(files(&dir.0), ["in", "out"]);
+}
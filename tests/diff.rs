@@ -0,0 +1,153 @@
+use hangouts_rs::builder::{
+    default_start, participant_id, ChatMessageBuilder, ConversationBuilder, EventBuilder,
+};
+use hangouts_rs::chrono::Duration;
+use hangouts_rs::diff::{Change, ConversationSummary, Diff, EventField};
+use hangouts_rs::{Hangouts, LinkSharingStatus, NotificationLevel};
+
+fn friends() -> ConversationBuilder {
+    ConversationBuilder::new("friends")
+        .name("Friends")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .message("1", "call me at 555-0100")
+        .message("2", "ok")
+}
+
+fn hangouts(conversations: Vec<ConversationBuilder>) -> Hangouts {
+    Hangouts {
+        conversations: conversations
+            .into_iter()
+            .map(ConversationBuilder::build)
+            .collect(),
+    }
+}
+
+#[test]
+fn equal_exports_have_no_differences() {
+    let old = hangouts(vec![friends()]);
+    let diff = Diff::new(&old, &old.clone());
+    assert!(diff.is_empty());
+    assert_eq!(diff.to_string(), "");
+}
+
+#[test]
+fn lists_added_and_removed_conversations() {
+    let old = hangouts(vec![friends()]);
+    let new = hangouts(vec![ConversationBuilder::new("work")
+        .name("Work")
+        .message("1", "hi")]);
+
+    let diff = Diff::new(&old, &new);
+    assert_eq!(
+        diff.added,
+        [ConversationSummary {
+            conversation_id: "work".to_owned(),
+            name: "Work".to_owned(),
+            events: 1,
+        }]
+    );
+    assert_eq!(diff.removed[0].conversation_id, "friends");
+    assert!(diff.changed.is_empty());
+    assert_eq!(
+        diff.to_string(),
+        "+ Work (work, 1 events)\n- Friends (friends, 2 events)\n"
+    );
+}
+
+#[test]
+fn lists_changes_within_conversations() {
+    let old = hangouts(vec![friends().message("1", "bye")]);
+    let new = hangouts(vec![ConversationBuilder::new("friends")
+        .name("Besties")
+        .participant("1", "Alice")
+        .participant("2", "Robert")
+        .participant("3", "Carol")
+        .notification_level(NotificationLevel::Quiet)
+        .link_sharing(LinkSharingStatus::On)
+        .event(EventBuilder::message(
+            "1",
+            ChatMessageBuilder::new().text("call me at [PHONE]"),
+        ))
+        .message("2", "ok")
+        .message("3", "hello")]);
+
+    let diff = Diff::new(&old, &new);
+    assert!(diff.added.is_empty() && diff.removed.is_empty());
+    let changes = &diff.changed[0].changes;
+    assert_eq!(
+        changes[..6],
+        [
+            Change::Renamed {
+                old: Some("Friends".to_owned()),
+                new: Some("Besties".to_owned()),
+            },
+            Change::NotificationLevel {
+                old: NotificationLevel::Ring,
+                new: NotificationLevel::Quiet,
+            },
+            Change::LinkSharing {
+                old: LinkSharingStatus::Off,
+                new: LinkSharingStatus::On,
+            },
+            Change::ParticipantRenamed {
+                participant: participant_id("2"),
+                old: Some("Bob".to_owned()),
+                new: Some("Robert".to_owned()),
+            },
+            Change::ParticipantAdded {
+                participant: participant_id("3"),
+                name: Some("Carol".to_owned()),
+            },
+            Change::EventModified {
+                event_id: "event-1".to_owned(),
+                timestamp: new.conversations[0].events[0].timestamp,
+                fields: vec![EventField::Data],
+                old_text: Some("call me at 555-0100".to_owned()),
+                new_text: Some("call me at [PHONE]".to_owned()),
+            },
+        ]
+    );
+    // The third event was replaced: same ID, different sender and text.
+    assert!(matches!(
+        &changes[6],
+        Change::EventModified { event_id, fields, .. }
+            if event_id == "event-3" && fields == &[EventField::Sender, EventField::Data]
+    ));
+    assert_eq!(changes.len(), 7);
+
+    let text = diff.to_string();
+    assert!(text.starts_with("~ Besties (friends)\n    name: \"Friends\" -> \"Besties\"\n"));
+    assert!(text.contains("    participant 2: \"Bob\" -> \"Robert\"\n"));
+    assert!(text.contains("        - \"call me at 555-0100\"\n        + \"call me at [PHONE]\"\n"));
+}
+
+#[test]
+fn lists_added_and_removed_events_by_time() {
+    let old = hangouts(vec![friends()]);
+    let mut new = old.clone();
+    let events = &mut new.conversations[0].events;
+    let removed = events.remove(0);
+    let late = default_start() + Duration::days(1);
+    events.push(
+        EventBuilder::message("1", ChatMessageBuilder::new().text("new"))
+            .id("late")
+            .timestamp(late)
+            .build(),
+    );
+
+    let diff = Diff::new(&old, &new);
+    assert_eq!(
+        diff.changed[0].changes,
+        [
+            Change::EventRemoved {
+                event_id: removed.id,
+                timestamp: removed.timestamp,
+            },
+            Change::EventAdded {
+                event_id: "late".to_owned(),
+                timestamp: late,
+            },
+        ]
+    );
+}
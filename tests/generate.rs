@@ -0,0 +1,164 @@
+use std::collections::HashSet;
+use std::convert::TryInto;
+
+use hangouts_rs::raw::generate::{generate, GeneratorOptions};
+use hangouts_rs::raw::{self, ChatSegment, EventData, EventOtr, EventType, HangoutEvent};
+use hangouts_rs::Hangouts;
+
+fn options() -> GeneratorOptions {
+    GeneratorOptions {
+        seed: 7,
+        conversations: 12,
+        participants: 10,
+        events: 300,
+        ..GeneratorOptions::default()
+    }
+}
+
+#[test]
+fn same_seed_same_data() {
+    assert_eq!(generate(&options()), generate(&options()));
+
+    let other = GeneratorOptions {
+        seed: 8,
+        ..options()
+    };
+    assert_ne!(generate(&options()), generate(&other));
+}
+
+#[test]
+fn sizes_follow_options() {
+    let hangouts = generate(&options());
+    assert_eq!(hangouts.conversations.len(), 12);
+    for conversation in &hangouts.conversations {
+        assert_eq!(conversation.events.len(), 300);
+    }
+
+    let tiny = generate(&GeneratorOptions {
+        conversations: 3,
+        participants: 0,
+        events: 1,
+        ..GeneratorOptions::default()
+    });
+    assert_eq!(tiny.conversations.len(), 3);
+    let hangouts: Hangouts = tiny.try_into().unwrap();
+    assert!(hangouts
+        .conversations
+        .iter()
+        .all(|conversation| conversation.participants.len() == 2));
+}
+
+#[test]
+fn ids_are_unique_and_times_increase() {
+    let hangouts = generate(&options());
+    let mut conversation_ids = HashSet::new();
+    let mut event_ids = HashSet::new();
+    for conversation in &hangouts.conversations {
+        assert!(conversation_ids.insert(conversation.header.conversation_id.id.clone()));
+        let mut last = 0;
+        for event in &conversation.events {
+            assert!(event_ids.insert(event.header.event_id.clone()));
+            let timestamp: i64 = event.header.timestamp.parse().unwrap();
+            assert!(timestamp > last);
+            last = timestamp;
+        }
+    }
+}
+
+#[test]
+fn covers_all_kinds_of_events() {
+    let hangouts = generate(&options());
+    let events: Vec<&raw::Event> = hangouts
+        .conversations
+        .iter()
+        .flat_map(|conversation| &conversation.events)
+        .collect();
+    let has = |f: &dyn Fn(&raw::Event) -> bool| events.iter().any(|event| f(event));
+
+    for typ in [
+        EventType::RegularChatMessage,
+        EventType::Sms,
+        EventType::HangoutEvent,
+        EventType::AddUser,
+        EventType::RemoveUser,
+        EventType::RenameConversation,
+    ] {
+        assert!(has(&|event| event.event_type == typ), "{:?}", typ);
+    }
+    assert!(has(
+        &|event| event.header.event_otr == EventOtr::OffTheRecord
+    ));
+    assert!(has(&|event| matches!(
+        event.data,
+        EventData::HangoutEvent {
+            data: HangoutEvent::EndHangout { .. },
+            ..
+        }
+    )));
+
+    let segments = |f: &dyn Fn(&ChatSegment) -> bool| {
+        has(&|event| match &event.data {
+            EventData::ChatMessage {
+                message_content, ..
+            } => message_content.segments.iter().any(f),
+            _ => false,
+        })
+    };
+    assert!(segments(&|segment| matches!(
+        segment,
+        ChatSegment::Link { link_data, .. } if link_data.link_target.starts_with("https://www.google.com/url?q=")
+    )));
+    assert!(segments(&|segment| matches!(
+        segment,
+        ChatSegment::LineBreak { .. }
+    )));
+    assert!(segments(&|segment| matches!(
+        segment,
+        ChatSegment::Text { formatting, .. } if formatting.bold
+    )));
+
+    let attachments = |f: &dyn Fn(&raw::EmbedItem) -> bool| {
+        has(&|event| match &event.data {
+            EventData::ChatMessage {
+                message_content, ..
+            } => message_content
+                .attachments
+                .iter()
+                .any(|attachment| f(&attachment.embed_item)),
+            _ => false,
+        })
+    };
+    assert!(attachments(&|item| item.plus_photo.is_some()));
+    assert!(attachments(&|item| item.place_v2.is_some()));
+    assert!(attachments(&|item| item.thing_v2.is_some()));
+}
+
+#[test]
+fn converts() {
+    let generated = generate(&options());
+    let hangouts: Hangouts = generated.clone().try_into().unwrap();
+    assert_eq!(hangouts.conversations.len(), generated.conversations.len());
+
+    let groups = hangouts
+        .conversations
+        .iter()
+        .filter(|conversation| conversation.is_group())
+        .count();
+    assert!(groups > 0 && groups < hangouts.conversations.len());
+    assert!(hangouts.conversations.iter().any(|conversation| {
+        conversation
+            .participants
+            .values()
+            .any(|participant| participant.phone_number.is_some())
+    }));
+
+    for (conversation, raw) in hangouts.conversations.iter().zip(&generated.conversations) {
+        assert_eq!(conversation.events.len(), raw.events.len());
+        for id in &conversation.current_participants {
+            assert!(conversation.participants.contains_key(id));
+        }
+        for event in &conversation.events {
+            assert!(conversation.participants.contains_key(&event.sender));
+        }
+    }
+}
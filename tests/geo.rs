@@ -0,0 +1,243 @@
+use hangouts_rs::builder::{default_start, ChatMessageBuilder, ConversationBuilder, EventBuilder};
+use hangouts_rs::chrono::Duration;
+use hangouts_rs::export::geo::{self, shared_places};
+use hangouts_rs::{
+    Address, AttachmentSegment, EmbedItem, Geo, Hangouts, PlaceV2, RepresentativeImage,
+};
+
+fn place(id: &str, name: Option<&str>, address: Address, geo: Geo) -> AttachmentSegment {
+    AttachmentSegment {
+        id: id.to_owned(),
+        item: EmbedItem {
+            id: Some(id.to_owned()),
+            photo: None,
+            place: Some(PlaceV2 {
+                url: format!("https://maps.google.com/?cid={}", id),
+                name: name.map(str::to_owned),
+                address,
+                geo,
+                representative_image: RepresentativeImage {
+                    id: String::new(),
+                    url: String::new(),
+                    width: None,
+                    height: None,
+                },
+                place_id: None,
+                cluster_id: None,
+                reference_id: None,
+            }),
+            thing: None,
+        },
+    }
+}
+
+fn no_address() -> Address {
+    Address {
+        name: None,
+        street: None,
+        locality: None,
+        region: None,
+        country: None,
+        postal_code: None,
+    }
+}
+
+fn hangouts() -> Hangouts {
+    let cafe = place(
+        "cafe",
+        Some("Café & <Bar>"),
+        Address {
+            street: Some("1 Main St".to_owned()),
+            locality: Some("Springfield".to_owned()),
+            region: Some("IL".to_owned()),
+            postal_code: Some("62701".to_owned()),
+            country: Some("US".to_owned()),
+            ..no_address()
+        },
+        Geo {
+            latitude: 39.8,
+            longitude: -89.6,
+        },
+    );
+    let nowhere = place(
+        "nowhere",
+        None,
+        no_address(),
+        Geo {
+            latitude: 0.0,
+            longitude: 0.0,
+        },
+    );
+    let park = place(
+        "park",
+        None,
+        Address {
+            name: Some("Park".to_owned()),
+            ..no_address()
+        },
+        Geo {
+            latitude: 48.1,
+            longitude: 11.5,
+        },
+    );
+
+    Hangouts {
+        conversations: vec![
+            ConversationBuilder::new("trip")
+                .name("Trip")
+                .participant("1", "Alice")
+                .participant("2", "Bob")
+                .message("1", "Where?")
+                .event(EventBuilder::message(
+                    "2",
+                    ChatMessageBuilder::new().text("Here").attachment(cafe),
+                ))
+                .event(EventBuilder::message(
+                    "1",
+                    ChatMessageBuilder::new().attachment(nowhere),
+                ))
+                .build(),
+            ConversationBuilder::new("walks")
+                .participant("1", "Alice")
+                .participant("3", "Carol")
+                .event(
+                    EventBuilder::message("3", ChatMessageBuilder::new().attachment(park))
+                        .timestamp(default_start() + Duration::days(1)),
+                )
+                .build(),
+            ConversationBuilder::new("empty")
+                .participant("1", "Alice")
+                .message("1", "No places here")
+                .build(),
+        ],
+    }
+}
+
+#[test]
+fn collects_places() {
+    let hangouts = hangouts();
+    let places = shared_places(&hangouts);
+    let ids: Vec<_> = places.iter().map(|shared| shared.attachment_id).collect();
+    assert_eq!(ids, ["cafe", "nowhere", "park"]);
+
+    assert_eq!(places[0].conversation_id, "trip");
+    assert_eq!(places[0].conversation_name, "Trip");
+    assert_eq!(places[0].sender.gaia_id, "2");
+    assert_eq!(places[0].sender_name, Some("Bob"));
+    assert_eq!(places[0].timestamp, default_start() + Duration::minutes(1));
+    assert_eq!(places[0].name(), Some("Café & <Bar>"));
+    assert_eq!(places[1].name(), None);
+    // Without a name, the place is named after its address.
+    assert_eq!(places[2].name(), Some("Park"));
+    assert_eq!(places[2].conversation_name, "Alice, Carol");
+}
+
+#[test]
+fn writes_geojson() {
+    let mut written = Vec::new();
+    geo::write_geojson(&mut written, &hangouts()).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&written).unwrap();
+
+    assert_eq!(value["type"], "FeatureCollection");
+    let features = value["features"].as_array().unwrap();
+    assert_eq!(features.len(), 3);
+    assert_eq!(
+        features[0],
+        serde_json::json!({
+            "type": "Feature",
+            "id": "cafe",
+            "geometry": {"type": "Point", "coordinates": [-89.6, 39.8]},
+            "properties": {
+                "name": "Café & <Bar>",
+                "address": "1 Main St, Springfield, IL 62701, US",
+                "url": "https://maps.google.com/?cid=cafe",
+                "sender_gaia_id": "2",
+                "sender_name": "Bob",
+                "timestamp": "2015-01-01T00:01:00Z",
+                "conversation_id": "trip",
+                "conversation_name": "Trip",
+            },
+        })
+    );
+    assert_eq!(
+        features[1]["geometry"]["coordinates"],
+        serde_json::json!([0.0, 0.0])
+    );
+    assert_eq!(features[1]["properties"]["name"], serde_json::Value::Null);
+    assert_eq!(
+        features[1]["properties"]["address"],
+        serde_json::Value::Null
+    );
+    assert_eq!(
+        features[2]["properties"]["timestamp"],
+        "2015-01-02T00:00:00Z"
+    );
+}
+
+#[test]
+fn writes_kml() {
+    let mut written = Vec::new();
+    geo::write_kml(&mut written, &hangouts()).unwrap();
+    assert_eq!(
+        String::from_utf8(written).unwrap(),
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<kml xmlns="http://www.opengis.net/kml/2.2">
+  <Document>
+    <name>Hangouts places</name>
+    <Folder>
+      <name>Trip</name>
+      <Placemark id="cafe">
+        <name>Café &amp; &lt;Bar&gt;</name>
+        <address>1 Main St, Springfield, IL 62701, US</address>
+        <TimeStamp><when>2015-01-01T00:01:00Z</when></TimeStamp>
+        <ExtendedData>
+          <Data name="url"><value>https://maps.google.com/?cid=cafe</value></Data>
+          <Data name="sender_gaia_id"><value>2</value></Data>
+          <Data name="sender_name"><value>Bob</value></Data>
+          <Data name="conversation_id"><value>trip</value></Data>
+          <Data name="conversation_name"><value>Trip</value></Data>
+        </ExtendedData>
+        <Point><coordinates>-89.6,39.8</coordinates></Point>
+      </Placemark>
+      <Placemark id="nowhere">
+        <TimeStamp><when>2015-01-01T00:02:00Z</when></TimeStamp>
+        <ExtendedData>
+          <Data name="url"><value>https://maps.google.com/?cid=nowhere</value></Data>
+          <Data name="sender_gaia_id"><value>1</value></Data>
+          <Data name="sender_name"><value>Alice</value></Data>
+          <Data name="conversation_id"><value>trip</value></Data>
+          <Data name="conversation_name"><value>Trip</value></Data>
+        </ExtendedData>
+        <Point><coordinates>0,0</coordinates></Point>
+      </Placemark>
+    </Folder>
+    <Folder>
+      <name>Alice, Carol</name>
+      <Placemark id="park">
+        <name>Park</name>
+        <TimeStamp><when>2015-01-02T00:00:00Z</when></TimeStamp>
+        <ExtendedData>
+          <Data name="url"><value>https://maps.google.com/?cid=park</value></Data>
+          <Data name="sender_gaia_id"><value>3</value></Data>
+          <Data name="sender_name"><value>Carol</value></Data>
+          <Data name="conversation_id"><value>walks</value></Data>
+          <Data name="conversation_name"><value>Alice, Carol</value></Data>
+        </ExtendedData>
+        <Point><coordinates>11.5,48.1</coordinates></Point>
+      </Placemark>
+    </Folder>
+  </Document>
+</kml>
+"#
+    );
+
+    let mut written = Vec::new();
+    geo::write_kml(
+        &mut written,
+        &Hangouts {
+            conversations: Vec::new(),
+        },
+    )
+    .unwrap();
+    assert!(!String::from_utf8(written).unwrap().contains("<Folder>"));
+}
@@ -0,0 +1,130 @@
+use hangouts_rs::builder::{default_start, ConversationBuilder, EventBuilder};
+use hangouts_rs::chrono::Duration;
+use hangouts_rs::export::ical::{self, IcalOptions};
+use hangouts_rs::{Conversation, Hangouts, MediaType};
+
+fn conversation() -> Conversation {
+    let start = default_start();
+    ConversationBuilder::new("calls")
+        .name("Family, \"home\"")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .event(EventBuilder::call_start("1", MediaType::AudioVideo).timestamp(start))
+        .event(
+            EventBuilder::call_end("1", 590, &["1", "2"]).timestamp(start + Duration::minutes(10)),
+        )
+        // A call whose end was never recorded, then one whose start was never recorded.
+        .event(
+            EventBuilder::call_start("2", MediaType::Audio).timestamp(start + Duration::hours(1)),
+        )
+        .event(
+            EventBuilder::call_end("2", 60, &["2"])
+                .id("orphan")
+                .timestamp(start + Duration::hours(5)),
+        )
+        .build()
+}
+
+fn calendar(conversation: Conversation) -> String {
+    let hangouts = Hangouts {
+        conversations: vec![conversation],
+    };
+    let mut written = Vec::new();
+    ical::write_calendar(&mut written, &hangouts, &IcalOptions::default()).unwrap();
+    String::from_utf8(written).unwrap()
+}
+
+#[test]
+fn pairs_call_events() {
+    let conversation = conversation();
+    let calls = conversation.calls();
+    assert_eq!(calls.len(), 2);
+
+    assert_eq!(calls[0].start_event.unwrap().id, "event-1");
+    assert_eq!(calls[0].start, default_start());
+    assert_eq!(calls[0].end, default_start() + Duration::minutes(10));
+    assert_eq!(calls[0].media_type, Some(&MediaType::AudioVideo));
+    assert_eq!(calls[0].participants.len(), 2);
+
+    assert_eq!(calls[1].start_event, None);
+    assert_eq!(calls[1].end_event.id, "orphan");
+    assert_eq!(
+        calls[1].start,
+        default_start() + Duration::hours(5) - Duration::minutes(1)
+    );
+    assert_eq!(calls[1].media_type, None);
+}
+
+#[test]
+fn survives_absurd_durations() {
+    let end = default_start() + Duration::hours(1);
+    let conversation = ConversationBuilder::new("calls")
+        .participant("1", "Alice")
+        .event(EventBuilder::call_start("1", MediaType::Audio).timestamp(default_start()))
+        .event(EventBuilder::call_end("1", u64::MAX, &["1"]).timestamp(end))
+        .event(EventBuilder::call_end("1", i64::MAX as u64, &["1"]).timestamp(end))
+        // Representable as a duration, but too far before the end.
+        .event(EventBuilder::call_end("1", 1 << 50, &["1"]).timestamp(end))
+        .build();
+
+    let calls = conversation.calls();
+    assert_eq!(calls.len(), 3);
+    for call in &calls {
+        assert_eq!(call.start_event, None);
+        assert_eq!(call.start, end);
+        assert_eq!(call.end, end);
+    }
+    assert_eq!(calls[0].duration, u64::MAX);
+
+    let calendar = calendar(conversation);
+    assert_eq!(calendar.matches("BEGIN:VEVENT").count(), 3);
+    assert!(calendar.contains("DTSTART:20150101T010000Z\r\nDTEND:20150101T010000Z"));
+}
+
+#[test]
+fn writes_calendar() {
+    assert_eq!(
+        calendar(conversation()),
+        "BEGIN:VCALENDAR\r\n\
+         VERSION:2.0\r\n\
+         PRODID:-//hangouts-rs//Hangouts calls//EN\r\n\
+         CALSCALE:GREGORIAN\r\n\
+         BEGIN:VEVENT\r\n\
+         UID:event-2@hangouts.invalid\r\n\
+         DTSTAMP:20150101T001000Z\r\n\
+         DTSTART:20150101T000000Z\r\n\
+         DTEND:20150101T001000Z\r\n\
+         SUMMARY:Family\\, \"home\"\r\n\
+         DESCRIPTION:Hangouts video call\\, 9m 50s\r\n\
+         CATEGORIES:Hangouts,Video\r\n\
+         ORGANIZER;CN=\"Alice\":mailto:1@hangouts.invalid\r\n\
+         ATTENDEE;CN=\"Alice\":mailto:1@hangouts.invalid\r\n\
+         ATTENDEE;CN=\"Bob\":mailto:2@hangouts.invalid\r\n\
+         END:VEVENT\r\n\
+         BEGIN:VEVENT\r\n\
+         UID:orphan@hangouts.invalid\r\n\
+         DTSTAMP:20150101T050000Z\r\n\
+         DTSTART:20150101T045900Z\r\n\
+         DTEND:20150101T050000Z\r\n\
+         SUMMARY:Family\\, \"home\"\r\n\
+         DESCRIPTION:Hangouts call\\, 1m 0s\r\n\
+         CATEGORIES:Hangouts\r\n\
+         ORGANIZER;CN=\"Bob\":mailto:2@hangouts.invalid\r\n\
+         ATTENDEE;CN=\"Bob\":mailto:2@hangouts.invalid\r\n\
+         END:VEVENT\r\n\
+         END:VCALENDAR\r\n"
+    );
+}
+
+#[test]
+fn folds_long_lines() {
+    let mut conversation = conversation();
+    conversation.name = Some("Ü".repeat(60));
+    let calendar = calendar(conversation);
+
+    for line in calendar.split("\r\n") {
+        assert!(line.len() <= 75, "{}", line);
+    }
+    let unfolded = calendar.replace("\r\n ", "");
+    assert!(unfolded.contains(&format!("\r\nSUMMARY:{}\r\n", "Ü".repeat(60))));
+}
@@ -0,0 +1,132 @@
+use std::collections::HashSet;
+use std::convert::TryInto;
+
+use hangouts_rs::export::{geo, jsonl, vcard};
+use hangouts_rs::raw::anonymize::{AnonymizeOptions, Anonymizer, TextMode};
+use hangouts_rs::raw::generate::{generate, GeneratorOptions};
+use hangouts_rs::raw::{self, stream};
+use hangouts_rs::Hangouts;
+
+fn options() -> GeneratorOptions {
+    GeneratorOptions {
+        seed: 3,
+        conversations: 8,
+        participants: 8,
+        events: 150,
+        ..GeneratorOptions::default()
+    }
+}
+
+#[test]
+fn roundtrips_through_json() {
+    let generated = generate(&options());
+    let json = serde_json::to_string(&generated).unwrap();
+
+    let parsed: raw::Hangouts = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, generated);
+
+    let mut streamed = Vec::new();
+    stream::for_each_conversation(json.as_bytes(), |conversation| {
+        streamed.push(conversation);
+        Ok::<_, std::convert::Infallible>(())
+    })
+    .unwrap();
+    assert_eq!(streamed, generated.conversations);
+}
+
+#[test]
+fn exports_events_as_json_lines() {
+    let generated = generate(&options());
+    let events: usize = generated
+        .conversations
+        .iter()
+        .map(|conversation| conversation.events.len())
+        .sum();
+    let json = serde_json::to_vec(&generated).unwrap();
+
+    let mut streamed = Vec::new();
+    jsonl::write_stream(json.as_slice(), &mut streamed).unwrap();
+
+    let hangouts: Hangouts = generated.try_into().unwrap();
+    let mut written = Vec::new();
+    jsonl::write_hangouts(&mut written, &hangouts).unwrap();
+    assert_eq!(written, streamed);
+
+    let records: Vec<serde_json::Value> = String::from_utf8(written)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), events);
+    for kind in [
+        "chat_message",
+        "hangout_event",
+        "membership_change",
+        "conversation_rename",
+    ] {
+        assert!(
+            records.iter().any(|record| record["kind"] == kind),
+            "{}",
+            kind
+        );
+    }
+}
+
+#[test]
+fn exports_places_and_contacts() {
+    let hangouts: Hangouts = generate(&options()).try_into().unwrap();
+
+    let places = geo::shared_places(&hangouts);
+    assert!(!places.is_empty());
+    let mut written = Vec::new();
+    geo::write_geojson(&mut written, &hangouts).unwrap();
+    let collection: serde_json::Value = serde_json::from_slice(&written).unwrap();
+    assert_eq!(
+        collection["features"].as_array().unwrap().len(),
+        places.len()
+    );
+
+    let people: HashSet<_> = hangouts
+        .conversations
+        .iter()
+        .flat_map(|conversation| conversation.participants.keys())
+        .collect();
+    let contacts = vcard::contacts(&hangouts);
+    assert_eq!(contacts.len(), people.len());
+    assert!(contacts.iter().any(|contact| contact.tel().is_some()));
+    let mut written = Vec::new();
+    vcard::write_vcards(&mut written, &hangouts).unwrap();
+    let written = String::from_utf8(written).unwrap();
+    assert_eq!(written.matches("BEGIN:VCARD").count(), contacts.len());
+}
+
+#[test]
+fn anonymizes() {
+    let generated = generate(&options());
+    let names: Vec<String> = generated
+        .conversations
+        .iter()
+        .flat_map(|conversation| &conversation.header.details.participant_data)
+        .filter_map(|data| data.fallback_name.clone())
+        .collect();
+
+    for text in [TextMode::Scramble, TextMode::Hash] {
+        let anonymize = || {
+            let mut hangouts = generated.clone();
+            Anonymizer::new(AnonymizeOptions { seed: 42, text }).anonymize(&mut hangouts);
+            hangouts
+        };
+        let anonymized = anonymize();
+        assert_eq!(anonymized, anonymize());
+        assert_ne!(anonymized, generated);
+
+        let json = serde_json::to_string(&anonymized).unwrap();
+        for name in &names {
+            assert!(!json.contains(name.as_str()), "{}", name);
+        }
+        assert!(!json.contains("example.org"));
+
+        let hangouts: Hangouts = anonymized.try_into().unwrap();
+        assert_eq!(hangouts.conversations.len(), generated.conversations.len());
+    }
+}
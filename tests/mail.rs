@@ -0,0 +1,162 @@
+use hangouts_rs::builder::{ChatMessageBuilder, ConversationBuilder, EventBuilder};
+use hangouts_rs::export::mail::{self, MailBatch, MailOptions};
+use hangouts_rs::{Conversation, Hangouts};
+
+fn conversation() -> Conversation {
+    ConversationBuilder::new("chat")
+        .name("Plans")
+        .participant("1", "Alice")
+        .participant("2", "Bob \"B\"")
+        .message("1", "Hi")
+        .message("2", "From here on\nFrom there")
+        .event(EventBuilder::message(
+            "1",
+            ChatMessageBuilder::new()
+                .text("Look")
+                .photo("photo-1", "https://example.com/photo.jpg"),
+        ))
+        .build()
+}
+
+fn mbox(conversation: &Conversation, options: &MailOptions) -> String {
+    let mut written = Vec::new();
+    mail::write_mbox(&mut written, conversation, options).unwrap();
+    String::from_utf8(written).unwrap()
+}
+
+/// Decode the RFC 2047 encoded-words of a header value.
+fn decode_words(value: &str) -> String {
+    let mut bytes = Vec::new();
+    for word in value.split_whitespace() {
+        let encoded = word
+            .strip_prefix("=?utf-8?B?")
+            .and_then(|word| word.strip_suffix("?="))
+            .unwrap();
+        let mut bits = 0u32;
+        let mut count = 0;
+        for c in encoded.bytes().filter(|c| *c != b'=') {
+            let value = match c {
+                b'A'..=b'Z' => c - b'A',
+                b'a'..=b'z' => c - b'a' + 26,
+                b'0'..=b'9' => c - b'0' + 52,
+                b'+' => 62,
+                _ => 63,
+            };
+            bits = bits << 6 | u32::from(value);
+            count += 6;
+            if count >= 8 {
+                count -= 8;
+                bytes.push((bits >> count) as u8);
+            }
+        }
+    }
+    String::from_utf8(bytes).unwrap()
+}
+
+#[test]
+fn writes_threaded_messages() {
+    let written = mbox(&conversation(), &MailOptions::default());
+    let messages: Vec<&str> = written.split("\n\nFrom ").collect();
+    assert_eq!(messages.len(), 3);
+
+    assert!(messages[0].starts_with("From 1@hangouts.invalid "));
+    assert!(messages[0].contains("\nFrom: \"Alice\" <1@hangouts.invalid>\n"));
+    assert!(messages[0].contains("\nTo: \"Bob \\\"B\\\"\" <2@hangouts.invalid>\n"));
+    assert!(messages[0].contains("\nSubject: Plans\n"));
+    assert!(messages[0].contains("\nDate: Thu, 1 Jan 2015 00:00:00 +0000\n"));
+    assert!(messages[0].contains("\nMessage-ID: <event-1@hangouts.invalid>\n"));
+    assert!(!messages[0].contains("In-Reply-To"));
+
+    assert!(messages[1].contains("\nIn-Reply-To: <event-1@hangouts.invalid>\n"));
+    assert!(messages[1].contains("\nReferences: <event-1@hangouts.invalid>\n"));
+    assert!(messages[1].ends_with("\n\n>From here on\n>From there"));
+    assert!(messages[2].contains("\nIn-Reply-To: <event-2@hangouts.invalid>\n"));
+    assert!(messages[2]
+        .contains("\nReferences: <event-1@hangouts.invalid> <event-2@hangouts.invalid>\n"));
+    assert!(messages[2].ends_with("\n\nLook\nPhoto: https://example.com/photo.jpg\n\n"));
+}
+
+#[test]
+fn batches_messages_by_day() {
+    let options = MailOptions {
+        batch: MailBatch::Day,
+        domain: "example.com".to_owned(),
+    };
+    let written = mbox(&conversation(), &options);
+
+    assert_eq!(written.matches("\nMessage-ID: ").count(), 1);
+    assert!(written.contains("\nMessage-ID: <chat.20150101@example.com>\n"));
+    assert!(written
+        .contains("\n[00:00:00] Alice: Hi\n[00:01:00] Bob \"B\": From here on\n>From there\n"));
+}
+
+#[test]
+fn splits_and_folds_long_subjects() {
+    let name = "Ünïcödé ".repeat(12);
+    let mut conversation = conversation();
+    conversation.name = Some(name.clone());
+    let written = mbox(&conversation, &MailOptions::default());
+
+    let message = written.split("\n\n").next().unwrap();
+    let start = message.find("\nSubject: ").unwrap() + 1;
+    let end = message[start..].find("\nDate: ").unwrap() + start;
+    let subject = &message[start..end];
+
+    let lines: Vec<&str> = subject.lines().collect();
+    assert!(lines.len() > 1);
+    for line in &lines[1..] {
+        assert!(line.starts_with(' '));
+    }
+    for line in &lines {
+        assert!(line.len() <= 78, "{}", line);
+    }
+    for word in subject["Subject:".len()..].split_whitespace() {
+        assert!(word.len() <= 75, "{}", word);
+    }
+    assert_eq!(decode_words(&subject["Subject:".len()..]), name);
+}
+
+#[test]
+fn folds_long_ascii_subjects() {
+    let name = "a long conversation name ".repeat(6);
+    let mut conversation = conversation();
+    conversation.name = Some(name.clone());
+    let written = mbox(&conversation, &MailOptions::default());
+
+    let start = written.find("\nSubject: ").unwrap() + 1;
+    let end = written[start..].find("\nDate: ").unwrap() + start;
+    let subject = &written[start..end];
+    assert!(subject.lines().count() > 1);
+    assert!(subject.lines().all(|line| line.len() <= 78));
+    assert_eq!(subject.replace('\n', ""), format!("Subject: {}", name));
+}
+
+#[test]
+fn writes_maildirs() {
+    let dir = std::env::temp_dir().join(format!("hangouts-mail-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let hangouts = Hangouts {
+        conversations: vec![conversation(), conversation()],
+    };
+    mail::export_maildirs(&dir, &hangouts, &MailOptions::default()).unwrap();
+
+    for folder in ["Plans", "Plans (2)"] {
+        let folder = dir.join(folder);
+        assert_eq!(std::fs::read_dir(folder.join("new")).unwrap().count(), 0);
+        assert_eq!(std::fs::read_dir(folder.join("tmp")).unwrap().count(), 0);
+        let mut names: Vec<String> = std::fs::read_dir(folder.join("cur"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "1420070400.event-1.hangouts:2,S",
+                "1420070460.event-2.hangouts:2,S",
+                "1420070520.event-3.hangouts:2,S",
+            ]
+        );
+    }
+    std::fs::remove_dir_all(&dir).unwrap();
+}
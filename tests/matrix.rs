@@ -0,0 +1,236 @@
+use std::collections::HashMap;
+
+use hangouts_rs::builder::{ChatMessageBuilder, ConversationBuilder, EventBuilder};
+use hangouts_rs::export::matrix::{self, MatrixContent, MatrixEvent, MatrixOptions, MatrixRoom};
+use hangouts_rs::{Conversation, MediaType};
+
+fn conversation() -> Conversation {
+    ConversationBuilder::new("room")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .participant("3", "Carol")
+        .event(EventBuilder::message(
+            "1",
+            ChatMessageBuilder::new()
+                .bold("Hi")
+                .text(" <all>")
+                .line_break()
+                .link("docs", "https://example.com/?a=1&b=2"),
+        ))
+        .event(EventBuilder::join("1", &["3"]))
+        .message("3", "Thanks")
+        .event(EventBuilder::rename("3", "", "Friends"))
+        .event(EventBuilder::call_start("2", MediaType::AudioVideo))
+        .event(EventBuilder::call_end("2", 125, &["2", "3"]))
+        .event(EventBuilder::leave("2", &["2"]))
+        .event(EventBuilder::join("2", &["2"]))
+        .event(EventBuilder::leave("1", &["3"]))
+        .build()
+}
+
+/// Replay `events` the way a homeserver applying the Matrix authorization rules to a public
+/// room would, with `joined` users already in the room. Returns the final membership of every user.
+fn replay(
+    joined: &[&str],
+    events: &[MatrixEvent],
+) -> Result<HashMap<String, &'static str>, String> {
+    let mut members: HashMap<String, &'static str> = joined
+        .iter()
+        .map(|user| (user.to_string(), "join"))
+        .collect();
+    for event in events {
+        let sender_joined = members.get(&event.sender) == Some(&"join");
+        match (&event.content, &event.state_key) {
+            (MatrixContent::Member { membership, .. }, Some(target)) => {
+                let target_membership = members.get(target).copied();
+                let allowed = match *membership {
+                    "join" => event.sender == *target,
+                    "invite" => sender_joined && target_membership != Some("join"),
+                    "leave" if event.sender == *target => target_membership.is_some(),
+                    "leave" => sender_joined && target_membership.is_some(),
+                    _ => false,
+                };
+                if !allowed {
+                    return Err(format!("rejected {:?}", event));
+                }
+                members.insert(target.clone(), membership);
+            }
+            _ if !sender_joined => return Err(format!("rejected {:?}", event)),
+            _ => {}
+        }
+    }
+    Ok(members)
+}
+
+#[test]
+fn converts_messages() {
+    let room = MatrixRoom::new(&conversation(), &MatrixOptions::default());
+
+    assert_eq!(room.conversation_id, "room");
+    assert_eq!(room.name, "Friends");
+    assert_eq!(
+        room.members,
+        [
+            "@hangouts_1:localhost",
+            "@hangouts_2:localhost",
+            "@hangouts_3:localhost"
+        ]
+    );
+
+    let event = &room.events[0];
+    assert_eq!(event.typ, "m.room.message");
+    assert_eq!(event.sender, "@hangouts_1:localhost");
+    assert_eq!(event.origin_server_ts, 1420070400000);
+    assert_eq!(event.txn_id, "event-1");
+    assert!(!event.is_state());
+    assert_eq!(
+        serde_json::to_value(event).unwrap(),
+        serde_json::json!({
+            "type": "m.room.message",
+            "sender": "@hangouts_1:localhost",
+            "origin_server_ts": 1420070400000i64,
+            "txn_id": "event-1",
+            "content": {
+                "msgtype": "m.text",
+                "body": "Hi <all>\ndocs",
+                "format": "org.matrix.custom.html",
+                "formatted_body":
+                    "<strong>Hi</strong> &lt;all&gt;<br><a href=\"https://example.com/?a=1&amp;b=2\">docs</a>",
+            },
+        })
+    );
+    assert_eq!(
+        event.request_path("!abc:localhost"),
+        "/_matrix/client/v3/rooms/%21abc%3Alocalhost/send/m.room.message/event-1\
+         ?user_id=%40hangouts_1%3Alocalhost&ts=1420070400000"
+    );
+}
+
+#[test]
+fn converts_state_events_and_calls() {
+    let room = MatrixRoom::new(&conversation(), &MatrixOptions::default());
+    let summary: Vec<(&str, &str, Option<&str>)> = room
+        .events
+        .iter()
+        .map(|event| (event.typ, event.sender.as_str(), event.state_key.as_deref()))
+        .collect();
+    assert_eq!(
+        summary,
+        [
+            ("m.room.message", "@hangouts_1:localhost", None),
+            (
+                "m.room.member",
+                "@hangouts_1:localhost",
+                Some("@hangouts_3:localhost")
+            ),
+            (
+                "m.room.member",
+                "@hangouts_3:localhost",
+                Some("@hangouts_3:localhost")
+            ),
+            ("m.room.message", "@hangouts_3:localhost", None),
+            ("m.room.name", "@hangouts_3:localhost", Some("")),
+            ("m.room.message", "@hangouts_2:localhost", None),
+            ("m.room.message", "@hangouts_2:localhost", None),
+            (
+                "m.room.member",
+                "@hangouts_2:localhost",
+                Some("@hangouts_2:localhost")
+            ),
+            (
+                "m.room.member",
+                "@hangouts_2:localhost",
+                Some("@hangouts_2:localhost")
+            ),
+            (
+                "m.room.member",
+                "@hangouts_1:localhost",
+                Some("@hangouts_3:localhost")
+            ),
+        ]
+    );
+
+    assert_eq!(
+        room.events[4].content,
+        MatrixContent::Name {
+            name: "Friends".to_owned()
+        }
+    );
+    assert_eq!(
+        room.events[4].request_path("!abc:localhost"),
+        "/_matrix/client/v3/rooms/%21abc%3Alocalhost/state/m.room.name/\
+         ?user_id=%40hangouts_3%3Alocalhost&ts=1420070580000"
+    );
+    assert_eq!(
+        room.events[6].content,
+        MatrixContent::Message {
+            msgtype: "m.notice",
+            body: "Call ended after 2m 5s".to_owned(),
+            format: None,
+            formatted_body: None,
+        }
+    );
+}
+
+#[test]
+fn invites_members_added_by_others() {
+    let room = MatrixRoom::new(&conversation(), &MatrixOptions::default());
+
+    let (invite, join) = (&room.events[1], &room.events[2]);
+    assert_eq!(invite.txn_id, "event-2.@hangouts_3:localhost.invite");
+    assert_eq!(
+        invite.content,
+        MatrixContent::Member {
+            membership: "invite",
+            displayname: Some("Carol".to_owned()),
+        }
+    );
+    assert_eq!(join.txn_id, "event-2.@hangouts_3:localhost");
+    assert_eq!(
+        join.content,
+        MatrixContent::Member {
+            membership: "join",
+            displayname: Some("Carol".to_owned()),
+        }
+    );
+    assert_eq!(
+        join.request_path("!abc:localhost"),
+        "/_matrix/client/v3/rooms/%21abc%3Alocalhost/state/m.room.member/\
+         %40hangouts_3%3Alocalhost?user_id=%40hangouts_3%3Alocalhost&ts=1420070460000"
+    );
+
+    let members = replay(
+        &["@hangouts_1:localhost", "@hangouts_2:localhost"],
+        &room.events,
+    )
+    .unwrap();
+    assert_eq!(members["@hangouts_1:localhost"], "join");
+    assert_eq!(members["@hangouts_2:localhost"], "join");
+    assert_eq!(members["@hangouts_3:localhost"], "leave");
+}
+
+#[test]
+fn uses_explicit_user_ids() {
+    let mut options = MatrixOptions {
+        server_name: "example.org".to_owned(),
+        ..MatrixOptions::default()
+    };
+    options.user_ids.insert(
+        hangouts_rs::builder::participant_id("1"),
+        "@alice:example.org".to_owned(),
+    );
+
+    let mut written = Vec::new();
+    matrix::write_room(&mut written, &conversation(), &options).unwrap();
+    let room: serde_json::Value = serde_json::from_slice(&written).unwrap();
+    assert_eq!(
+        room["members"],
+        serde_json::json!([
+            "@alice:example.org",
+            "@hangouts_2:example.org",
+            "@hangouts_3:example.org"
+        ])
+    );
+    assert_eq!(room["events"][1]["sender"], "@alice:example.org");
+    assert_eq!(room["events"][1]["content"]["membership"], "invite");
+}
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use hangouts_rs::builder::{participant_id, ChatMessageBuilder, ConversationBuilder, EventBuilder};
+use hangouts_rs::export::mattermost::{self, MattermostOptions};
+use hangouts_rs::Hangouts;
+
+fn hangouts() -> Hangouts {
+    let group = ConversationBuilder::new("Group-1")
+        .name("Friends")
+        .participant("1", "Alice Smith")
+        .participant("2", "Bob")
+        .participant("3", "Al")
+        .event(EventBuilder::message(
+            "1",
+            ChatMessageBuilder::new()
+                .bold("Hi")
+                .text(" all ")
+                .italics("there")
+                .line_break()
+                .text("a*b_c [d] <e>")
+                .link("see [this]", "https://example.com/a_(b)"),
+        ))
+        .event(EventBuilder::message(
+            "2",
+            ChatMessageBuilder::new()
+                .photo("photo-1", "https://example.com/1.jpg")
+                .photo("photo-2", "https://example.com/2.jpg"),
+        ))
+        .build();
+    let direct = ConversationBuilder::new("direct")
+        .participant("1", "Alice Smith")
+        .participant("2", "Bob")
+        .message("2", "Hey")
+        .build();
+    Hangouts {
+        conversations: vec![group, direct],
+    }
+}
+
+fn lines(options: &MattermostOptions) -> Vec<serde_json::Value> {
+    let mut written = Vec::new();
+    mattermost::write_bulk_import(&mut written, &hangouts(), options).unwrap();
+    String::from_utf8(written)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[test]
+fn writes_bulk_import() {
+    let mut options = MattermostOptions::default();
+    options
+        .media
+        .insert("photo-1".to_owned(), PathBuf::from("/takeout/1.jpg"));
+    let lines = lines(&options);
+
+    let types: Vec<&str> = lines
+        .iter()
+        .map(|line| line["type"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        types,
+        [
+            "version",
+            "team",
+            "channel",
+            "user",
+            "user",
+            "user",
+            "post",
+            "post",
+            "direct_channel",
+            "direct_post"
+        ]
+    );
+    assert_eq!(
+        lines[2]["channel"],
+        serde_json::json!({
+            "team": "hangouts",
+            "name": "group-1",
+            "display_name": "Friends",
+            "type": "P",
+        })
+    );
+    assert_eq!(
+        lines[3]["user"],
+        serde_json::json!({
+            "username": "alice.smith",
+            "email": "alice.smith@hangouts.invalid",
+            "teams": [{ "name": "hangouts", "channels": [{ "name": "group-1" }] }],
+        })
+    );
+    // Too short a name falls back to the Gaia ID.
+    assert_eq!(lines[5]["user"]["username"], "user3");
+
+    let post = &lines[7]["post"];
+    assert_eq!(post["user"], "bob");
+    assert_eq!(post["create_at"], 1420070460000i64);
+    assert_eq!(post["message"], "https://example.com/2.jpg");
+    assert_eq!(
+        post["attachments"],
+        serde_json::json!([{ "path": "/takeout/1.jpg" }])
+    );
+
+    assert_eq!(
+        lines[8]["direct_channel"]["members"],
+        serde_json::json!(["alice.smith", "bob"])
+    );
+    assert_eq!(lines[9]["direct_post"]["message"], "Hey");
+}
+
+#[test]
+fn renders_escaped_markdown() {
+    let lines = lines(&MattermostOptions::default());
+    assert_eq!(
+        lines[6]["post"]["message"],
+        "**Hi** all _there_\n\
+         a\\*b\\_c \\[d\\] \\<e>\
+         [see \\[this\\]](https://example.com/a_%28b%29)"
+    );
+}
+
+#[test]
+fn uses_explicit_usernames() {
+    let options = MattermostOptions {
+        usernames: HashMap::from([(participant_id("2"), "robert".to_owned())]),
+        ..MattermostOptions::default()
+    };
+    let lines = lines(&options);
+    assert_eq!(lines[7]["post"]["user"], "robert");
+    assert_eq!(
+        lines[8]["direct_channel"]["members"],
+        serde_json::json!(["alice.smith", "robert"])
+    );
+}
@@ -0,0 +1,346 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use hangouts_rs::builder::{
+    default_start, participant_id, ChatMessageBuilder, ConversationBuilder, EventBuilder,
+};
+use hangouts_rs::chrono::Duration;
+use hangouts_rs::media::{
+    organize_media, MediaInventory, MediaResolver, MediaTotals, OrganizeOptions,
+    UnmatchedAttachment,
+};
+use hangouts_rs::{AttachmentSegment, Hangouts, MediaType};
+
+/// A photo attachment with the given IDs and URL.
+fn photo(id: &str, album: &str, url: &str) -> AttachmentSegment {
+    let mut attachment = ChatMessageBuilder::new()
+        .photo(id, url)
+        .build()
+        .attachments
+        .remove(0);
+    attachment.item.photo.as_mut().unwrap().album_id = album.to_owned();
+    attachment
+}
+
+fn hangouts() -> Hangouts {
+    let conversation = ConversationBuilder::new("chat")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .event(EventBuilder::message(
+            "1",
+            ChatMessageBuilder::new()
+                .attachment(photo("111", "", "https://example.com/a.jpg"))
+                .attachment(photo("222", "12", "https://example.com/b.jpg")),
+        ))
+        .event(EventBuilder::message(
+            "2",
+            ChatMessageBuilder::new()
+                .attachment(photo("333", "", "https://example.com/x/My%20Pic.PNG?sz=1"))
+                .attachment(photo("444", "", "https://example.com/gone.jpg")),
+        ))
+        .build();
+    Hangouts {
+        conversations: vec![conversation],
+    }
+}
+
+/// A Takeout directory with the given files, removed when dropped.
+struct Takeout(PathBuf);
+
+impl Takeout {
+    fn new(name: &str, files: &[(&str, &[u8])]) -> Self {
+        let dir = std::env::temp_dir().join(format!("hangouts-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (path, contents) in files {
+            let path = dir.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        Self(dir)
+    }
+
+    fn path(&self, path: &str) -> PathBuf {
+        self.0.join(path)
+    }
+}
+
+impl Drop for Takeout {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+fn takeout(name: &str) -> Takeout {
+    Takeout::new(
+        name,
+        &[
+            ("Hangouts/111-photo.jpg", b"first"),
+            ("a12/222.jpg", b"second, wrong album"),
+            ("b/12/222.jpg", b"second"),
+            ("Hangouts/my pic.png", b"third"),
+            ("Hangouts/unused.gif", b"unused"),
+            ("Hangouts/Hangouts.json", b"{}"),
+        ],
+    )
+}
+
+#[test]
+fn resolves_attachments() {
+    let takeout = takeout("resolve");
+    let resolver = MediaResolver::new(&takeout.0).unwrap();
+    assert_eq!(resolver.files().count(), 5);
+
+    let report = resolver.resolve_all(&hangouts());
+    assert_eq!(report.resolved.len(), 3);
+
+    let first = &report.resolved["111"];
+    assert_eq!(first.path, takeout.path("Hangouts/111-photo.jpg"));
+    assert_eq!(first.size, 5);
+    assert_eq!(first.mime_type, "image/jpeg");
+    // Matched by the album folder, not by a folder name merely containing the album ID.
+    assert_eq!(report.resolved["222"].path, takeout.path("b/12/222.jpg"));
+    // Matched by the decoded file name of the URL.
+    assert_eq!(
+        report.resolved["333"].path,
+        takeout.path("Hangouts/my pic.png")
+    );
+    assert_eq!(report.resolved["333"].mime_type, "image/png");
+
+    assert_eq!(
+        report.unmatched,
+        [UnmatchedAttachment {
+            conversation_id: "chat".to_owned(),
+            event_id: "event-2".to_owned(),
+            attachment_id: "444".to_owned(),
+            photo_id: "444".to_owned(),
+        }]
+    );
+    let orphaned: Vec<&Path> = report
+        .orphaned
+        .iter()
+        .map(|media| media.path.as_path())
+        .collect();
+    assert_eq!(
+        orphaned,
+        [
+            takeout.path("Hangouts/unused.gif"),
+            takeout.path("a12/222.jpg")
+        ]
+    );
+    assert_eq!(
+        report.paths()["111"],
+        takeout.path("Hangouts/111-photo.jpg")
+    );
+}
+
+#[test]
+fn prefers_files_below_album_folders() {
+    let takeout = takeout("resolve-album");
+    let resolver = MediaResolver::new(&takeout.0).unwrap();
+
+    let attachment = photo("222", "b", "https://example.com/b.jpg");
+    assert_eq!(
+        resolver.resolve(&attachment).unwrap().path,
+        takeout.path("b/12/222.jpg")
+    );
+    // Without a matching folder, the first file in path order is used.
+    let attachment = photo("222", "1", "https://example.com/b.jpg");
+    assert_eq!(
+        resolver.resolve(&attachment).unwrap().path,
+        takeout.path("a12/222.jpg")
+    );
+}
+
+#[test]
+fn lists_media_with_duplicates_and_totals() {
+    let takeout = Takeout::new(
+        "inventory",
+        &[
+            ("111.jpg", b"first"),
+            ("222.jpg", b"second"),
+            ("copy-of-first.jpg", b"first"),
+        ],
+    );
+    let resolver = MediaResolver::new(&takeout.0).unwrap();
+
+    let mut hangouts = hangouts();
+    hangouts.conversations.push(
+        ConversationBuilder::new("other")
+            .participant("2", "Bob")
+            .participant("3", "Carol")
+            .event(EventBuilder::message(
+                "3",
+                ChatMessageBuilder::new()
+                    .attachment(photo("111", "", "https://example.com/a.jpg"))
+                    .attachment(photo("copy", "", "https://example.com/copy-of-first.jpg")),
+            ))
+            .build(),
+    );
+
+    let inventory = MediaInventory::new(&hangouts, Some(&resolver)).unwrap();
+    let ids: Vec<&str> = inventory
+        .items
+        .iter()
+        .map(|item| item.attachment_id.as_str())
+        .collect();
+    assert_eq!(ids, ["111", "222", "333", "444", "111", "copy"]);
+
+    let item = &inventory.items[1];
+    assert_eq!(item.conversation_id, "chat");
+    assert_eq!(item.event_id, "event-1");
+    assert_eq!(item.sender, participant_id("1"));
+    assert_eq!(item.timestamp, default_start());
+    assert_eq!(item.media_type, MediaType::Photo);
+    assert_eq!(item.local.as_ref().unwrap().path, takeout.path("222.jpg"));
+    assert!(item.content_hash.is_some());
+    assert_eq!(inventory.items[2].local, None);
+    assert_eq!(inventory.items[2].content_hash, None);
+
+    // The second `111` shares the photo ID, and `copy` the contents of its file.
+    assert_eq!(inventory.duplicates, [vec![0, 4, 5]]);
+
+    assert_eq!(
+        inventory.by_conversation["chat"],
+        MediaTotals {
+            count: 4,
+            local_count: 2,
+            size: 11,
+        }
+    );
+    assert_eq!(
+        inventory.by_participant[&participant_id("3")],
+        MediaTotals {
+            count: 2,
+            local_count: 2,
+            size: 10,
+        }
+    );
+    assert_eq!(
+        inventory.by_participant[&participant_id("2")],
+        MediaTotals {
+            count: 2,
+            local_count: 0,
+            size: 0,
+        }
+    );
+    assert_eq!(
+        inventory.totals(),
+        MediaTotals {
+            count: 6,
+            local_count: 4,
+            size: 21,
+        }
+    );
+}
+
+#[test]
+fn lists_media_without_local_files() {
+    let inventory = MediaInventory::new(&hangouts(), None).unwrap();
+    assert_eq!(inventory.items.len(), 4);
+    assert!(inventory.items.iter().all(|item| item.local.is_none()));
+    assert!(inventory.duplicates.is_empty());
+    assert_eq!(
+        inventory.totals(),
+        MediaTotals {
+            count: 4,
+            local_count: 0,
+            size: 0,
+        }
+    );
+}
+
+#[test]
+fn organizes_media() {
+    let takeout = takeout("organize");
+    let resolver = MediaResolver::new(&takeout.0).unwrap();
+    let out = takeout.path("out");
+
+    let dry_run = OrganizeOptions {
+        dry_run: true,
+        ..OrganizeOptions::default()
+    };
+    let planned = organize_media(&out, &hangouts(), &resolver, &dry_run).unwrap();
+    assert!(!out.exists());
+
+    let manifest =
+        organize_media(&out, &hangouts(), &resolver, &OrganizeOptions::default()).unwrap();
+    assert_eq!(manifest, planned);
+    let files: Vec<(&str, &Path)> = manifest
+        .files
+        .iter()
+        .map(|(id, path)| (id.as_str(), path.as_path()))
+        .collect();
+    assert_eq!(
+        files,
+        [
+            (
+                "111",
+                Path::new("media/Alice, Bob/2015-01-01_000000_Alice_111.jpg")
+            ),
+            (
+                "222",
+                Path::new("media/Alice, Bob/2015-01-01_000000_Alice_222.jpg")
+            ),
+            (
+                "333",
+                Path::new("media/Alice, Bob/2015-01-01_000100_Bob_333.png")
+            ),
+        ]
+    );
+    assert_eq!(manifest.missing.len(), 1);
+    assert_eq!(manifest.missing[0].attachment_id, "444");
+
+    let paths = manifest.paths(&out);
+    assert_eq!(fs::read(&paths["222"]).unwrap(), b"second");
+    let modified = fs::metadata(&paths["333"]).unwrap().modified().unwrap();
+    assert_eq!(
+        modified,
+        SystemTime::from(default_start() + Duration::minutes(1))
+    );
+}
+
+#[test]
+fn keeps_files_in_place_on_rerun() {
+    let takeout = takeout("organize-rerun");
+    let resolver = MediaResolver::new(&takeout.0).unwrap();
+    let out = takeout.path("out");
+    let options = OrganizeOptions::default();
+
+    let first = organize_media(&out, &hangouts(), &resolver, &options).unwrap();
+    let second = organize_media(&out, &hangouts(), &resolver, &options).unwrap();
+    assert_eq!(second, first);
+    assert_eq!(
+        fs::read_dir(out.join("media/Alice, Bob")).unwrap().count(),
+        3
+    );
+}
+
+#[test]
+fn renames_around_other_files() {
+    let takeout = takeout("organize-taken");
+    let resolver = MediaResolver::new(&takeout.0).unwrap();
+    let out = takeout.path("out");
+    let taken = out.join("media/Alice, Bob/2015-01-01_000000_Alice_111.jpg");
+    fs::create_dir_all(taken.parent().unwrap()).unwrap();
+    fs::write(&taken, b"someone else").unwrap();
+
+    let manifest =
+        organize_media(&out, &hangouts(), &resolver, &OrganizeOptions::default()).unwrap();
+    assert_eq!(
+        manifest.files["111"],
+        Path::new("media/Alice, Bob/2015-01-01_000000_Alice_111_2.jpg")
+    );
+    assert_eq!(fs::read(&taken).unwrap(), b"someone else");
+
+    let overwrite = OrganizeOptions {
+        overwrite: true,
+        ..OrganizeOptions::default()
+    };
+    let manifest = organize_media(&out, &hangouts(), &resolver, &overwrite).unwrap();
+    assert_eq!(
+        manifest.files["111"],
+        Path::new("media/Alice, Bob/2015-01-01_000000_Alice_111.jpg")
+    );
+    assert_eq!(fs::read(&taken).unwrap(), b"first");
+}
@@ -0,0 +1,152 @@
+use hangouts_rs::builder::{
+    default_start, participant_id, ChatMessageBuilder, ConversationBuilder, EventBuilder,
+};
+use hangouts_rs::chrono::Duration;
+use hangouts_rs::merge::Conflict;
+use hangouts_rs::{Hangouts, View};
+
+fn export(conversations: Vec<ConversationBuilder>) -> Hangouts {
+    Hangouts {
+        conversations: conversations
+            .into_iter()
+            .map(ConversationBuilder::build)
+            .collect(),
+    }
+}
+
+/// The first three messages of a conversation, as exported in 2015.
+fn old() -> ConversationBuilder {
+    ConversationBuilder::new("c1")
+        .name("Friends")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .message("1", "one")
+        .message("2", "two")
+        .message("1", "three")
+}
+
+/// The same conversation exported later, with more messages.
+fn new() -> ConversationBuilder {
+    old()
+        .views(vec![View::Archived])
+        .event(EventBuilder::rename("2", "Friends", "Best friends"))
+        .message("2", "five")
+}
+
+#[test]
+fn unites_conversations_and_events() {
+    let mut hangouts = export(vec![old()]);
+    let report = hangouts.merge(export(vec![
+        new(),
+        ConversationBuilder::new("c2")
+            .participant("1", "Alice")
+            .message("1", "alone"),
+    ]));
+
+    assert_eq!(report.conversations_added, 1);
+    assert_eq!(report.conversations_merged, 1);
+    assert_eq!(report.events_added, 3);
+    assert_eq!(report.events_duplicate, 3);
+    assert_eq!(report.events_replaced, 0);
+
+    assert_eq!(hangouts.conversations.len(), 2);
+    let merged = &hangouts.conversations[0];
+    assert_eq!(merged, &new().build());
+    assert_eq!(
+        report.conflicts[0],
+        Conflict::ConversationName {
+            conversation_id: "c1".to_owned(),
+            kept: Some("Best friends".to_owned()),
+            dropped: Some("Friends".to_owned()),
+        }
+    );
+    // Both participants have read further in the newer export.
+    assert_eq!(report.conflicts.len(), 3);
+
+    // Merging the older export into the newer one gives the same conversation.
+    let mut hangouts = export(vec![new()]);
+    let report = hangouts.merge(export(vec![old()]));
+    assert_eq!(report.events_added, 0);
+    assert_eq!(hangouts.conversations[0], new().build());
+}
+
+#[test]
+fn keeps_newer_versions_of_events() {
+    let mut hangouts = export(vec![ConversationBuilder::new("c")
+        .message("1", "typo")
+        .event(EventBuilder::message("1", Default::default()).id("same"))]);
+    let edited = ConversationBuilder::new("c")
+        .event(
+            EventBuilder::message("1", ChatMessageBuilder::new().text("fixed"))
+                .id("event-1")
+                .version(u64::MAX),
+        )
+        .event(
+            EventBuilder::message("2", Default::default())
+                .id("same")
+                .version(hangouts.conversations[0].events[1].version),
+        );
+
+    let report = hangouts.merge(export(vec![edited]));
+    assert_eq!(report.events_replaced, 1);
+    assert_eq!(report.events_duplicate, 1);
+    let events = &hangouts.conversations[0].events;
+    assert_eq!(
+        events[0].data.as_chat_message().unwrap().contents_as_str(),
+        "fixed"
+    );
+    assert_eq!(events[1].sender, participant_id("1"));
+    assert!(matches!(
+        &report.conflicts[..],
+        [.., Conflict::Event { event_id, .. }] if event_id == "same"
+    ));
+}
+
+#[test]
+fn reports_participant_conflicts() {
+    let mut hangouts = export(vec![ConversationBuilder::new("c")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .message("1", "hi")]);
+    let report = hangouts.merge(export(vec![ConversationBuilder::new("c")
+        .participant("1", "Alice")
+        .participant("2", "Robert")
+        .start(default_start() + Duration::days(1))
+        .message("3", "hello")]));
+
+    let conversation = &hangouts.conversations[0];
+    assert_eq!(conversation.participants.len(), 3);
+    assert_eq!(
+        conversation.participant_name(&participant_id("2")).unwrap(),
+        "Robert"
+    );
+
+    let read = default_start() + Duration::days(1);
+    assert_eq!(
+        conversation.participants[&participant_id("1")]
+            .read_state
+            .timestamp,
+        read
+    );
+    assert!(report.conflicts.contains(&Conflict::ParticipantName {
+        conversation_id: "c".to_owned(),
+        participant: participant_id("2"),
+        kept: "Robert".to_owned(),
+        dropped: "Bob".to_owned(),
+    }));
+    assert!(report.conflicts.contains(&Conflict::ReadState {
+        conversation_id: "c".to_owned(),
+        participant: participant_id("1"),
+        kept: read,
+        dropped: default_start(),
+    }));
+    let name = report
+        .conflicts
+        .iter()
+        .find(|conflict| matches!(conflict, Conflict::ParticipantName { .. }))
+        .unwrap();
+    assert_eq!(
+        name.to_string(),
+        "c: participant 2 named \"Robert\" kept over \"Bob\""
+    );
+}
@@ -0,0 +1,228 @@
+use std::fs;
+use std::path::Path;
+
+use hangouts_rs::builder::{ChatMessageBuilder, ConversationBuilder, EventBuilder};
+use hangouts_rs::export::slack::{SlackExport, SlackOptions};
+use hangouts_rs::Hangouts;
+
+fn hangouts() -> Hangouts {
+    let group = ConversationBuilder::new("group")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .event(EventBuilder::message(
+            "1",
+            ChatMessageBuilder::new()
+                .bold("Hey")
+                .text(" <!channel> & co")
+                .line_break()
+                .link("a|b > c", "https://example.com/?a=1&b=|"),
+        ))
+        .event(EventBuilder::join("1", &["3", "4", "5"]))
+        .event(EventBuilder::rename("2", "", "<!here> & friends"))
+        .event(EventBuilder::message(
+            "2",
+            ChatMessageBuilder::new().photo("photo-1", "https://example.com/1.jpg"),
+        ))
+        .build();
+    let direct = ConversationBuilder::new("direct")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .message("2", "Hi")
+        .build();
+    Hangouts {
+        conversations: vec![group, direct],
+    }
+}
+
+fn read(dir: &Path, path: &str) -> serde_json::Value {
+    serde_json::from_slice(&fs::read(dir.join(path)).unwrap()).unwrap()
+}
+
+#[test]
+fn writes_export_archive() {
+    let dir = std::env::temp_dir().join(format!("hangouts-slack-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let media = dir.join("photo.jpg");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(&media, b"jpeg").unwrap();
+
+    let mut options = SlackOptions::default();
+    options.media.insert("photo-1".to_owned(), media);
+    let export = SlackExport::new(&hangouts(), &options).unwrap();
+    assert_eq!(
+        export.paths().collect::<Vec<_>>(),
+        [
+            "Ddirect/2015-01-01.json",
+            "__uploads/photo-1/photo.jpg",
+            "channels.json",
+            "dms.json",
+            "here-friends/2015-01-01.json",
+            "users.json",
+        ]
+    );
+
+    let out = dir.join("export");
+    export.write_dir(&out).unwrap();
+    assert_eq!(
+        fs::read(out.join("__uploads/photo-1/photo.jpg")).unwrap(),
+        b"jpeg"
+    );
+
+    let users = read(&out, "users.json");
+    assert_eq!(users.as_array().unwrap().len(), 5);
+    assert_eq!(users[0]["id"], "U1");
+    assert_eq!(users[0]["name"], "alice");
+
+    let channels = read(&out, "channels.json");
+    assert_eq!(channels[0]["id"], "Cgroup");
+    assert_eq!(channels[0]["name"], "here-friends");
+    assert_eq!(channels[0]["creator"], "U1");
+    assert_eq!(
+        channels[0]["members"],
+        serde_json::json!(["U1", "U2", "U3", "U4", "U5"])
+    );
+
+    let dms = read(&out, "dms.json");
+    assert_eq!(dms[0]["id"], "Ddirect");
+    let direct = read(&out, "Ddirect/2015-01-01.json");
+    assert_eq!(direct[0]["text"], "Hi");
+
+    let messages = read(&out, "here-friends/2015-01-01.json");
+    let messages = messages.as_array().unwrap();
+    assert_eq!(messages.len(), 6);
+    assert_eq!(
+        messages[5]["files"],
+        serde_json::json!([{
+            "id": "photo-1",
+            "name": "photo.jpg",
+            "url_private": "__uploads/photo-1/photo.jpg",
+        }])
+    );
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn escapes_text() {
+    let dir = std::env::temp_dir().join(format!("hangouts-slack-text-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    SlackExport::new(&hangouts(), &SlackOptions::default())
+        .unwrap()
+        .write_dir(&dir)
+        .unwrap();
+    let messages = read(&dir, "here-friends/2015-01-01.json");
+
+    assert_eq!(
+        messages[0]["text"],
+        "*Hey* &lt;!channel&gt; &amp; co\n\
+         <https://example.com/?a=1&amp;b=%7C|a|b &gt; c>"
+    );
+    assert_eq!(messages[4]["subtype"], "channel_name");
+    assert_eq!(
+        messages[4]["text"],
+        "<@U2> has renamed the channel from \"\" to \"&lt;!here&gt; &amp; friends\""
+    );
+    assert_eq!(messages[4]["name"], "<!here> & friends");
+    assert_eq!(messages[5]["text"], "<https://example.com/1.jpg>");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn gives_membership_messages_unique_timestamps() {
+    let dir = std::env::temp_dir().join(format!("hangouts-slack-ts-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    SlackExport::new(&hangouts(), &SlackOptions::default())
+        .unwrap()
+        .write_dir(&dir)
+        .unwrap();
+    let messages = read(&dir, "here-friends/2015-01-01.json");
+
+    let joins: Vec<(&str, &str)> = messages.as_array().unwrap()[1..4]
+        .iter()
+        .map(|message| {
+            assert_eq!(message["subtype"], "channel_join");
+            (
+                message["user"].as_str().unwrap(),
+                message["ts"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        joins,
+        [
+            ("U3", "1420070460.000000"),
+            ("U4", "1420070460.000001"),
+            ("U5", "1420070460.000002"),
+        ]
+    );
+    assert_eq!(messages[4]["ts"], "1420070520.000000");
+
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn keeps_hostile_ids_inside_the_archive() {
+    let dir = std::env::temp_dir().join(format!("hangouts-slack-ids-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    let out = dir.join("out");
+    fs::create_dir_all(&out).unwrap();
+    let media = dir.join("photo.jpg");
+    fs::write(&media, b"jpeg").unwrap();
+
+    let hostile = |id: &str| {
+        ConversationBuilder::new(id)
+            .participant("1", "Alice")
+            .participant("2", "Bob")
+            .event(EventBuilder::message(
+                "1",
+                ChatMessageBuilder::new().photo("/../../../evil", "https://example.com/1.jpg"),
+            ))
+            .build()
+    };
+    let hangouts = Hangouts {
+        conversations: vec![hostile("/../../../x"), hostile("\\..\\..\\..\\x")],
+    };
+    let mut options = SlackOptions::default();
+    options.media.insert("/../../../evil".to_owned(), media);
+    let export = SlackExport::new(&hangouts, &options).unwrap();
+    assert_eq!(
+        export.paths().collect::<Vec<_>>(),
+        [
+            "D_.._.._.._x-2/2015-01-01.json",
+            "D_.._.._.._x/2015-01-01.json",
+            "__uploads/_.._.._.._evil/photo.jpg",
+            "channels.json",
+            "dms.json",
+            "users.json",
+        ]
+    );
+
+    export.write_dir(&out).unwrap();
+    assert!(!dir.join("x").exists());
+    assert!(!dir.join("evil").exists());
+    assert_eq!(
+        fs::read(out.join("__uploads/_.._.._.._evil/photo.jpg")).unwrap(),
+        b"jpeg"
+    );
+    let dms = read(&out, "dms.json");
+    assert_eq!(dms[0]["id"], "D_.._.._.._x");
+    assert_eq!(dms[1]["id"], "D_.._.._.._x-2");
+    let messages = read(&out, "D_.._.._.._x/2015-01-01.json");
+    assert_eq!(
+        messages[0]["files"][0]["url_private"],
+        "__uploads/_.._.._.._evil/photo.jpg"
+    );
+
+    #[cfg(feature = "zip")]
+    {
+        let mut zip = std::io::Cursor::new(Vec::new());
+        export.write_zip(&mut zip).unwrap();
+        let archive = zip::ZipArchive::new(zip).unwrap();
+        for name in archive.file_names() {
+            assert!(!name.split('/').any(|part| part == ".."), "{}", name);
+        }
+    }
+
+    fs::remove_dir_all(&dir).unwrap();
+}
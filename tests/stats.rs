@@ -0,0 +1,381 @@
+use std::collections::BTreeMap;
+use std::convert::TryInto;
+use std::io;
+
+use hangouts_rs::builder::{
+    default_start, participant_id, ChatMessageBuilder, ConversationBuilder, EventBuilder,
+};
+
+use hangouts_rs::chrono::{Duration, FixedOffset, NaiveDate, Utc};
+use hangouts_rs::raw::generate::{generate, GeneratorOptions};
+use hangouts_rs::stats::{
+    sessions, write_heatmap, Activity, ActivityFilter, DayCount, EventKind, HangoutsStats,
+    HeatmapOptions, MessageCounts, ResponseStats, Responsiveness, SessionOptions, Silence, Stats,
+    Turn,
+};
+use hangouts_rs::{Conversation, Hangouts, MediaType};
+
+fn conversation() -> Conversation {
+    let start = default_start();
+    ConversationBuilder::new("chat")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .message("1", "Good morning")
+        .message("2", "Hi")
+        .message("1", "See you later")
+        .event(
+            EventBuilder::message(
+                "2",
+                ChatMessageBuilder::new()
+                    .text("Look at ")
+                    .link("this", "https://example.com/")
+                    .photo("p1", "https://example.com/p1.jpg"),
+            )
+            .timestamp(start + Duration::days(2)),
+        )
+        .event(EventBuilder::call_start("1", MediaType::Audio).timestamp(start + Duration::days(3)))
+        .event(
+            EventBuilder::call_end("1", 300, &["1", "2"])
+                .timestamp(start + Duration::days(3) + Duration::minutes(5)),
+        )
+        .event(EventBuilder::rename("1", "", "Us").timestamp(start + Duration::days(4)))
+        .build()
+}
+
+#[test]
+fn counts_conversation() {
+    let stats = Stats::for_conversation(&conversation());
+    let start = default_start();
+
+    assert_eq!(stats.conversation_id.as_deref(), Some("chat"));
+    assert_eq!(
+        stats.totals,
+        MessageCounts {
+            messages: 4,
+            words: 9,
+            characters: 39,
+            attachments: 1,
+            links: 1,
+            places: 0,
+        }
+    );
+    assert_eq!(stats.calls, 1);
+    assert_eq!(stats.call_duration, 300);
+
+    let alice = stats.participant(&participant_id("1")).unwrap();
+    assert_eq!(alice.name.as_deref(), Some("Alice"));
+    assert_eq!(alice.counts.messages, 2);
+    assert_eq!(alice.counts.words, 5);
+    let bob = stats.participant(&participant_id("2")).unwrap();
+    assert_eq!(bob.counts.attachments, 1);
+    assert_eq!(bob.counts.links, 1);
+    // Sorted by message count, then by ID.
+    assert_eq!(stats.participants[0].id, participant_id("1"));
+
+    assert_eq!(stats.first_message.as_ref().unwrap().event_id, "event-1");
+    let last = stats.last_message.as_ref().unwrap();
+    assert_eq!(last.event_id, "event-4");
+    assert_eq!(last.sender, participant_id("2"));
+    assert_eq!(last.timestamp, start + Duration::days(2));
+    assert_eq!(
+        stats.busiest_day,
+        Some(DayCount {
+            date: NaiveDate::from_ymd_opt(2015, 1, 1).unwrap(),
+            messages: 3,
+        })
+    );
+    assert_eq!(
+        stats.longest_silence,
+        Some(Silence {
+            start: start + Duration::minutes(2),
+            end: start + Duration::days(2),
+            secs: 2 * 86400 - 120,
+        })
+    );
+}
+
+#[test]
+fn counts_empty_conversation() {
+    let stats = Stats::for_conversation(&ConversationBuilder::new("empty").build());
+    assert_eq!(stats.totals, MessageCounts::default());
+    assert!(stats.participants.is_empty());
+    assert_eq!(stats.first_message, None);
+    assert_eq!(stats.busiest_day, None);
+    assert_eq!(stats.longest_silence, None);
+}
+
+#[test]
+fn sums_conversations() {
+    let hangouts: Hangouts = generate(&GeneratorOptions {
+        seed: 5,
+        conversations: 4,
+        events: 120,
+        ..GeneratorOptions::default()
+    })
+    .try_into()
+    .unwrap();
+    let stats = HangoutsStats::new(&hangouts);
+    assert_eq!(stats.conversations.len(), 4);
+    assert_eq!(stats.overall.conversation_id, None);
+
+    let sum = |f: fn(&Stats) -> usize| stats.conversations.iter().map(f).sum::<usize>();
+    assert_eq!(stats.overall.totals.messages, sum(|s| s.totals.messages));
+    assert_eq!(stats.overall.totals.words, sum(|s| s.totals.words));
+    assert_eq!(stats.overall.totals.places, sum(|s| s.totals.places));
+    assert_eq!(stats.overall.calls, sum(|s| s.calls));
+    assert!(stats.overall.totals.places > 0);
+
+    let places: usize = hangouts
+        .conversations
+        .iter()
+        .flat_map(|conversation| &conversation.events)
+        .filter_map(|event| event.data.as_chat_message())
+        .flat_map(|message| &message.attachments)
+        .filter(|attachment| attachment.item.place.is_some())
+        .count();
+    assert_eq!(stats.overall.totals.places, places);
+
+    let participant_messages: usize = stats
+        .overall
+        .participants
+        .iter()
+        .map(|participant| participant.counts.messages)
+        .sum();
+    assert_eq!(participant_messages, stats.overall.totals.messages);
+    let first = stats
+        .conversations
+        .iter()
+        .filter_map(|s| s.first_message.as_ref())
+        .map(|message| message.timestamp)
+        .min();
+    assert_eq!(
+        stats.overall.first_message.map(|message| message.timestamp),
+        first
+    );
+}
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+#[test]
+fn buckets_activity() {
+    let conversation = conversation();
+
+    let activity = Activity::for_conversation(&conversation, &Utc, &ActivityFilter::default());
+    assert_eq!(activity.total(), 7);
+    assert_eq!(activity.by_hour[0], 7);
+    // 2015-01-01 was a Thursday.
+    assert_eq!(activity.by_weekday, [1, 0, 0, 3, 0, 1, 2]);
+    assert_eq!(activity.by_day.len(), 4);
+    assert_eq!(activity.by_month, BTreeMap::from([(date(2015, 1, 1), 7)]));
+    assert_eq!(activity.by_year, BTreeMap::from([(2015, 7)]));
+
+    // Eleven hours west of UTC, the first events fall on the last day of 2014.
+    let tz = FixedOffset::west_opt(11 * 3600).unwrap();
+    let messages = Activity::for_conversation(&conversation, &tz, &ActivityFilter::chat_messages());
+    assert_eq!(messages.total(), 4);
+    assert_eq!(messages.by_hour[13], 4);
+    assert_eq!(
+        messages.by_day,
+        BTreeMap::from([(date(2014, 12, 31), 3), (date(2015, 1, 2), 1)])
+    );
+    assert_eq!(
+        messages.by_month,
+        BTreeMap::from([(date(2014, 12, 1), 3), (date(2015, 1, 1), 1)])
+    );
+    assert_eq!(messages.by_year, BTreeMap::from([(2014, 3), (2015, 1)]));
+
+    let filter = ActivityFilter {
+        senders: vec![participant_id("1")],
+        kinds: vec![EventKind::ChatMessage, EventKind::HangoutEvent],
+    };
+    let hangouts = Hangouts {
+        conversations: vec![conversation.clone(), conversation],
+    };
+    assert_eq!(Activity::for_hangouts(&hangouts, &Utc, &filter).total(), 8);
+}
+
+fn heatmap(days: &BTreeMap<NaiveDate, usize>, options: &HeatmapOptions) -> io::Result<String> {
+    let mut written = Vec::new();
+    write_heatmap(&mut written, days, options)?;
+    Ok(String::from_utf8(written).unwrap())
+}
+
+#[test]
+fn renders_heatmap() {
+    let days = BTreeMap::from([
+        (date(2015, 1, 1), 1),
+        (date(2015, 1, 2), 4),
+        (date(2015, 2, 2), 2),
+    ]);
+    let svg = heatmap(&days, &HeatmapOptions::default()).unwrap();
+
+    assert!(
+        svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"106\" height=\"107\"")
+    );
+    assert!(svg.ends_with("</svg>\n"));
+    // One cell for every day from the first to the last with activity.
+    assert_eq!(svg.matches("<rect ").count(), 33);
+    assert!(svg.contains("<text x=\"28\" y=\"11\">Jan 2015</text>"));
+    assert!(svg.contains(">Feb</text>"));
+    // Thursday of the first week, colored by a quarter of the busiest day.
+    assert!(svg.contains(
+        "<rect x=\"28\" y=\"55\" width=\"11\" height=\"11\" rx=\"2\" fill=\"#9be9a8\">\
+         <title>2015-01-01: 1</title></rect>"
+    ));
+    assert!(svg.contains("fill=\"#216e39\"><title>2015-01-02: 4</title>"));
+    assert!(svg.contains("fill=\"#40c463\"><title>2015-02-02: 2</title>"));
+    assert!(svg.contains("fill=\"#ebedf0\"><title>2015-01-03: 0</title>"));
+
+    let svg = heatmap(&BTreeMap::new(), &HeatmapOptions::default()).unwrap();
+    assert_eq!(
+        svg,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"0\" height=\"0\"></svg>\n"
+    );
+}
+
+#[test]
+fn checks_heatmap_colors() {
+    let days = BTreeMap::from([(date(2015, 1, 1), 1)]);
+
+    let options = HeatmapOptions {
+        colors: Vec::new(),
+        ..HeatmapOptions::default()
+    };
+    let err = heatmap(&days, &options).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+    let options = HeatmapOptions {
+        colors: vec!["red\" onload=\"alert(1)".to_owned()],
+        ..HeatmapOptions::default()
+    };
+    let svg = heatmap(&days, &options).unwrap();
+    assert!(svg.contains("fill=\"red&quot; onload=&quot;alert(1)\""));
+}
+
+/// Alice and Bob chatting in three sessions with one hour gaps.
+fn chat() -> Conversation {
+    let message = |sender: &str, minutes: i64| {
+        EventBuilder::message(sender, ChatMessageBuilder::new().text("..."))
+            .timestamp(default_start() + Duration::minutes(minutes))
+    };
+    ConversationBuilder::new("chat")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .event(message("1", 0))
+        .event(message("1", 1))
+        .event(message("2", 3))
+        .event(message("1", 10))
+        .event(message("2", 120))
+        .event(
+            EventBuilder::call_start("2", MediaType::Video)
+                .timestamp(default_start() + Duration::minutes(121)),
+        )
+        .event(message("1", 125))
+        .event(message("1", 300))
+        .build()
+}
+
+#[test]
+fn segments_sessions() {
+    let start = default_start();
+    let turn = |sender: &str, from: i64, to: i64, messages: usize| Turn {
+        sender: participant_id(sender),
+        start: start + Duration::minutes(from),
+        end: start + Duration::minutes(to),
+        messages,
+    };
+
+    let split = sessions(&chat(), &SessionOptions::default());
+    assert_eq!(split.len(), 3);
+    assert_eq!(
+        split[0].turns,
+        vec![turn("1", 0, 1, 2), turn("2", 3, 3, 1), turn("1", 10, 10, 1)]
+    );
+    assert_eq!(split[0].starter(), &participant_id("1"));
+    assert_eq!(split[0].start(), start);
+    assert_eq!(split[0].end(), start + Duration::minutes(10));
+    assert_eq!(split[0].messages(), 4);
+    // The call does not split Bob's turn from Alice's reply.
+    assert_eq!(
+        split[1].turns,
+        vec![turn("2", 120, 120, 1), turn("1", 125, 125, 1)]
+    );
+    assert_eq!(split[1].starter(), &participant_id("2"));
+    assert_eq!(split[2].turns, vec![turn("1", 300, 300, 1)]);
+
+    let options = SessionOptions {
+        gap: Duration::hours(3),
+    };
+    assert_eq!(sessions(&chat(), &options).len(), 1);
+    assert!(sessions(&ConversationBuilder::new("empty").build(), &options).is_empty());
+}
+
+#[test]
+fn measures_responsiveness() {
+    let responsiveness = Responsiveness::for_conversation(&chat(), &SessionOptions::default());
+    assert_eq!(responsiveness.conversation_id.as_deref(), Some("chat"));
+    assert_eq!(responsiveness.sessions, 3);
+    assert_eq!(responsiveness.average_session_secs, 300.0);
+    assert_eq!(responsiveness.average_session_messages, 7.0 / 3.0);
+    assert_eq!(
+        responsiveness.participants,
+        vec![
+            ResponseStats {
+                id: participant_id("1"),
+                turns: 4,
+                sessions_started: 2,
+                replies: 2,
+                median_reply_secs: Some(300),
+                p90_reply_secs: Some(420),
+            },
+            ResponseStats {
+                id: participant_id("2"),
+                turns: 2,
+                sessions_started: 1,
+                replies: 1,
+                median_reply_secs: Some(120),
+                p90_reply_secs: Some(120),
+            },
+        ]
+    );
+    assert!(responsiveness.participant(&participant_id("3")).is_none());
+
+    let hangouts = Hangouts {
+        conversations: vec![chat(), chat()],
+    };
+    let total = Responsiveness::for_hangouts(&hangouts, &SessionOptions::default());
+    assert_eq!(total.conversation_id, None);
+    assert_eq!(total.sessions, 6);
+    assert_eq!(total.average_session_secs, 300.0);
+    let alice = total.participant(&participant_id("1")).unwrap();
+    assert_eq!(
+        (alice.turns, alice.sessions_started, alice.replies),
+        (8, 4, 4)
+    );
+    assert_eq!(
+        (alice.median_reply_secs, alice.p90_reply_secs),
+        (Some(300), Some(420))
+    );
+
+    let empty = Responsiveness::for_hangouts(
+        &Hangouts {
+            conversations: Vec::new(),
+        },
+        &SessionOptions::default(),
+    );
+    assert_eq!(empty, Responsiveness::default());
+}
+
+#[test]
+fn saturates_call_duration() {
+    let conversation = ConversationBuilder::new("calls")
+        .participant("1", "Alice")
+        .event(EventBuilder::call_end("1", u64::MAX, &["1"]))
+        .event(EventBuilder::call_end("1", 60, &["1"]))
+        .build();
+    let stats = Stats::for_conversation(&conversation);
+    assert_eq!(stats.calls, 2);
+    assert_eq!(stats.call_duration, u64::MAX);
+}
@@ -0,0 +1,206 @@
+use std::convert::TryInto;
+
+use hangouts_rs::builder::{default_start, participant_id, ConversationBuilder, EventBuilder};
+use hangouts_rs::chrono::Duration;
+use hangouts_rs::raw::generate::{generate, GeneratorOptions};
+use hangouts_rs::raw::{EventData, HangoutEvent};
+use hangouts_rs::validate::{Issue, IssueKind, ValidationReport};
+use hangouts_rs::{Hangouts, MediaType};
+
+fn kinds(report: &ValidationReport) -> Vec<&IssueKind> {
+    report.issues.iter().map(|issue| &issue.kind).collect()
+}
+
+#[test]
+fn generated_data_is_valid() {
+    let generated = generate(&GeneratorOptions::default());
+    let mut report = ValidationReport::new(default_start() + Duration::days(3650));
+    for conversation in &generated.conversations {
+        report.check_raw(conversation);
+    }
+    let hangouts: Hangouts = generated.try_into().unwrap();
+    for conversation in &hangouts.conversations {
+        report.check(conversation);
+    }
+
+    assert!(report.is_valid(), "{}", report);
+    assert_eq!(report.conversations, 10);
+    assert_eq!(report.events, 2000);
+    assert!(hangouts.validate().is_valid());
+}
+
+#[test]
+fn flags_participant_problems() {
+    let mut conversation = ConversationBuilder::new("c")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .message("1", "hi")
+        .build();
+    conversation.current_participants.push(participant_id("3"));
+    conversation.events[0].sender = participant_id("4");
+    conversation
+        .participants
+        .get_mut(&participant_id("2"))
+        .unwrap()
+        .read_state
+        .timestamp = default_start() + Duration::days(1);
+
+    let mut report = ValidationReport::new(default_start() + Duration::hours(1));
+    report.check(&conversation);
+    assert_eq!(
+        kinds(&report),
+        [
+            &IssueKind::UnknownCurrentParticipant {
+                participant: participant_id("3"),
+            },
+            &IssueKind::FutureReadState {
+                participant: participant_id("2"),
+                timestamp: default_start() + Duration::days(1),
+            },
+            &IssueKind::UnknownSender {
+                sender: participant_id("4"),
+            },
+        ]
+    );
+    assert_eq!(report.issues[2].event_id.as_deref(), Some("event-1"));
+}
+
+#[test]
+fn flags_event_problems() {
+    let conversation = ConversationBuilder::new("c")
+        .participant("1", "Alice")
+        .event(EventBuilder::call_start("1", MediaType::AudioVideo))
+        .event(EventBuilder::call_end("1", 60, &["1"]))
+        .event(EventBuilder::call_end("1", 30, &["1"]).id("lonely"))
+        .event(EventBuilder::rename("1", "", "Friends"))
+        .event(EventBuilder::rename("1", "Family", "Pals").id("event-1"))
+        .build();
+
+    let mut report = ValidationReport::new(default_start() + Duration::days(1));
+    report.check(&conversation);
+    assert_eq!(
+        report.issues,
+        [
+            Issue {
+                conversation_id: "c".to_owned(),
+                event_id: Some("event-1".to_owned()),
+                kind: IssueKind::DuplicateEvent,
+            },
+            Issue {
+                conversation_id: "c".to_owned(),
+                event_id: Some("event-1".to_owned()),
+                kind: IssueKind::RenameMismatch {
+                    expected: "Friends".to_owned(),
+                    old: "Family".to_owned(),
+                },
+            },
+            Issue {
+                conversation_id: "c".to_owned(),
+                event_id: Some("lonely".to_owned()),
+                kind: IssueKind::CallEndWithoutStart,
+            },
+        ]
+    );
+    assert_eq!(
+        report.to_string(),
+        "1 conversations, 5 events\n\
+         c: duplicate event event-1\n\
+         c: event event-1 renames from \"Family\" but the name was \"Friends\"\n\
+         c: call ended by event lonely never started\n"
+    );
+}
+
+#[test]
+fn flags_events_of_other_conversations() {
+    let mut generated = generate(&GeneratorOptions {
+        conversations: 2,
+        events: 5,
+        ..GeneratorOptions::default()
+    });
+    let other = generated.conversations[1].header.conversation_id.clone();
+    let event = &mut generated.conversations[0].events[2];
+    event.header.conversation_id = other.clone();
+
+    let mut report = ValidationReport::new(default_start());
+    report.check_raw(&generated.conversations[0]);
+    assert_eq!(
+        kinds(&report),
+        [&IssueKind::ConversationMismatch { header: other.id }]
+    );
+    assert_eq!(report.conversations, 0);
+}
+
+#[test]
+fn flags_call_ends_after_unfinished_calls() {
+    let conversation = ConversationBuilder::new("c")
+        .participant("1", "Alice")
+        .event(EventBuilder::call_start("1", MediaType::Audio))
+        .event(
+            EventBuilder::call_end("1", 60, &["1"])
+                .id("lonely")
+                .timestamp(default_start() + Duration::hours(3)),
+        )
+        .build();
+
+    let mut report = ValidationReport::new(default_start() + Duration::days(1));
+    report.check(&conversation);
+    assert_eq!(
+        report.issues,
+        [Issue {
+            conversation_id: "c".to_owned(),
+            event_id: Some("lonely".to_owned()),
+            kind: IssueKind::CallEndWithoutStart,
+        }]
+    );
+}
+
+#[test]
+fn validates_raw_exports() {
+    let mut generated = generate(&GeneratorOptions {
+        conversations: 2,
+        events: 5,
+        ..GeneratorOptions::default()
+    });
+    let report = generated.validate().unwrap();
+    assert!(report.is_valid(), "{}", report);
+    assert_eq!(report.conversations, 2);
+    assert_eq!(report.events, 10);
+
+    let other = generated.conversations[1].header.conversation_id.clone();
+    generated.conversations[0].events[2].header.conversation_id = other.clone();
+    let report = generated.validate().unwrap();
+    assert_eq!(
+        kinds(&report),
+        [&IssueKind::ConversationMismatch { header: other.id }]
+    );
+    assert_eq!(report.conversations, 2);
+}
+
+#[test]
+fn flags_absurd_call_durations() {
+    let mut generated = generate(&GeneratorOptions {
+        conversations: 1,
+        events: 200,
+        ..GeneratorOptions::default()
+    });
+    let events = &mut generated.conversations[0].events;
+    let end = events
+        .iter_mut()
+        .find_map(|event| match &mut event.data {
+            EventData::HangoutEvent {
+                data:
+                    HangoutEvent::EndHangout {
+                        hangout_duration_secs,
+                    },
+                ..
+            } => Some((event.header.event_id.clone(), hangout_duration_secs)),
+            _ => None,
+        })
+        .unwrap();
+    *end.1 = u64::MAX.to_string();
+    let id = end.0;
+
+    let report = generated.validate().unwrap();
+    assert_eq!(kinds(&report), [&IssueKind::CallEndWithoutStart]);
+    assert_eq!(report.issues[0].event_id.as_deref(), Some(id.as_str()));
+}
@@ -0,0 +1,130 @@
+use hangouts_rs::builder::{
+    default_start, participant_id, ChatMessageBuilder, ConversationBuilder, EventBuilder,
+};
+use hangouts_rs::chrono::Duration;
+use hangouts_rs::export::vcard::{self, contacts};
+use hangouts_rs::{Conversation, Hangouts, ParticipantType, PhoneNumber};
+
+/// Set the type and phone number of participant `gaia_id` in `conversation`.
+fn phone(conversation: &mut Conversation, gaia_id: &str, e164: Option<&str>) {
+    let participant = conversation
+        .participants
+        .get_mut(&participant_id(gaia_id))
+        .unwrap();
+    participant.typ = Some(ParticipantType::OffNetworkPhone);
+    participant.phone_number = e164.map(|e164| PhoneNumber {
+        e164: Some(e164.to_owned()),
+        international: None,
+        national: None,
+        region_code: None,
+    });
+}
+
+fn hangouts() -> Hangouts {
+    let mut friends = ConversationBuilder::new("friends")
+        .name("Friends, old; and new")
+        .participant("1", "Alice")
+        .participant("2", "+1 555 0100")
+        .participant("3", "+")
+        .message("1", "Hi")
+        .event(
+            EventBuilder::message("2", ChatMessageBuilder::new().text("Hello"))
+                .timestamp(default_start() + Duration::days(40)),
+        )
+        .build();
+    phone(&mut friends, "2", None);
+    phone(&mut friends, "3", None);
+
+    let mut direct = ConversationBuilder::new("direct")
+        .participant("1", "Alice Again")
+        .participant("4", "Dan")
+        .event(EventBuilder::message(
+            "4",
+            ChatMessageBuilder::new().text("Yo"),
+        ))
+        .event(
+            EventBuilder::message("1", ChatMessageBuilder::new().text("Hey"))
+                .timestamp(default_start() + Duration::days(400)),
+        )
+        .build();
+    phone(&mut direct, "4", Some("+15550199"));
+    // A participant without a name or events.
+    let mut quiet = ConversationBuilder::new("quiet")
+        .participant("5", "x")
+        .build();
+    quiet
+        .participants
+        .get_mut(&participant_id("5"))
+        .unwrap()
+        .fallback_name = None;
+
+    Hangouts {
+        conversations: vec![friends, direct, quiet],
+    }
+}
+
+fn cards() -> Vec<String> {
+    let mut written = Vec::new();
+    vcard::write_vcards(&mut written, &hangouts()).unwrap();
+    let written = String::from_utf8(written).unwrap();
+    assert!(written.ends_with("END:VCARD\r\n"));
+    written
+        .split_inclusive("END:VCARD\r\n")
+        .map(str::to_owned)
+        .collect()
+}
+
+#[test]
+fn merges_participants() {
+    let hangouts = hangouts();
+    let contacts = contacts(&hangouts);
+    let ids: Vec<_> = contacts.iter().map(|c| c.id.gaia_id.as_str()).collect();
+    assert_eq!(ids, ["1", "2", "3", "4", "5"]);
+
+    let alice = &contacts[0];
+    assert_eq!(alice.name, Some("Alice"));
+    assert_eq!(
+        alice.conversations,
+        ["Friends, old; and new", "Alice Again, Dan"]
+    );
+    assert_eq!(alice.first_seen, Some(default_start()));
+    assert_eq!(alice.last_seen, Some(default_start() + Duration::days(400)));
+    assert_eq!(contacts[4].first_seen, None);
+
+    assert_eq!(contacts[1].tel(), Some("+1 555 0100"));
+    // A lone plus sign is not a phone number.
+    assert_eq!(contacts[2].tel(), None);
+    assert_eq!(contacts[3].tel(), Some("+15550199"));
+    assert_eq!(alice.tel(), None);
+}
+
+#[test]
+fn writes_vcards() {
+    let cards = cards();
+    assert_eq!(cards.len(), 5);
+    assert_eq!(
+        cards[0],
+        "BEGIN:VCARD\r\n\
+         VERSION:4.0\r\n\
+         KIND:individual\r\n\
+         FN:Alice\r\n\
+         UID;VALUE=text:1\r\n\
+         NOTE:Seen in Hangouts from 2015-01-01 to 2016-02-05 in 2 conversations: Fri\r\n \
+         ends\\, old\\; and new\\; Alice Again\\, Dan\r\n\
+         END:VCARD\r\n"
+    );
+    assert!(cards[1]
+        .contains("\r\nFN:+1 555 0100\r\nUID;VALUE=text:2\r\nTEL;VALUE=uri:tel:+15550100\r\n"));
+    assert!(cards[1]
+        .contains("\r\nNOTE:Seen in Hangouts from 2015-01-01 to 2015-02-10 in 1 conversation: "));
+    assert!(cards[2].contains("\r\nFN:+\r\n"));
+    assert!(!cards[2].contains("TEL"));
+    assert!(cards[3].contains("\r\nFN:Dan\r\nUID;VALUE=text:4\r\nTEL;VALUE=uri:tel:+15550199\r\n"));
+    // Without a name, the card is named after the ID.
+    assert!(cards[4].contains("\r\nFN:5\r\n"));
+    assert!(cards[4].contains("\r\nNOTE:Seen in Hangouts in 1 conversation: quiet\r\n"));
+
+    for line in cards.iter().flat_map(|card| card.split("\r\n")) {
+        assert!(line.len() <= 75, "{:?}", line);
+    }
+}
@@ -0,0 +1,186 @@
+use std::convert::TryInto;
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::thread;
+
+use hangouts_rs::raw::generate::{generate, GeneratorOptions};
+use hangouts_rs::web::{Server, Viewer, MAX_LIMIT};
+use hangouts_rs::Hangouts;
+
+use serde_json::Value;
+
+fn viewer() -> Viewer {
+    let hangouts: Hangouts = generate(&GeneratorOptions {
+        seed: 5,
+        conversations: 4,
+        participants: 6,
+        events: 250,
+        ..GeneratorOptions::default()
+    })
+    .try_into()
+    .unwrap();
+    Viewer::new(hangouts, None)
+}
+
+fn get(viewer: &Viewer, url: &str) -> (u16, Value) {
+    let response = viewer.handle("GET", url);
+    let body = serde_json::from_slice(&response.bytes().unwrap()).unwrap();
+    (response.status, body)
+}
+
+#[test]
+fn pages_through_events() {
+    let viewer = viewer();
+    let (status, conversations) = get(&viewer, "/api/conversations");
+    assert_eq!(status, 200);
+    let conversations = conversations.as_array().unwrap();
+    assert_eq!(conversations.len(), 4);
+
+    let id = conversations[0]["id"].as_str().unwrap();
+    let (_, latest) = get(&viewer, &format!("/api/conversations/{}/events", id));
+    assert_eq!(latest["total"], 250);
+    assert_eq!(latest["offset"], 150);
+    assert_eq!(latest["items"].as_array().unwrap().len(), 100);
+
+    let (_, first) = get(
+        &viewer,
+        &format!("/api/conversations/{}/events?offset=0&limit=10", id),
+    );
+    let items = first["items"].as_array().unwrap();
+    assert_eq!(items.len(), 10);
+    assert_eq!(items[0]["index"], 0);
+
+    let (status, _) = get(&viewer, "/api/conversations/nope/events");
+    assert_eq!(status, 404);
+}
+
+#[test]
+fn searches_messages() {
+    let viewer = viewer();
+    let (status, page) = get(&viewer, "/api/search?q=coffee&limit=5");
+    assert_eq!(status, 200);
+    assert!(page["total"].as_u64().unwrap() > 0);
+    for hit in page["items"].as_array().unwrap() {
+        let text: String = hit["event"]["segments"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|segment| segment["text"].as_str().unwrap())
+            .collect();
+        assert!(text.to_lowercase().contains("coffee"));
+    }
+
+    let (status, _) = get(&viewer, "/api/search?q=");
+    assert_eq!(status, 400);
+}
+
+#[test]
+fn bounds_event_pages() {
+    let viewer = viewer();
+    let (_, conversations) = get(&viewer, "/api/conversations");
+    let id = conversations[0]["id"].as_str().unwrap().to_owned();
+    let events = |query: &str| {
+        get(
+            &viewer,
+            &format!("/api/conversations/{}/events?{}", id, query),
+        )
+    };
+
+    let (status, past_end) = events("offset=1000000");
+    assert_eq!(status, 200);
+    assert_eq!(past_end["offset"], 250);
+    assert!(past_end["items"].as_array().unwrap().is_empty());
+
+    let (_, tail) = events("offset=245&limit=10");
+    assert_eq!(tail["items"].as_array().unwrap().len(), 5);
+    assert_eq!(tail["items"][4]["index"], 249);
+
+    let (_, smallest) = events("offset=0&limit=0");
+    assert_eq!(smallest["items"].as_array().unwrap().len(), 1);
+
+    let (_, largest) = events(&format!("offset=0&limit={}", usize::MAX));
+    assert_eq!(
+        largest["items"].as_array().unwrap().len(),
+        250.min(MAX_LIMIT)
+    );
+    let (_, latest) = events(&format!("limit={}", usize::MAX));
+    assert_eq!(latest["offset"], 0);
+
+    for query in [
+        "offset=-1",
+        "limit=-5",
+        "offset=ten",
+        &format!("offset={}0", usize::MAX),
+    ] {
+        let (status, _) = events(query);
+        assert_eq!(status, 400, "{}", query);
+    }
+
+    let (status, page) = get(&viewer, &format!("/api/search?q=a&offset={}", usize::MAX));
+    assert_eq!(status, 200);
+    assert!(page["items"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn rejects_unknown_routes() {
+    let viewer = viewer();
+    for url in [
+        "/media/nope",
+        "/media/..%2F..%2Fetc%2Fpasswd",
+        "/media/",
+        "/api/nope",
+    ] {
+        let (status, _) = get(&viewer, url);
+        assert_eq!(status, 404, "{}", url);
+    }
+    assert_eq!(viewer.handle("POST", "/api/conversations").status, 405);
+}
+
+fn request(server: &Server, host: &str, path: &str) -> String {
+    let mut stream = TcpStream::connect(server.addr()).unwrap();
+    write!(
+        stream,
+        "GET {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\n\r\n",
+        path, host
+    )
+    .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    response
+}
+
+#[test]
+fn serves_local_requests_only() {
+    let viewer = viewer();
+    let server = Server::bind(0).unwrap();
+
+    thread::scope(|scope| {
+        scope.spawn(|| server.run(&viewer));
+
+        let host = format!("localhost:{}", server.addr().port());
+        let index = request(&server, &host, "/");
+        assert!(index.starts_with("HTTP/1.1 200"));
+        assert!(index.contains("<title>Hangouts</title>"));
+
+        let conversations = request(&server, &host, "/api/conversations");
+        assert!(conversations.starts_with("HTTP/1.1 200"));
+        let body = &conversations[conversations.find("\r\n\r\n").unwrap() + 4..];
+        let conversations: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(conversations.as_array().unwrap().len(), 4);
+
+        for foreign in [
+            "attacker.example:80",
+            "attacker.example",
+            "localhost.attacker.example",
+            "127.0.0.1.nip.io:8080",
+            "[::1].attacker.example",
+        ] {
+            let response = request(&server, foreign, "/api/conversations");
+            assert!(response.starts_with("HTTP/1.1 403"), "{}", foreign);
+        }
+        let local = request(&server, "127.0.0.1", "/api/conversations");
+        assert!(local.starts_with("HTTP/1.1 200"));
+
+        server.unblock();
+    });
+}
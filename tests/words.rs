@@ -0,0 +1,185 @@
+use hangouts_rs::builder::{
+    default_start, participant_id, ChatMessageBuilder, ConversationBuilder, EventBuilder,
+};
+use hangouts_rs::chrono::Duration;
+use hangouts_rs::stats::{Frequencies, Language, TextOptions, Vocabulary};
+use hangouts_rs::{Conversation, Hangouts};
+
+fn conversation() -> Conversation {
+    ConversationBuilder::new("chat")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .event(EventBuilder::message(
+            "1",
+            ChatMessageBuilder::new()
+                .text("I love the pizza 🍕🍕 ")
+                .link("here", "https://www.Example.com:8080/menu?size=xl"),
+        ))
+        .message("1", "To the")
+        .event(EventBuilder::rename("2", "", "Pizza pizza"))
+        .event(
+            EventBuilder::message(
+                "2",
+                ChatMessageBuilder::new()
+                    .text("Pizza is great 🇫🇷 1️⃣")
+                    .line_break()
+                    .link(
+                        "news",
+                        "https://www.google.com/url?q=https%3A%2F%2Fnews.example.org%2Fx&sa=D",
+                    )
+                    .link("mail", "mailto:bob@example.com"),
+            )
+            .timestamp(default_start() + Duration::days(365)),
+        )
+        .build()
+}
+
+fn english() -> TextOptions {
+    TextOptions {
+        stopwords: vec![Language::English],
+        ..TextOptions::default()
+    }
+}
+
+fn sorted(frequencies: &Frequencies) -> Vec<(&str, usize)> {
+    frequencies.top(usize::MAX)
+}
+
+#[test]
+fn counts_words() {
+    let vocabulary = Vocabulary::for_conversation(&conversation(), &english());
+    let overall = &vocabulary.overall;
+
+    assert_eq!(
+        sorted(&overall.words),
+        vec![("pizza", 2), ("great", 1), ("love", 1)]
+    );
+    // "to the" is made of stopwords only.
+    assert_eq!(
+        sorted(&overall.ngrams),
+        vec![
+            ("i love", 1),
+            ("is great", 1),
+            ("love the", 1),
+            ("pizza is", 1),
+            ("the pizza", 1),
+        ]
+    );
+    assert_eq!(
+        sorted(&overall.emoji),
+        vec![("🍕", 2), ("1️⃣", 1), ("🇫🇷", 1)]
+    );
+    // Google redirects count for the domain they lead to, other schemes are skipped.
+    assert_eq!(
+        sorted(&overall.domains),
+        vec![("example.com", 1), ("news.example.org", 1)]
+    );
+
+    let alice = &vocabulary.by_participant[&participant_id("1")];
+    assert_eq!(sorted(&alice.words), vec![("love", 1), ("pizza", 1)]);
+    assert_eq!(alice.emoji.get("🍕"), 2);
+    let bob = &vocabulary.by_participant[&participant_id("2")];
+    assert_eq!(sorted(&bob.words), vec![("great", 1), ("pizza", 1)]);
+    assert_eq!(vocabulary.by_participant.len(), 2);
+
+    assert_eq!(
+        vocabulary.by_year.keys().copied().collect::<Vec<_>>(),
+        vec![2015, 2016]
+    );
+    assert_eq!(vocabulary.by_year[&2015], *alice);
+    assert_eq!(vocabulary.by_year[&2016].domains.get("news.example.org"), 1);
+}
+
+#[test]
+fn applies_options() {
+    let options = TextOptions {
+        stopwords: Vec::new(),
+        ngram_len: 1,
+    };
+    let vocabulary = Vocabulary::for_conversation(&conversation(), &options);
+    assert_eq!(vocabulary.overall.words.total(), 9);
+    assert_eq!(vocabulary.overall.words.get("the"), 2);
+    assert!(vocabulary.overall.ngrams.counts.is_empty());
+
+    let options = TextOptions {
+        stopwords: vec![Language::English],
+        ngram_len: 3,
+    };
+    let vocabulary = Vocabulary::for_conversation(&conversation(), &options);
+    assert_eq!(
+        sorted(&vocabulary.overall.ngrams),
+        vec![
+            ("i love the", 1),
+            ("love the pizza", 1),
+            ("pizza is great", 1)
+        ]
+    );
+
+    let message = ConversationBuilder::new("chat")
+        .message("1", "Der Hund und die Katze")
+        .build();
+    let options = TextOptions {
+        stopwords: vec![Language::German, Language::French],
+        ..TextOptions::default()
+    };
+    let vocabulary = Vocabulary::for_conversation(&message, &options);
+    assert_eq!(
+        sorted(&vocabulary.overall.words),
+        vec![("hund", 1), ("katze", 1)]
+    );
+}
+
+#[test]
+fn sums_conversations() {
+    let hangouts = Hangouts {
+        conversations: vec![conversation(), conversation()],
+    };
+    let vocabulary = Vocabulary::for_hangouts(&hangouts, &english());
+    assert_eq!(vocabulary.overall.words.get("pizza"), 4);
+    assert_eq!(vocabulary.overall.emoji.total(), 8);
+    assert_eq!(vocabulary.by_year[&2016].words.total(), 4);
+}
+
+#[test]
+fn counts_emoji_only() {
+    let message = ConversationBuilder::new("symbols")
+        .participant("1", "Alice")
+        .message(
+            "1",
+            "\u{2318} \u{2713} \u{2300} \u{1F030} \u{1F000} \u{1F1E6} \u{1F1E6}\u{1F1E8}\u{1F1E9} \
+             \u{00A9} \u{00A9}\u{FE0F} \u{2614} \u{2614}\u{FE0E} \u{1F004} 1\u{20E3} \
+             \u{1F44D}\u{1F3FD} \u{1F469}\u{200D}\u{1F4BB}",
+        )
+        .build();
+    let vocabulary = Vocabulary::for_conversation(&message, &TextOptions::default());
+    let mut emoji: Vec<_> = sorted(&vocabulary.overall.emoji)
+        .into_iter()
+        .map(|(emoji, _)| emoji)
+        .collect();
+    emoji.sort_unstable();
+    assert_eq!(
+        emoji,
+        [
+            "1\u{20E3}",
+            "\u{00A9}\u{FE0F}",
+            "\u{2614}",
+            "\u{1F004}",
+            "\u{1F1E6}\u{1F1E8}",
+            "\u{1F44D}\u{1F3FD}",
+            "\u{1F469}\u{200D}\u{1F4BB}",
+        ]
+    );
+}
+
+#[test]
+fn ranks_frequencies() {
+    let mut frequencies = Frequencies::default();
+    for s in ["b", "a", "c", "b", "c"] {
+        frequencies.add(s);
+    }
+    assert_eq!(frequencies.get("b"), 2);
+    assert_eq!(frequencies.get("d"), 0);
+    assert_eq!(frequencies.total(), 5);
+    assert_eq!(frequencies.top(2), vec![("b", 2), ("c", 2)]);
+    assert!(Frequencies::default().top(3).is_empty());
+}
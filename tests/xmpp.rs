@@ -0,0 +1,104 @@
+use hangouts_rs::builder::{participant_id, ChatMessageBuilder, ConversationBuilder, EventBuilder};
+use hangouts_rs::export::xmpp::{self, XmppOptions};
+use hangouts_rs::{Conversation, Hangouts, MediaType};
+
+fn direct() -> Conversation {
+    ConversationBuilder::new("direct")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .message("1", "Fish & <chips>?")
+        .event(EventBuilder::message(
+            "2",
+            ChatMessageBuilder::new()
+                .text("Sure")
+                .photo("photo-1", "https://example.com/a.jpg?x=1&y=2"),
+        ))
+        .event(EventBuilder::call_start("2", MediaType::Audio))
+        .build()
+}
+
+fn group() -> Conversation {
+    ConversationBuilder::new("group")
+        .participant("1", "Alice")
+        .participant("2", "Bob")
+        .participant("3", "Carol")
+        .message("3", "Hello all")
+        .build()
+}
+
+fn options() -> XmppOptions {
+    let mut options = XmppOptions::default();
+    options
+        .jids
+        .insert(participant_id("1"), "alice@example.org".to_owned());
+    options
+}
+
+#[test]
+fn writes_user_archive() {
+    let hangouts = Hangouts {
+        conversations: vec![direct(), group()],
+    };
+    let mut written = Vec::new();
+    xmpp::write_user_data(&mut written, &hangouts, &participant_id("1"), &options()).unwrap();
+    let written = String::from_utf8(written).unwrap();
+
+    assert!(written.starts_with(
+        "<?xml version='1.0' encoding='UTF-8'?>\n\
+         <server-data xmlns='urn:xmpp:pie:0'>\n  \
+         <host jid='example.org'>\n    \
+         <user name='alice'>\n      \
+         <archive xmlns='urn:xmpp:pie:0#mam'>\n"
+    ));
+    assert!(written.ends_with("      </archive>\n    </user>\n  </host>\n</server-data>\n"));
+
+    // Only the chat messages of the one-to-one conversation are archived.
+    assert_eq!(written.matches("<result ").count(), 2);
+    assert!(!written.contains("Hello all"));
+    assert!(written.contains(
+        "<delay xmlns='urn:xmpp:delay' stamp='2015-01-01T00:00:00.000000Z'/>\n            \
+         <message xmlns='jabber:client' from='alice@example.org' to='2@localhost' \
+         type='chat' id='event-1'>\n              \
+         <body>Fish &amp; &lt;chips&gt;?</body>"
+    ));
+    assert!(written.contains(
+        "from='2@localhost' to='alice@example.org' type='chat' id='event-2'>\n              \
+         <body>Sure\nhttps://example.com/a.jpg?x=1&amp;y=2</body>\n              \
+         <x xmlns='jabber:x:oob'><url>https://example.com/a.jpg?x=1&amp;y=2</url></x>"
+    ));
+}
+
+#[test]
+fn writes_room_archive() {
+    let mut written = Vec::new();
+    xmpp::write_muc_archive(&mut written, &group(), &options()).unwrap();
+    let written = String::from_utf8(written).unwrap();
+
+    assert!(written.starts_with(
+        "<?xml version='1.0' encoding='UTF-8'?>\n\
+         <server-data xmlns='urn:xmpp:pie:0'>\n  \
+         <host jid='conference.localhost'>\n    \
+         <user name='group'>\n      \
+         <archive xmlns='urn:xmpp:pie:0#mam'>\n"
+    ));
+    assert!(written.ends_with("      </archive>\n    </user>\n  </host>\n</server-data>\n"));
+    assert!(written.contains(
+        "<message xmlns='jabber:client' from='group@conference.localhost/Carol' \
+         to='group@conference.localhost' type='groupchat' id='event-1'>"
+    ));
+    assert!(written
+        .contains("<x xmlns='http://jabber.org/protocol/muc#user'><item jid='3@localhost'/></x>"));
+}
+
+#[test]
+fn escapes_room_localparts() {
+    let options = XmppOptions::default();
+    let room = |id: &str| options.room_jid(&ConversationBuilder::new(id).build());
+
+    assert_eq!(room("ugx-1.a_b"), "ugx-1.a_b@conference.localhost");
+    assert_eq!(room("UgxAB"), r"\55gx\41\42@conference.localhost");
+    assert_ne!(room("UgxAB"), room("ugxab"));
+    assert_eq!(room("a b@c/d"), r"a\20b\40c\2fd@conference.localhost");
+    assert_eq!(room("\\41"), r"\5c41@conference.localhost");
+    assert_eq!(room("é"), r"\c3\a9@conference.localhost");
+}